@@ -0,0 +1,13 @@
+#!/usr/bin/env bash
+
+# Runs the library unit tests (drivers on fake devices) natively on the host.
+# Cargo reads .cargo/config.toml from the working directory, so running from outside of the
+# repository drops the kernel target and build-std settings.
+
+set -eo pipefail # Cause script to exit on error
+
+root="$(cd "$(dirname "$0")" && pwd)"
+host="$(rustc +nightly -vV | sed -n 's/^host: //p')"
+
+cd /
+cargo +nightly test --manifest-path "$root/Cargo.toml" --target "$host" --lib "$@"
@@ -2,4 +2,5 @@
 
 qemu-system-i386 -serial stdio -device isa-debug-exit,iobase=0xf4,iosize=0x04 -kernel "$1"
 
+# The debug exit device makes QEMU exit with (code << 1) | 1, see qemu::ExitCode
 exit $(($? >> 1))
@@ -0,0 +1,245 @@
+use core::cell::RefCell;
+use thiserror_no_std::Error;
+
+use crate::boot_info::Rsdp;
+use crate::io::port_manager::{Port, PortManager};
+
+const RSDP_SIGNATURE: &[u8; 8] = b"RSD PTR ";
+const SDT_HEADER_SIZE: usize = 36;
+
+// Where the BIOS may have put the RSDP, on 16 byte boundaries
+const EBDA_SEGMENT_POINTER: usize = 0x40E;
+const BIOS_AREA: (usize, usize) = (0xE0000, 0x100000);
+
+// Field offsets in the FADT
+const FADT_DSDT: usize = 40;
+const FADT_SMI_COMMAND: usize = 48;
+const FADT_ACPI_ENABLE: usize = 52;
+const FADT_PM1A_CONTROL: usize = 64;
+const FADT_PM1B_CONTROL: usize = 68;
+
+// PM1 control register
+const SCI_ENABLE: u16 = 1 << 0;
+const SLEEP_ENABLE: u16 = 1 << 13;
+const SLEEP_TYPE_SHIFT: u16 = 10;
+
+// AML opcodes found in the \_S5 package
+const AML_NAME_OP: u8 = 0x08;
+const AML_PACKAGE_OP: u8 = 0x12;
+const AML_BYTE_PREFIX: u8 = 0x0A;
+const AML_ZERO_OP: u8 = 0x00;
+const AML_ONE_OP: u8 = 0x01;
+
+static POWER_OFF: PowerOffCell = PowerOffCell {
+    inner: RefCell::new(None),
+};
+
+struct PowerOffCell {
+    inner: RefCell<Option<PowerOff>>,
+}
+
+unsafe impl Sync for PowerOffCell {}
+
+#[derive(Debug, Error)]
+pub enum AcpiError {
+    #[error("No RSDP found")]
+    NoRsdp,
+    #[error("Invalid checksum of table {0:?}")]
+    InvalidChecksum([u8; 4]),
+    #[error("No FADT found")]
+    NoFadt,
+    #[error("No \\_S5 sleep state in the DSDT")]
+    NoSoftOff,
+    #[error("PM1 control ports unavailable")]
+    PortsReserved,
+}
+
+/// Values to enter the S5 soft-off sleep state.
+#[derive(Debug, Clone, Copy)]
+struct PowerOff {
+    pm1a_control: u16,
+    pm1b_control: u16,
+    sleep_type_a: u16,
+    sleep_type_b: u16,
+    smi_command: u16,
+    acpi_enable: u8,
+}
+
+/// Prepares `power_off` from the FADT and DSDT.
+///
+/// The RSDP the boot loader passed is used, otherwise the BIOS areas are searched for it.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn init(rsdp: Option<&Rsdp>, port_manager: &PortManager) -> Result<(), AcpiError> {
+    use AcpiError::*;
+
+    let rsdp = rsdp
+        .map(|rsdp| rsdp.addr() as usize)
+        .or_else(|| find_rsdp())
+        .ok_or(NoRsdp)?;
+
+    let fadt = find_table(rsdp, b"FACP")?.ok_or(NoFadt)?;
+    let dsdt = read_u32(fadt, FADT_DSDT) as usize;
+    let dsdt = table(dsdt).ok_or(InvalidChecksum(*b"DSDT"))?;
+    let (sleep_type_a, sleep_type_b) = parse_soft_off(&dsdt[SDT_HEADER_SIZE..]).ok_or(NoSoftOff)?;
+
+    let power_off = PowerOff {
+        pm1a_control: read_u32(fadt, FADT_PM1A_CONTROL) as u16,
+        pm1b_control: read_u32(fadt, FADT_PM1B_CONTROL) as u16,
+        sleep_type_a: sleep_type_a as u16,
+        sleep_type_b: sleep_type_b as u16,
+        smi_command: read_u32(fadt, FADT_SMI_COMMAND) as u16,
+        acpi_enable: fadt[FADT_ACPI_ENABLE],
+    };
+
+    for control in [power_off.pm1a_control, power_off.pm1b_control] {
+        if control != 0 {
+            port_manager
+                .request_range(control, 2, "acpi pm1 control")
+                .map_err(|_| PortsReserved)?
+                .leak();
+        }
+    }
+
+    *POWER_OFF.inner.borrow_mut() = Some(power_off);
+
+    Ok(())
+}
+
+/// Enters the S5 soft-off state, returns if ACPI is unavailable or the request was ignored.
+pub fn power_off() {
+    let Some(power_off) = POWER_OFF.inner.try_borrow().ok().and_then(|inner| *inner) else {
+        return;
+    };
+
+    let pm1a_control = Port::<u16>::new(power_off.pm1a_control);
+
+    // Firmware still owns power management until ACPI mode is entered through the SMI port
+    if pm1a_control.read() & SCI_ENABLE == 0 && power_off.smi_command != 0 {
+        Port::<u8>::new(power_off.smi_command).write(power_off.acpi_enable);
+        for _ in 0..1_000_000 {
+            if pm1a_control.read() & SCI_ENABLE != 0 {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    pm1a_control.write(power_off.sleep_type_a << SLEEP_TYPE_SHIFT | SLEEP_ENABLE);
+    if power_off.pm1b_control != 0 {
+        Port::<u16>::new(power_off.pm1b_control)
+            .write(power_off.sleep_type_b << SLEEP_TYPE_SHIFT | SLEEP_ENABLE);
+    }
+}
+
+/// Extracts SLP_TYPa and SLP_TYPb from the `Name(\_S5, Package() {...})` of the DSDT AML.
+pub fn parse_soft_off(aml: &[u8]) -> Option<(u8, u8)> {
+    let position = aml.windows(4).position(|window| window == b"_S5_")?;
+
+    // Either `NameOp _S5_` or `NameOp \_S5_`
+    let name_op = match position {
+        1.. if aml[position - 1] == AML_NAME_OP => true,
+        2.. => aml[position - 1] == b'\\' && aml[position - 2] == AML_NAME_OP,
+        _ => false,
+    };
+    if !name_op {
+        return None;
+    }
+
+    let mut rest = aml.get(position + 4..)?;
+    if *rest.first()? != AML_PACKAGE_OP {
+        return None;
+    }
+
+    // The top two bits of the first PkgLength byte count the bytes following it
+    let pkg_length_bytes = 1 + (rest.get(1)? >> 6) as usize;
+    rest = rest.get(1 + pkg_length_bytes + 1..)?; // PackageOp, PkgLength and NumElements
+
+    let mut element = || -> Option<u8> {
+        let (value, len) = match *rest.first()? {
+            AML_BYTE_PREFIX => (*rest.get(1)?, 2),
+            AML_ZERO_OP => (0, 1),
+            AML_ONE_OP => (1, 1),
+            _ => return None,
+        };
+        rest = &rest[len..];
+
+        Some(value)
+    };
+
+    Some((element()?, element()?))
+}
+
+/// Validates the checksum of the table at `addr` and returns it as bytes.
+unsafe fn table(addr: usize) -> Option<&'static [u8]> {
+    if addr == 0 {
+        return None;
+    }
+
+    let len = read_u32(core::slice::from_raw_parts(addr as *const u8, 8), 4) as usize;
+    let table = core::slice::from_raw_parts(addr as *const u8, len);
+
+    (len >= SDT_HEADER_SIZE && checksum(table) == 0).then_some(table)
+}
+
+// Walks the XSDT if the RSDP has one, the RSDT otherwise
+unsafe fn find_table(rsdp: usize, signature: &[u8; 4]) -> Result<Option<&'static [u8]>, AcpiError> {
+    let rsdp_bytes = core::slice::from_raw_parts(rsdp as *const u8, 36);
+    if checksum(&rsdp_bytes[..20]) != 0 {
+        return Err(AcpiError::InvalidChecksum(*b"RSD "));
+    }
+
+    let revision = rsdp_bytes[15];
+    let xsdt = read_u64(rsdp_bytes, 24);
+    let (root, entry_size) = if revision >= 2 && xsdt != 0 && xsdt <= u32::MAX as u64 {
+        (xsdt as usize, 8)
+    } else {
+        (read_u32(rsdp_bytes, 16) as usize, 4)
+    };
+
+    let root = table(root).ok_or(AcpiError::InvalidChecksum(*b"RSDT"))?;
+    for entry in root[SDT_HEADER_SIZE..].chunks_exact(entry_size) {
+        let addr = match entry_size {
+            8 => read_u64(entry, 0),
+            _ => read_u32(entry, 0) as u64,
+        };
+        if addr == 0 || addr > u32::MAX as u64 {
+            continue;
+        }
+
+        let header = core::slice::from_raw_parts(addr as usize as *const u8, 4);
+        if header == signature {
+            return table(addr as usize)
+                .map(Some)
+                .ok_or(AcpiError::InvalidChecksum(*signature));
+        }
+    }
+
+    Ok(None)
+}
+
+// The first KiB of the EBDA, then the BIOS read-only area
+unsafe fn find_rsdp() -> Option<usize> {
+    let ebda = ((EBDA_SEGMENT_POINTER as *const u16).read_volatile() as usize) << 4;
+    let areas = [(ebda, ebda + 1024), BIOS_AREA];
+
+    areas
+        .into_iter()
+        .filter(|&(start, _)| start != 0)
+        .flat_map(|(start, end)| (start..end).step_by(16))
+        .find(|&addr| {
+            let candidate = core::slice::from_raw_parts(addr as *const u8, 20);
+            candidate.starts_with(RSDP_SIGNATURE) && checksum(candidate) == 0
+        })
+}
+
+pub fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
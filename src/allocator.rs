@@ -1,12 +1,19 @@
 use core::{
     alloc::GlobalAlloc,
-    ptr::{addr_of, addr_of_mut},
     sync::atomic::{AtomicPtr, Ordering},
 };
+use log::{info, trace};
 
-use crate::{multiboot::MultibootInfo, println};
+use crate::{
+    memory_map::{MemoryMap, MemoryRegionKind},
+    println,
+};
+
+const HEAP_MIN_ADDR: u64 = 0x100000; // 1M
+const HEAP_MAX_ADDR: u64 = 0xFFFF_FFF0;
 
-#[global_allocator]
+// Host tests run on the std allocator
+#[cfg_attr(not(test), global_allocator)]
 pub static ALLOC: Allocator = Allocator::new();
 
 #[repr(C, packed)]
@@ -56,6 +63,13 @@ impl UsedSegment {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocatorStats {
+    pub free_bytes: usize,
+    pub free_segments: usize,
+    pub largest_free_segment: usize,
+}
+
 pub struct Allocator {
     pub first_free: AtomicPtr<FreeSegment>,
 }
@@ -69,33 +83,58 @@ impl Allocator {
     }
 
     #[allow(clippy::missing_safety_doc)]
-    pub unsafe fn init(&self, info: &MultibootInfo) {
+    pub unsafe fn init(&self, memory_map: &MemoryMap) {
         assert_eq!(
             core::mem::size_of::<UsedSegment>(),
             core::mem::size_of::<FreeSegment>()
         );
-        let kernel_start_addr = addr_of!(crate::libc::KERNEL_START) as u64;
-        let kernel_end_addr = addr_of!(crate::libc::KERNEL_END) as u64;
-        let big_block = info
-            .get_memory_map()
+
+        // Regions come sorted, so the free list is sorted by address as well
+        let mut last_segment: *mut FreeSegment = core::ptr::null_mut();
+        for region in memory_map
+            .regions()
             .iter()
-            .find(
-                |entry| entry.addr == kernel_start_addr - 1024 * 1024, // Kernel start at 2M due to UEFI
-            )
-            .expect("Failed to find big block of ram");
-
-        // Kernel is in big block
-        let reserved_memory = kernel_end_addr - big_block.addr;
-        let segment_size =
-            (big_block.len - reserved_memory) as usize - core::mem::size_of::<FreeSegment>();
-
-        let segment = addr_of_mut!(crate::libc::KERNEL_END) as *mut FreeSegment;
-        *segment = FreeSegment {
-            size: segment_size,
-            next_segment: core::ptr::null_mut(),
-        };
-        self.first_free.store(segment, Ordering::Relaxed);
-        println!("Allocator Initialized");
+            .filter(|region| region.kind == MemoryRegionKind::Available)
+        {
+            // Low memory holds address 0 and the BIOS data, the heap must be addressable in 32 bits
+            let start = region.addr.max(HEAP_MIN_ADDR).next_multiple_of(16);
+            let end = (region.addr + region.len).min(HEAP_MAX_ADDR);
+            if end <= start + 2 * core::mem::size_of::<FreeSegment>() as u64 {
+                continue;
+            }
+
+            let segment = start as usize as *mut FreeSegment;
+            *segment = FreeSegment {
+                size: (end - start) as usize - core::mem::size_of::<FreeSegment>(),
+                next_segment: core::ptr::null_mut(),
+            };
+
+            if last_segment.is_null() {
+                self.first_free.store(segment, Ordering::Relaxed);
+            } else {
+                (*last_segment).next_segment = segment;
+            }
+            last_segment = segment;
+        }
+
+        assert!(!last_segment.is_null(), "Failed to find available memory");
+        info!("Allocator Initialized");
+    }
+
+    /// Walks the free list, sizes exclude the segment headers.
+    pub fn stats(&self) -> AllocatorStats {
+        let mut stats = AllocatorStats::default();
+
+        let mut segment = self.first_free.load(Ordering::Relaxed);
+        while !segment.is_null() {
+            let size = unsafe { (*segment).size };
+            stats.free_bytes += size;
+            stats.free_segments += 1;
+            stats.largest_free_segment = stats.largest_free_segment.max(size);
+            segment = unsafe { (*segment).next_segment };
+        }
+
+        stats
     }
 }
 
@@ -107,7 +146,7 @@ unsafe fn get_header_ptr(segment: &FreeSegment, layout: &core::alloc::Layout) ->
     ptr = ptr.sub(core::mem::size_of::<UsedSegment>());
 
     if ptr < segment_start {
-        println!("Segment size too small");
+        trace!("Segment size too small");
         return None;
     }
 
@@ -0,0 +1,122 @@
+use core::arch::asm;
+use core::fmt;
+use core::ptr::addr_of;
+
+use crate::libc::{STACK_BOTTOM, STACK_TOP};
+use crate::symbols::Symbolized;
+
+pub const MAX_FRAMES: usize = 32;
+
+// Layout of a frame built by `push %ebp; mov %esp, %ebp`
+#[repr(C)]
+struct Frame {
+    previous: u32,
+    return_address: u32,
+}
+
+/// Return addresses collected by walking the EBP chain of the kernel stack.
+pub struct Backtrace {
+    frames: [u32; MAX_FRAMES],
+    len: usize,
+}
+
+impl Backtrace {
+    /// Captures the backtrace of the caller.
+    #[inline(always)]
+    pub fn capture() -> Backtrace {
+        let mut backtrace = Backtrace::empty();
+        backtrace.walk(read_ebp());
+        backtrace
+    }
+
+    /// Captures the backtrace of the code interrupted at `eip`.
+    ///
+    /// Has to be called directly from the interrupt handler, whose saved frame pointer is the one
+    /// of the interrupted function.
+    #[inline(always)]
+    pub fn capture_interrupted(eip: u32) -> Backtrace {
+        let mut backtrace = Backtrace::empty();
+        backtrace.push(eip);
+
+        let ebp = read_ebp();
+        if is_valid_frame(ebp) {
+            backtrace.walk(unsafe { (*(ebp as *const Frame)).previous });
+        }
+
+        backtrace
+    }
+
+    fn empty() -> Backtrace {
+        Backtrace {
+            frames: [0; MAX_FRAMES],
+            len: 0,
+        }
+    }
+
+    pub fn frames(&self) -> &[u32] {
+        &self.frames[..self.len]
+    }
+
+    fn push(&mut self, addr: u32) {
+        if self.len < MAX_FRAMES {
+            self.frames[self.len] = addr;
+            self.len += 1;
+        }
+    }
+
+    // Frames live on the kernel stack and older frames are always further up, anything else
+    // means the chain is corrupted or ended
+    fn walk(&mut self, mut ebp: u32) {
+        while self.len < MAX_FRAMES && is_valid_frame(ebp) {
+            let frame = unsafe { &*(ebp as *const Frame) };
+            if frame.return_address == 0 {
+                break;
+            }
+
+            self.push(frame.return_address);
+
+            if frame.previous <= ebp {
+                break;
+            }
+            ebp = frame.previous;
+        }
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Backtrace:")?;
+        for (index, &addr) in self.frames().iter().enumerate() {
+            writeln!(f, "  #{:<2} {}", index, Symbolized(addr))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the bounds of the kernel stack from boot.s.
+#[allow(unused_unsafe)]
+pub fn stack_range() -> (u32, u32) {
+    unsafe { (addr_of!(STACK_BOTTOM) as u32, addr_of!(STACK_TOP) as u32) }
+}
+
+fn is_valid_frame(ebp: u32) -> bool {
+    let (bottom, top) = stack_range();
+
+    ebp & 0x3 == 0 && ebp >= bottom && ebp <= top - core::mem::size_of::<Frame>() as u32
+}
+
+#[inline(always)]
+fn read_ebp() -> u32 {
+    let ebp: u32;
+    unsafe {
+        asm!(r#"
+            mov %ebp, {ebp}
+            "#,
+            ebp = out(reg) ebp,
+            options(att_syntax, nomem, nostack, preserves_flags),
+        );
+    }
+
+    ebp
+}
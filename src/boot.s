@@ -5,6 +5,11 @@
 .set MAGIC,    0x1BADB002       /* 'magic number' lets bootloader find the header */
 .set CHECKSUM, -(MAGIC + FLAGS) /* checksum of above, to prove we are multiboot */
 
+/*
+No video mode is requested (flag bit 2), so Multiboot1 boots stay in VGA text
+mode. The graphical framebuffer console needs `multiboot2` in grub.cfg.
+*/
+
 /* 
 Declare a multiboot header that marks the program as a kernel. These are magic
 values that are documented in the multiboot standard. The bootloader will
@@ -18,6 +23,53 @@ forced to be within the first 8 KiB of the kernel file.
 .long FLAGS
 .long CHECKSUM
 
+/* Declare constants for the multiboot2 header. */
+.set MB2_MAGIC,        0xE85250D6 /* 'magic number' for multiboot2 */
+.set MB2_ARCH,         0          /* 32-bit (protected) mode of i386 */
+.set MB2_TAG_OPTIONAL, 1          /* boot loader may ignore the tag */
+
+/*
+Declare a multiboot2 header so multiboot2 loaders (`multiboot2` in grub.cfg)
+can boot the kernel as well. It has to be 8 byte aligned and within the first
+32 KiB of the kernel file. Every tag is 8 byte aligned and the header ends with
+an end tag.
+*/
+.section .multiboot2
+.align 8
+mb2_header_start:
+.long MB2_MAGIC
+.long MB2_ARCH
+.long mb2_header_end - mb2_header_start
+.long 0x100000000 - (MB2_MAGIC + MB2_ARCH + (mb2_header_end - mb2_header_start))
+
+/*
+Information request tag, asks for the command line, boot loader name, modules,
+memory map, framebuffer, ELF sections and the old/new ACPI RSDP.
+*/
+.align 8
+mb2_info_request_start:
+.short 1
+.short MB2_TAG_OPTIONAL
+.long mb2_info_request_end - mb2_info_request_start
+.long 1, 2, 3, 6, 8, 9, 14, 15
+mb2_info_request_end:
+
+/* Framebuffer tag, linear framebuffer 1024x768, 32 bpp */
+.align 8
+.short 5
+.short MB2_TAG_OPTIONAL
+.long 20
+.long 1024
+.long 768
+.long 32
+
+/* End tag */
+.align 8
+.short 0
+.short 0
+.long 8
+mb2_header_end:
+
 /*
 The multiboot standard does not define the value of the stack pointer register
 (esp) and it is up to the kernel to provide a stack. This allocates room for a
@@ -32,6 +84,8 @@ undefined behavior.
 */
 .section .bss
 .align 16
+.global stack_bottom
+.global stack_top
 stack_bottom:
 .skip 16380 # 16 KiB - 4 bytes
 stack_top:
@@ -85,6 +139,13 @@ _start:
 	push %ebx
 	push %eax
 
+	/*
+	Clear the frame pointer so the backtrace stops at kernel_main, the
+	kernel is built with frame pointers and kernel_main pushes this value
+	as the caller's frame.
+	*/
+	xor %ebp, %ebp
+
 	/*
 	Enter the high-level kernel. The ABI requires the stack is 16-byte
 	aligned at the time of the call instruction (which afterwards pushes
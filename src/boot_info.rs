@@ -0,0 +1,362 @@
+use thiserror_no_std::Error;
+
+use crate::memory_map::{MemoryMap, MemoryRegionKind};
+use crate::multiboot::{MultibootInfo, MULTIBOOT_BOOTLOADER_MAGIC};
+use crate::multiboot2::{self, Tag, MULTIBOOT2_BOOTLOADER_MAGIC};
+use crate::println;
+
+// Capacity of the fixed size tables, the boot info is parsed before the allocator exists
+pub const MAX_MEMORY_REGIONS: usize = 64;
+pub const MAX_MODULES: usize = 16;
+pub const MAX_INFO_RANGES: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum BootInfoError {
+    #[error("Unknown boot loader magic {0:#x}")]
+    UnknownMagic(u32),
+    #[error("Boot information pointer is null")]
+    NullInfo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BootProtocol {
+    Multiboot,
+    Multiboot2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryRegion {
+    pub addr: u64,
+    pub len: u64,
+    pub kind: MemoryRegionKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BootModule {
+    pub start: u32,
+    pub end: u32,
+    pub cmdline: &'static str,
+}
+
+// Location of the kernel ELF section header table
+#[derive(Debug, Clone, Copy)]
+pub struct ElfSections {
+    pub num: u32,
+    pub entsize: u32,
+    pub shndx: u32,
+    pub addr: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FramebufferKind {
+    Indexed {
+        palette_addr: u32,
+        num_colors: u16,
+    },
+    Rgb {
+        red_position: u8,
+        red_size: u8,
+        green_position: u8,
+        green_size: u8,
+        blue_position: u8,
+        blue_size: u8,
+    },
+    EgaText,
+    Unknown(u8),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FramebufferInfo {
+    pub addr: u64,
+    pub pitch: u32,
+    pub width: u32,
+    pub height: u32,
+    pub bpp: u8,
+    pub kind: FramebufferKind,
+}
+
+// Address of the RSDP copy made by the boot loader
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rsdp {
+    V1(u32),
+    V2(u32),
+}
+
+impl Rsdp {
+    pub fn addr(&self) -> u32 {
+        match self {
+            Rsdp::V1(addr) | Rsdp::V2(addr) => *addr,
+        }
+    }
+}
+
+/// Boot loader independent view of the information handed over at boot.
+pub struct BootInfo {
+    pub protocol: BootProtocol,
+    pub cmdline: Option<&'static str>,
+    pub boot_loader_name: Option<&'static str>,
+    pub elf_sections: Option<ElfSections>,
+    pub framebuffer: Option<FramebufferInfo>,
+    pub rsdp: Option<Rsdp>,
+    memory_regions: [MemoryRegion; MAX_MEMORY_REGIONS],
+    memory_regions_len: usize,
+    modules: [BootModule; MAX_MODULES],
+    modules_len: usize,
+    // Memory holding the boot information structures, as [start, end)
+    info_ranges: [(u64, u64); MAX_INFO_RANGES],
+    info_ranges_len: usize,
+}
+
+impl BootInfo {
+    /// Parses the boot information, `magic` decides which protocol `info` follows.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn new(magic: u32, info: *const u8) -> Result<BootInfo, BootInfoError> {
+        if info.is_null() {
+            return Err(BootInfoError::NullInfo);
+        }
+
+        match magic {
+            MULTIBOOT_BOOTLOADER_MAGIC => {
+                Ok(BootInfo::from_multiboot(&*(info as *const MultibootInfo)))
+            }
+            MULTIBOOT2_BOOTLOADER_MAGIC => Ok(BootInfo::from_multiboot2(info)),
+            _ => Err(BootInfoError::UnknownMagic(magic)),
+        }
+    }
+
+    fn empty(protocol: BootProtocol) -> BootInfo {
+        BootInfo {
+            protocol,
+            cmdline: None,
+            boot_loader_name: None,
+            elf_sections: None,
+            framebuffer: None,
+            rsdp: None,
+            memory_regions: [MemoryRegion {
+                addr: 0,
+                len: 0,
+                kind: MemoryRegionKind::Reserved,
+            }; MAX_MEMORY_REGIONS],
+            memory_regions_len: 0,
+            modules: [BootModule {
+                start: 0,
+                end: 0,
+                cmdline: "",
+            }; MAX_MODULES],
+            modules_len: 0,
+            info_ranges: [(0, 0); MAX_INFO_RANGES],
+            info_ranges_len: 0,
+        }
+    }
+
+    unsafe fn from_multiboot(info: &MultibootInfo) -> BootInfo {
+        let mut boot_info = BootInfo::empty(BootProtocol::Multiboot);
+        boot_info.cmdline = info.get_cmdline();
+        boot_info.boot_loader_name = info.get_boot_loader_name();
+
+        for range in info.get_info_ranges() {
+            boot_info.push_info_range(range);
+        }
+
+        for entry in info.get_memory_map() {
+            boot_info.push_memory_region(MemoryRegion {
+                addr: entry.addr,
+                len: entry.len,
+                kind: entry.kind(),
+            });
+        }
+
+        for module in info.get_modules() {
+            boot_info.push_module(BootModule {
+                start: module.mod_start,
+                end: module.mod_end,
+                cmdline: crate::util::c_str::from_raw(module.string as *const u8),
+            });
+        }
+
+        boot_info.elf_sections = info.get_elf_sections().map(|sections| ElfSections {
+            num: sections.num,
+            entsize: sections.size,
+            shndx: sections.shndx,
+            addr: sections.addr,
+        });
+
+        boot_info.framebuffer = info.get_framebuffer().map(|framebuffer| {
+            let color_info = framebuffer.color_info;
+            let kind = match framebuffer.type_ {
+                0 => FramebufferKind::Indexed {
+                    palette_addr: u32::from_le_bytes([
+                        color_info[0],
+                        color_info[1],
+                        color_info[2],
+                        color_info[3],
+                    ]),
+                    num_colors: u16::from_le_bytes([color_info[4], color_info[5]]),
+                },
+                type_ => framebuffer_kind(type_, color_info.as_ptr()),
+            };
+
+            FramebufferInfo {
+                addr: framebuffer.addr,
+                pitch: framebuffer.pitch,
+                width: framebuffer.width,
+                height: framebuffer.height,
+                bpp: framebuffer.bpp,
+                kind,
+            }
+        });
+
+        boot_info
+    }
+
+    unsafe fn from_multiboot2(info: *const u8) -> BootInfo {
+        let mut boot_info = BootInfo::empty(BootProtocol::Multiboot2);
+        boot_info.push_info_range((
+            info as u64,
+            info as u64 + multiboot2::total_size(info) as u64,
+        ));
+
+        for tag in multiboot2::tags(info) {
+            match tag {
+                Tag::CommandLine(cmdline) => boot_info.cmdline = Some(cmdline),
+                Tag::BootLoaderName(name) => boot_info.boot_loader_name = Some(name),
+                Tag::Module(module) => boot_info.push_module(BootModule {
+                    start: module.start,
+                    end: module.end,
+                    cmdline: module.cmdline,
+                }),
+                Tag::MemoryMap(memory_map) => {
+                    for entry in memory_map.iter() {
+                        boot_info.push_memory_region(MemoryRegion {
+                            addr: entry.addr,
+                            len: entry.len,
+                            kind: MemoryRegionKind::from_multiboot(entry.type_),
+                        });
+                    }
+                }
+                Tag::Framebuffer(framebuffer) => {
+                    let kind = match framebuffer.type_ {
+                        // The palette follows the number of colors directly
+                        0 => FramebufferKind::Indexed {
+                            palette_addr: framebuffer.color_info.add(2) as u32,
+                            num_colors: core::ptr::read_unaligned(
+                                framebuffer.color_info as *const u16,
+                            ),
+                        },
+                        type_ => framebuffer_kind(type_, framebuffer.color_info),
+                    };
+
+                    boot_info.framebuffer = Some(FramebufferInfo {
+                        addr: framebuffer.addr,
+                        pitch: framebuffer.pitch,
+                        width: framebuffer.width,
+                        height: framebuffer.height,
+                        bpp: framebuffer.bpp,
+                        kind,
+                    });
+                }
+                Tag::ElfSections(sections) => {
+                    boot_info.elf_sections = Some(ElfSections {
+                        num: sections.num,
+                        entsize: sections.entsize,
+                        shndx: sections.shndx,
+                        addr: sections.addr,
+                    })
+                }
+                // Prefer the ACPI 2.0 RSDP if the boot loader passes both
+                Tag::AcpiOld(addr) => {
+                    if boot_info.rsdp.is_none() {
+                        boot_info.rsdp = Some(Rsdp::V1(addr));
+                    }
+                }
+                Tag::AcpiNew(addr) => boot_info.rsdp = Some(Rsdp::V2(addr)),
+                Tag::Unknown(_) => {}
+            }
+        }
+
+        boot_info
+    }
+
+    pub fn memory_map(&self) -> &[MemoryRegion] {
+        &self.memory_regions[..self.memory_regions_len]
+    }
+
+    pub fn modules(&self) -> &[BootModule] {
+        &self.modules[..self.modules_len]
+    }
+
+    pub fn info_ranges(&self) -> &[(u64, u64)] {
+        &self.info_ranges[..self.info_ranges_len]
+    }
+
+    // Entries past the capacity are dropped
+    fn push_memory_region(&mut self, region: MemoryRegion) {
+        if self.memory_regions_len < MAX_MEMORY_REGIONS {
+            self.memory_regions[self.memory_regions_len] = region;
+            self.memory_regions_len += 1;
+        }
+    }
+
+    fn push_info_range(&mut self, (start, end): (u64, u64)) {
+        if start < end && self.info_ranges_len < MAX_INFO_RANGES {
+            self.info_ranges[self.info_ranges_len] = (start, end);
+            self.info_ranges_len += 1;
+        }
+    }
+
+    fn push_module(&mut self, module: BootModule) {
+        if self.modules_len < MAX_MODULES {
+            self.modules[self.modules_len] = module;
+            self.modules_len += 1;
+        }
+    }
+}
+
+// RGB and EGA text framebuffers share the same color info layout in both protocols
+unsafe fn framebuffer_kind(type_: u8, color_info: *const u8) -> FramebufferKind {
+    match type_ {
+        1 => FramebufferKind::Rgb {
+            red_position: *color_info,
+            red_size: *color_info.add(1),
+            green_position: *color_info.add(2),
+            green_size: *color_info.add(3),
+            blue_position: *color_info.add(4),
+            blue_size: *color_info.add(5),
+        },
+        2 => FramebufferKind::EgaText,
+        unknown => FramebufferKind::Unknown(unknown),
+    }
+}
+
+pub fn print_mmap_sections(boot_info: &BootInfo, memory_map: &MemoryMap) {
+    println!("Boot protocol: {:?}", boot_info.protocol);
+    if let Some(boot_loader_name) = boot_info.boot_loader_name {
+        println!("Boot Loader name: {}", boot_loader_name);
+    }
+
+    println!("Boot loader memory segments");
+    println!("mmap_length: {}", boot_info.memory_map().len());
+    for memory in boot_info.memory_map() {
+        let len = memory.len;
+        let addr = memory.addr;
+        let kind = memory.kind;
+
+        println!("len: {len}, addr: {addr:#0X}, type : {kind}");
+    }
+
+    println!("Sanitized memory segments");
+    for memory in memory_map.regions() {
+        let end = memory.addr + memory.len;
+
+        println!("[{:#010X} - {:#010X}) {}", memory.addr, end, memory.kind);
+    }
+
+    for kind in MemoryRegionKind::ALL {
+        println!(
+            "Total {} Memory: {}MB",
+            kind,
+            (memory_map.total(kind) as f32) / 1024.0 / 1024.0
+        );
+    }
+    println!("End of memory segments.");
+}
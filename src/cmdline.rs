@@ -0,0 +1,23 @@
+/// Iterates over the whitespace separated `key=value` and bare `flag` options of the kernel
+/// command line.
+pub fn options(cmdline: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    cmdline
+        .split_ascii_whitespace()
+        .map(|option| match option.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (option, None),
+        })
+}
+
+/// Returns the value of the last `key=value` option, later options override earlier ones.
+pub fn get<'a>(cmdline: &'a str, key: &str) -> Option<&'a str> {
+    options(cmdline)
+        .filter(|&(option, _)| option == key)
+        .filter_map(|(_, value)| value)
+        .last()
+}
+
+/// Returns true if `key` is given, with or without a value.
+pub fn has(cmdline: &str, key: &str) -> bool {
+    options(cmdline).any(|(option, _)| option == key)
+}
@@ -0,0 +1,234 @@
+use core::cell::RefCell;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use log::{Level, LevelFilter};
+
+pub const ENTRY_COUNT: usize = 256;
+// Longer messages are cut short
+pub const MESSAGE_LEN: usize = 160;
+
+/// Kernel log since boot, filled by the logger whether or not there is a display.
+pub static DMESG: LogBuffer<ENTRY_COUNT> = LogBuffer::new();
+
+/// Message kept in a [`LogBuffer`].
+#[derive(Clone, Copy)]
+pub struct Entry {
+    pub sequence: u32,
+    pub level: Level,
+    pub uptime_ms: u32,
+    len: usize,
+    truncated: bool,
+    text: [u8; MESSAGE_LEN],
+}
+
+impl Entry {
+    const EMPTY: Entry = Entry {
+        sequence: 0,
+        level: Level::Trace,
+        uptime_ms: 0,
+        len: 0,
+        truncated: false,
+        text: [0; MESSAGE_LEN],
+    };
+
+    pub fn text(&self) -> &str {
+        // Only whole characters are copied in
+        core::str::from_utf8(&self.text[..self.len]).unwrap_or_default()
+    }
+
+    /// True if the message did not fit into `MESSAGE_LEN` bytes.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_line(
+            f,
+            self.uptime_ms,
+            self.level,
+            format_args!("{}", self.text()),
+        )?;
+        if self.truncated {
+            f.write_str("...")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("sequence", &self.sequence)
+            .field("level", &self.level)
+            .field("uptime_ms", &self.uptime_ms)
+            .field("text", &self.text())
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
+
+/// Formats a log line without the line break, `[   12.345] INFO  kratos::pit: message`.
+pub fn write_line(
+    out: &mut dyn Write,
+    uptime_ms: u32,
+    level: Level,
+    message: fmt::Arguments,
+) -> fmt::Result {
+    write!(
+        out,
+        "[{:>5}.{:03}] {:<5} {}",
+        uptime_ms / 1000,
+        uptime_ms % 1000,
+        level,
+        message
+    )
+}
+
+// Copies what fits of the message into an entry
+struct EntryWriter<'a> {
+    entry: &'a mut Entry,
+}
+
+impl Write for EntryWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let free = MESSAGE_LEN - self.entry.len;
+        let mut len = s.len().min(free);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let start = self.entry.len;
+        self.entry.text[start..start + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.entry.len += len;
+        if len < s.len() {
+            self.entry.truncated = true;
+        }
+
+        Ok(())
+    }
+}
+
+struct Entries<const N: usize> {
+    entries: [Entry; N],
+    // Sequence number of the next message, its slot is the sequence number modulo N
+    next: u32,
+}
+
+/// Fixed size log that overwrites its oldest messages once full.
+///
+/// Every message gets a sequence number one higher than the one before, so a reader can tell
+/// how many messages it missed.
+pub struct LogBuffer<const N: usize> {
+    inner: RefCell<Entries<N>>,
+    // Messages lost because the buffer was in use, e.g. by the code an interrupt came from
+    dropped: AtomicU32,
+}
+
+unsafe impl<const N: usize> Sync for LogBuffer<N> {}
+
+impl<const N: usize> LogBuffer<N> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> LogBuffer<N> {
+        LogBuffer {
+            inner: RefCell::new(Entries {
+                entries: [Entry::EMPTY; N],
+                next: 0,
+            }),
+            dropped: AtomicU32::new(0),
+        }
+    }
+
+    /// Appends a message, returns its sequence number or `None` if it was dropped.
+    pub fn push(&self, level: Level, uptime_ms: u32, message: fmt::Arguments) -> Option<u32> {
+        let Ok(mut inner) = self.inner.try_borrow_mut() else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+
+        let sequence = inner.next;
+        let entry = &mut inner.entries[sequence as usize % N];
+        *entry = Entry {
+            sequence,
+            level,
+            uptime_ms,
+            ..Entry::EMPTY
+        };
+        let _ = EntryWriter { entry }.write_fmt(message);
+        inner.next = sequence.wrapping_add(1);
+
+        Some(sequence)
+    }
+
+    /// Returns the message with `sequence`, unless it was overwritten or not written yet.
+    pub fn get(&self, sequence: u32) -> Option<Entry> {
+        let inner = self.inner.try_borrow().ok()?;
+        let entry = inner.entries[sequence as usize % N];
+        let kept = sequence < inner.next && inner.next - sequence <= N as u32;
+
+        kept.then_some(entry)
+    }
+
+    /// Sequence number of the oldest message still kept.
+    pub fn first_sequence(&self) -> u32 {
+        self.next_sequence().saturating_sub(N as u32)
+    }
+
+    /// Sequence number the next message will get, which is also the number of messages so far.
+    pub fn next_sequence(&self) -> u32 {
+        self.inner.borrow().next
+    }
+
+    /// Number of messages overwritten by newer ones.
+    pub fn overwritten(&self) -> u32 {
+        self.first_sequence()
+    }
+
+    /// Number of messages that could not be stored at all.
+    pub fn dropped(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Kept messages from `sequence` on, oldest first.
+    ///
+    /// Messages added while iterating are included, overwritten ones are skipped.
+    pub fn entries_from(&self, sequence: u32) -> impl Iterator<Item = Entry> + '_ {
+        let mut sequence = sequence.max(self.first_sequence());
+        core::iter::from_fn(move || {
+            sequence = sequence.max(self.first_sequence());
+            let entry = self.get(sequence)?;
+            sequence += 1;
+            Some(entry)
+        })
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = Entry> + '_ {
+        self.entries_from(0)
+    }
+
+    /// Writes the kept messages up to `max_level` a line each, after a note on how many were
+    /// lost.
+    pub fn write_entries(&self, out: &mut dyn Write, max_level: LevelFilter) -> fmt::Result {
+        self.write_matching(out, |entry| entry.level <= max_level)
+    }
+
+    /// Like [`LogBuffer::write_entries`], for the kept messages `keep` returns true for.
+    pub fn write_matching(
+        &self,
+        out: &mut dyn Write,
+        keep: impl Fn(&Entry) -> bool,
+    ) -> fmt::Result {
+        let lost = self.overwritten() + self.dropped();
+        if lost > 0 {
+            writeln!(out, "[{} earlier messages lost]", lost)?;
+        }
+        for entry in self.entries().filter(|entry| keep(entry)) {
+            writeln!(out, "{}", entry)?;
+        }
+
+        Ok(())
+    }
+}
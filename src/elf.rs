@@ -0,0 +1,54 @@
+use crate::boot_info::ElfSections;
+
+// Section types
+pub const SHT_SYMTAB: u32 = 2;
+pub const SHT_STRTAB: u32 = 3;
+
+// Symbol types, stored in the low nibble of `st_info`
+pub const STT_FUNC: u8 = 2;
+
+// ELF32 section header
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SectionHeader {
+    pub sh_name: u32,
+    pub sh_type: u32,
+    pub sh_flags: u32,
+    pub sh_addr: u32,
+    pub sh_offset: u32,
+    pub sh_size: u32,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u32,
+    pub sh_entsize: u32,
+}
+
+// ELF32 symbol table entry
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Symbol {
+    pub st_name: u32,
+    pub st_value: u32,
+    pub st_size: u32,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+}
+
+impl Symbol {
+    pub fn symbol_type(&self) -> u8 {
+        self.st_info & 0xF
+    }
+}
+
+/// Returns the section header table the boot loader handed over.
+///
+/// An empty slice is returned if the entry size does not match an ELF32 section header.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn section_headers(sections: &ElfSections) -> &'static [SectionHeader] {
+    if sections.addr == 0 || sections.entsize as usize != core::mem::size_of::<SectionHeader>() {
+        return &[];
+    }
+
+    core::slice::from_raw_parts(sections.addr as *const SectionHeader, sections.num as usize)
+}
@@ -1,9 +1,10 @@
 use alloc::vec::Vec;
 use core::arch::asm;
 use core::cell::RefCell;
+use core::fmt::Write;
 
 // Library
-use crate::println;
+use crate::io::DisplayWriter;
 use crate::util::bit_manipulation::{get_bit, get_bits, set_bit, set_bits};
 
 static GDT_ENTRIES: GdtTable = GdtTable::new();
@@ -99,24 +100,33 @@ fn read_gdtr() -> Gdt {
 
 #[allow(clippy::missing_safety_doc)]
 pub unsafe fn print_gdtr() {
+    let _ = write_gdtr(&mut DisplayWriter);
+}
+
+/// Writes the segments of the loaded GDT to `out`.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn write_gdtr(out: &mut dyn Write) -> core::fmt::Result {
     let gdt = read_gdtr();
     let liimt = gdt.limit + 1;
     let base = gdt.base as *const GdtSegemt;
-    println!("Base: {:?}, Limit: {}", base, liimt);
+    writeln!(out, "Base: {:?}, Limit: {}", base, liimt)?;
 
     for index in 0..(liimt / 8) {
-        println!("Segment [{}]", index);
+        writeln!(out, "Segment [{}]", index)?;
         let segment = base.add(index.into());
         let base = (*segment).base();
         let limit = (*segment).limit();
         let access = (*segment).access();
         let flags = (*segment).flags();
 
-        println!(
+        writeln!(
+            out,
             "Base: {:#x}, Limit: {:#x}, Access: {:#x}, Flags: {:#x}",
             base, limit, access, flags
-        );
+        )?;
     }
+
+    Ok(())
 }
 
 #[allow(clippy::missing_safety_doc)]
@@ -1,10 +1,15 @@
 use core::arch::asm;
 use core::cell::RefCell;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use log::debug;
 
 use crate::{
+    backtrace::Backtrace,
     io::port_manager::PortManager,
-    println,
-    util::bit_manipulation::{set_bit, set_bits},
+    pic, println,
+    symbols::Symbolized,
+    util::bit_manipulation::{get_bit, get_bits, set_bit, set_bits},
 };
 
 #[macro_export]
@@ -30,11 +35,33 @@ impl InterruptTable {
 
 unsafe impl Sync for InterruptTable {}
 
+// Handlers are stored as function addresses so an IRQ never waits on a lock held by the code it
+// interrupted
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_HANDLER: AtomicUsize = AtomicUsize::new(0);
+static IRQ_HANDLERS: [AtomicUsize; pic::IRQ_COUNT as usize] = [NO_HANDLER; pic::IRQ_COUNT as usize];
+
 #[repr(C, packed)]
 #[derive(Clone, Copy)]
 struct GateDescriptor(u64);
 
 impl GateDescriptor {
+    fn offset(&self) -> u32 {
+        (get_bits(self.0, 0, 16) | get_bits(self.0, 48, 16) << 16) as u32
+    }
+
+    fn segment_selector(&self) -> u16 {
+        get_bits(self.0, 16, 16) as u16
+    }
+
+    fn gate_type(&self) -> u8 {
+        get_bits(self.0, 40, 4) as u8
+    }
+
+    fn present(&self) -> bool {
+        get_bit(self.0, 47) != 0
+    }
+
     fn new(params: GateDescriptorParams) -> GateDescriptor {
         let mut descriptor = 1u64;
 
@@ -62,17 +89,8 @@ struct GateDescriptorParams {
     p: bool,
 }
 
-pub fn init(port_manager: &mut PortManager) {
-    let master_pic_data = port_manager
-        .request_port(0x21)
-        .expect("Failed to get master data port");
-    let slave_pic_data = port_manager
-        .request_port(0xA1)
-        .expect("Failed to get slave data port");
-
-    // Disable External Interrupts
-    master_pic_data.writeb(0xFF);
-    slave_pic_data.writeb(0xFF);
+pub fn init(port_manager: &'static PortManager) {
+    pic::init(port_manager);
 
     let general_fault_descriptor = GateDescriptor::new(GateDescriptorParams {
         #[allow(clippy::fn_to_numeric_cast)]
@@ -95,6 +113,17 @@ pub fn init(port_manager: &mut PortManager) {
     table[8] = double_fault_descriptor;
     table[13] = general_fault_descriptor;
 
+    for (irq, stub) in IRQ_STUBS.iter().enumerate() {
+        table[pic::MASTER_OFFSET as usize + irq] = GateDescriptor::new(GateDescriptorParams {
+            #[allow(clippy::fn_to_numeric_cast)]
+            offset: *stub as u32,
+            segment_selector: 0x08,
+            gate_type: GateType::Interrupt as u8,
+            dpl: 0,
+            p: true,
+        });
+    }
+
     let size = table.len() * core::mem::size_of::<GateDescriptor>() - 1;
     let table_ptr = table.as_ptr();
     let idt = Idt {
@@ -102,7 +131,7 @@ pub fn init(port_manager: &mut PortManager) {
         base: table_ptr as u32,
     };
 
-    println!("Initial IDT: {:?}", read_idtr());
+    debug!("Initial IDT: {:?}", read_idtr());
 
     unsafe {
         asm!(r#"
@@ -114,7 +143,117 @@ pub fn init(port_manager: &mut PortManager) {
         );
     }
 
-    println!("Updated IDT {:?}", read_idtr());
+    debug!("Updated IDT {:?}", read_idtr());
+
+    // Handlers registered before the PICs were remapped
+    for (irq, handler) in IRQ_HANDLERS.iter().enumerate() {
+        if handler.load(Ordering::Acquire) != 0 {
+            pic::enable_irq(irq as u8);
+        }
+    }
+}
+
+/// Sleeps until `poll` returns a value, for input that IRQ handlers queue.
+///
+/// Before sleeping `poll` runs again with interrupts disabled, and the sleep starts with
+/// `sti; hlt`, which lets them in only once the CPU halts. An IRQ right after an empty poll
+/// still wakes it. Interrupts have to be enabled, nothing else ends the sleep.
+pub fn wait_for<T>(mut poll: impl FnMut() -> Option<T>) -> T {
+    loop {
+        if let Some(value) = poll() {
+            return value;
+        }
+
+        // No `nomem`, the polled memory must not be read outside of the `cli`
+        unsafe { asm!("cli", options(nostack)) };
+        if let Some(value) = poll() {
+            unsafe { asm!("sti", options(nostack)) };
+            return value;
+        }
+
+        unsafe { asm!("sti", "hlt", options(nostack)) };
+    }
+}
+
+/// Calls `handler` on every `irq` and unmasks the line.
+///
+/// The handler runs with interrupts disabled, the end of interrupt is sent once it returns.
+pub fn register_irq_handler(irq: u8, handler: fn()) {
+    IRQ_HANDLERS[irq as usize].store(handler as usize, Ordering::Release);
+    if pic::is_initialized() {
+        pic::enable_irq(irq);
+    }
+}
+
+pub fn unregister_irq_handler(irq: u8) {
+    if pic::is_initialized() {
+        pic::disable_irq(irq);
+    }
+    IRQ_HANDLERS[irq as usize].store(0, Ordering::Release);
+}
+
+fn dispatch_irq(irq: u8) {
+    if pic::is_spurious(irq) {
+        return;
+    }
+
+    let handler = IRQ_HANDLERS[irq as usize].load(Ordering::Acquire);
+    if handler != 0 {
+        let handler: fn() = unsafe { core::mem::transmute(handler) };
+        handler();
+    }
+
+    pic::end_of_interrupt(irq);
+}
+
+macro_rules! irq_stubs {
+    ($($irq:literal),*) => {
+        paste::paste! {
+            $(
+                extern "x86-interrupt" fn [<irq $irq _handler>](_stack_frame: InterruptStackFrame) {
+                    dispatch_irq($irq);
+                }
+            )*
+
+            static IRQ_STUBS: [extern "x86-interrupt" fn(InterruptStackFrame); pic::IRQ_COUNT as usize] =
+                [$([<irq $irq _handler>]),*];
+        }
+    };
+}
+
+irq_stubs!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+
+/// Writes the present gates of the loaded IDT to `out`.
+pub fn write_idt(out: &mut dyn Write) -> core::fmt::Result {
+    let idt = read_idtr();
+    let base = idt.base as *const GateDescriptor;
+    let size = idt.size;
+    writeln!(out, "Base: {:?}, Limit: {}", base, size as u32 + 1)?;
+
+    let entries = (size as usize + 1) / core::mem::size_of::<GateDescriptor>();
+    for vector in 0..entries {
+        let descriptor = unsafe { base.add(vector).read_unaligned() };
+        if !descriptor.present() {
+            continue;
+        }
+
+        let gate_type = match descriptor.gate_type() {
+            0b1110 => "interrupt",
+            0b1111 => "trap",
+            0b0101 => "task",
+            _ => "unknown",
+        };
+        writeln!(
+            out,
+            "[{:#04x}] Selector: {:#x}, Type: {}, Handler: {}",
+            vector,
+            descriptor.segment_selector(),
+            gate_type,
+            Symbolized(descriptor.offset())
+        )?;
+    }
+
+    Ok(())
 }
 
 fn read_idtr() -> Idt {
@@ -138,10 +277,29 @@ struct Idt {
     base: u32,
 }
 
-extern "x86-interrupt" fn general_fault_handler(error_code: u32) {
-    println!("General fault handler, Error code: {}", error_code);
+// Pushed by the CPU before calling the handler, no stack switch as everything runs in ring 0
+#[repr(C)]
+#[derive(Debug)]
+pub struct InterruptStackFrame {
+    pub eip: u32,
+    pub cs: u32,
+    pub eflags: u32,
+}
+
+extern "x86-interrupt" fn general_fault_handler(stack_frame: InterruptStackFrame, error_code: u32) {
+    println!(
+        "General fault handler, Error code: {}, at {}",
+        error_code,
+        Symbolized(stack_frame.eip)
+    );
+    println!("{}", Backtrace::capture_interrupted(stack_frame.eip));
 }
 
-extern "x86-interrupt" fn double_fault_handler(error_code: u32) {
-    println!("Double fault handler, Error code: {}", error_code);
+extern "x86-interrupt" fn double_fault_handler(stack_frame: InterruptStackFrame, error_code: u32) {
+    println!(
+        "Double fault handler, Error code: {}, at {}",
+        error_code,
+        Symbolized(stack_frame.eip)
+    );
+    println!("{}", Backtrace::capture_interrupted(stack_frame.eip));
 }
@@ -0,0 +1,150 @@
+const ESCAPE: u8 = 0x1B;
+const MAX_PARAMS: usize = 8;
+
+/// Something for the terminal to do after feeding a byte to the `Parser`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// A printable character or a C0 control like '\n' to be handled by the terminal
+    Print(u8),
+    /// A complete Control Sequence Introducer sequence, `ESC [ params final`
+    Csi(Csi),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Csi {
+    params: [u16; MAX_PARAMS],
+    len: usize,
+    /// Set for sequences starting with '?', '<', '=' or '>', which are vendor specific
+    pub private: bool,
+    pub final_byte: u8,
+}
+
+impl Csi {
+    pub fn params(&self) -> &[u16] {
+        &self.params[..self.len]
+    }
+
+    /// Returns parameter `index`, using `default` when it is missing or 0.
+    pub fn param(&self, index: usize, default: u16) -> u16 {
+        match self.params().get(index) {
+            Some(&0) | None => default,
+            Some(&param) => param,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    CsiEntry,
+    CsiParam,
+    // Malformed sequence, everything up to the final byte is dropped
+    CsiIgnore,
+}
+
+/// VT100 escape sequence state machine, fed one byte at a time.
+pub struct Parser {
+    state: State,
+    csi: Csi,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    pub const fn new() -> Parser {
+        Parser {
+            state: State::Ground,
+            csi: Csi {
+                params: [0; MAX_PARAMS],
+                len: 0,
+                private: false,
+                final_byte: 0,
+            },
+        }
+    }
+
+    pub fn advance(&mut self, byte: u8) -> Option<Action> {
+        // ESC aborts any sequence in progress and starts a new one
+        if byte == ESCAPE {
+            self.state = State::Escape;
+            return None;
+        }
+
+        match self.state {
+            State::Ground => Some(Action::Print(byte)),
+            State::Escape => {
+                // Only CSI sequences are supported, others like "ESC ( B" end at their first
+                // byte outside of the intermediate range
+                self.state = match byte {
+                    0x20..=0x2F => State::Escape,
+                    b'[' => {
+                        self.csi.params = [0; MAX_PARAMS];
+                        self.csi.len = 0;
+                        self.csi.private = false;
+                        State::CsiEntry
+                    }
+                    _ => State::Ground,
+                };
+                None
+            }
+            State::CsiEntry | State::CsiParam => self.csi_byte(byte),
+            State::CsiIgnore => {
+                if is_final_byte(byte) {
+                    self.state = State::Ground;
+                }
+                None
+            }
+        }
+    }
+
+    fn csi_byte(&mut self, byte: u8) -> Option<Action> {
+        match byte {
+            b'0'..=b'9' => {
+                if self.csi.len == 0 {
+                    self.csi.len = 1;
+                }
+                let param = &mut self.csi.params[self.csi.len - 1];
+                *param = param
+                    .saturating_mul(10)
+                    .saturating_add((byte - b'0') as u16);
+                self.state = State::CsiParam;
+                None
+            }
+            b';' => {
+                if self.csi.len == 0 {
+                    self.csi.len = 1;
+                }
+                if self.csi.len == MAX_PARAMS {
+                    self.state = State::CsiIgnore;
+                } else {
+                    self.csi.len += 1;
+                    self.state = State::CsiParam;
+                }
+                None
+            }
+            b'<'..=b'?' if self.state == State::CsiEntry => {
+                self.csi.private = true;
+                self.state = State::CsiParam;
+                None
+            }
+            _ if is_final_byte(byte) => {
+                self.csi.final_byte = byte;
+                self.state = State::Ground;
+                Some(Action::Csi(self.csi))
+            }
+            _ => {
+                self.state = State::CsiIgnore;
+                None
+            }
+        }
+    }
+}
+
+fn is_final_byte(byte: u8) -> bool {
+    (0x40..=0x7E).contains(&byte)
+}
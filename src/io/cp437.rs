@@ -0,0 +1,56 @@
+// Unicode equivalents of the code page 437 characters 0x80 to 0xFF
+const UPPER_HALF: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+// Look-alikes that are commonly used instead of the code page's own code point
+const ALIASES: [(char, u8); 6] = [
+    ('μ', 0xE6),        // Greek small letter mu for the micro sign
+    ('β', 0xE1),        // Greek small letter beta for sharp s
+    ('∑', 0xE4),        // N-ary summation for capital sigma
+    ('\u{2126}', 0xEA), // Ohm sign for capital omega
+    ('∈', 0xEE),        // Element of for small epsilon
+    ('•', 0xF9),        // Bullet for bullet operator
+];
+
+/// Character the VGA shows for code points that are not part of code page 437.
+pub const REPLACEMENT: u8 = 0xFE; // '■'
+
+/// Returns the code page 437 character for `character`.
+///
+/// The glyphs at 0x01 to 0x1F are not mapped, as those bytes are control characters to the
+/// terminal.
+pub fn from_char(character: char) -> Option<u8> {
+    match character {
+        '\0'..='\x7F' => Some(character as u8),
+        // 0x7F shows a house glyph on the VGA
+        '⌂' => Some(0x7F),
+        _ => UPPER_HALF
+            .iter()
+            .position(|&upper| upper == character)
+            .map(|index| 0x80 + index as u8)
+            .or_else(|| {
+                ALIASES
+                    .iter()
+                    .find(|(alias, _)| *alias == character)
+                    .map(|&(_, byte)| byte)
+            }),
+    }
+}
+
+/// Returns the Unicode character shown for code page 437 `byte`, for the printable ranges.
+pub fn to_char(byte: u8) -> Option<char> {
+    match byte {
+        0x20..=0x7E => Some(byte as char),
+        0x7F => Some('⌂'),
+        0x80..=0xFF => Some(UPPER_HALF[byte as usize - 0x80]),
+        _ => None,
+    }
+}
@@ -0,0 +1,79 @@
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::io::port_manager::WriteOnlyPort;
+use crate::io::sink::{ConsoleSink, RegistryFull, SinkCell};
+
+// Matches `-debugcon stdio` in QEMU and `port_e9_hack: enabled=1` in Bochs
+pub const PORT: u16 = 0xE9;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static DEBUGCON: SinkCell<DebugCon> = SinkCell::new();
+
+/// The debug console of Bochs and QEMU, every byte written to port 0xE9 shows up on the host.
+///
+/// The port needs no set up and is not reserved with the port manager, so the console works
+/// from the first instruction on. On hardware without it the writes go nowhere.
+pub struct DebugCon {
+    port: WriteOnlyPort,
+}
+
+impl Default for DebugCon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugCon {
+    pub fn new() -> DebugCon {
+        DebugCon {
+            port: WriteOnlyPort::new(PORT),
+        }
+    }
+}
+
+impl Write for DebugCon {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &byte in s.as_bytes() {
+            self.port.write(byte);
+        }
+
+        Ok(())
+    }
+}
+
+impl ConsoleSink for DebugCon {
+    fn name(&self) -> &'static str {
+        "debugcon"
+    }
+}
+
+/// Adds the debug console as a display sink, later calls do nothing.
+///
+/// The panic path writes to the console from then on, even if there was no room for the sink.
+///
+/// Needs neither the heap nor the port manager, see the `debugcon` feature and command line
+/// option.
+pub fn enable() -> Result<(), RegistryFull> {
+    if ENABLED.swap(true, Ordering::AcqRel) {
+        return Ok(());
+    }
+
+    let debugcon = DEBUGCON
+        .put(DebugCon::new())
+        .unwrap_or_else(|_| panic!("Debug console already initialized"));
+    crate::io::register_sink(debugcon)?;
+
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// Writes `text` to the debug console if it is enabled, for the panic path.
+pub fn write_raw(text: &str) {
+    if is_enabled() {
+        let _ = DebugCon::new().write_str(text);
+    }
+}
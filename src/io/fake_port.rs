@@ -0,0 +1,144 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use crate::io::port_manager::{PortRead, PortWrite};
+
+/// Port access recorded by a [`FakeBus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read(u16, u8),
+    Write(u16, u8),
+}
+
+/// Device model answering the accesses to the ports it is attached to.
+pub trait FakeDevice {
+    fn read(&mut self, addr: u16) -> u8;
+
+    fn write(&mut self, addr: u16, val: u8);
+}
+
+struct Attached {
+    start: u16,
+    len: u16,
+    device: Rc<RefCell<dyn FakeDevice>>,
+}
+
+/// Fake I/O space, ports without a device read as 0xFF like a floating ISA bus.
+#[derive(Default)]
+pub struct FakeBus {
+    devices: RefCell<Vec<Attached>>,
+    log: RefCell<Vec<Access>>,
+}
+
+impl FakeBus {
+    pub fn new() -> Rc<FakeBus> {
+        Rc::new(FakeBus::default())
+    }
+
+    pub fn attach(&self, start: u16, len: u16, device: Rc<RefCell<dyn FakeDevice>>) {
+        self.devices
+            .borrow_mut()
+            .push(Attached { start, len, device });
+    }
+
+    pub fn port(self: &Rc<Self>, addr: u16) -> FakePort {
+        FakePort {
+            bus: self.clone(),
+            addr,
+        }
+    }
+
+    /// Every access made so far, oldest first.
+    pub fn log(&self) -> Vec<Access> {
+        self.log.borrow().clone()
+    }
+
+    pub fn clear_log(&self) {
+        self.log.borrow_mut().clear();
+    }
+
+    fn device(&self, addr: u16) -> Option<Rc<RefCell<dyn FakeDevice>>> {
+        self.devices
+            .borrow()
+            .iter()
+            .find(|attached| addr >= attached.start && addr - attached.start < attached.len)
+            .map(|attached| attached.device.clone())
+    }
+}
+
+/// Port of a [`FakeBus`].
+pub struct FakePort {
+    bus: Rc<FakeBus>,
+    addr: u16,
+}
+
+impl PortRead for FakePort {
+    fn read(&self) -> u8 {
+        let val = match self.bus.device(self.addr) {
+            Some(device) => device.borrow_mut().read(self.addr),
+            None => 0xFF,
+        };
+        self.bus.log.borrow_mut().push(Access::Read(self.addr, val));
+
+        val
+    }
+}
+
+impl PortWrite for FakePort {
+    fn write(&self, val: u8) {
+        if let Some(device) = self.bus.device(self.addr) {
+            device.borrow_mut().write(self.addr, val);
+        }
+        self.bus
+            .log
+            .borrow_mut()
+            .push(Access::Write(self.addr, val));
+    }
+}
+
+/// Scriptable register file.
+///
+/// Writes are latched and read back, unless reads have been scripted for the register, which are
+/// then replayed first, e.g. a status bit that only clears on the third poll.
+#[derive(Default)]
+pub struct Registers {
+    values: BTreeMap<u16, u8>,
+    scripts: BTreeMap<u16, VecDeque<u8>>,
+}
+
+impl Registers {
+    pub fn new() -> Registers {
+        Registers::default()
+    }
+
+    pub fn get(&self, register: u16) -> u8 {
+        self.values.get(&register).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, register: u16, val: u8) {
+        self.values.insert(register, val);
+    }
+
+    /// Queues `reads` to be returned by the next reads of `register`.
+    pub fn script(&mut self, register: u16, reads: &[u8]) {
+        self.scripts
+            .entry(register)
+            .or_default()
+            .extend(reads.iter().copied());
+    }
+}
+
+impl FakeDevice for Registers {
+    fn read(&mut self, addr: u16) -> u8 {
+        match self.scripts.get_mut(&addr).and_then(VecDeque::pop_front) {
+            Some(val) => val,
+            None => self.get(addr),
+        }
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.set(addr, val);
+    }
+}
@@ -0,0 +1,341 @@
+use core::fmt::Write;
+use thiserror_no_std::Error;
+
+use crate::boot_info::{FramebufferInfo, FramebufferKind};
+use crate::io::sink::ConsoleSink;
+use crate::io::vga::VgaColor;
+
+// 8x16 PSF1 font rasterized from DejaVu Sans Mono, glyphs are indexed by Latin-1 code point
+static FONT: &[u8] = include_bytes!("font.psf");
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+
+const TAB_WIDTH: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum FramebufferInitError {
+    #[error("Framebuffer is not a direct RGB framebuffer")]
+    NotRgb,
+    #[error("Unsupported framebuffer depth {0} bpp")]
+    UnsupportedDepth(u8),
+    #[error("Framebuffer is not addressable")]
+    NotAddressable,
+    #[error("Invalid PSF font")]
+    InvalidFont,
+}
+
+/// PSF1 or PSF2 bitmap font.
+pub struct PsfFont {
+    glyphs: &'static [u8],
+    glyph_count: usize,
+    bytes_per_glyph: usize,
+    width: usize,
+    height: usize,
+}
+
+impl PsfFont {
+    pub fn builtin() -> Option<PsfFont> {
+        PsfFont::parse(FONT)
+    }
+
+    pub fn parse(data: &'static [u8]) -> Option<PsfFont> {
+        let read_u32 = |offset: usize| -> Option<usize> {
+            let bytes = data.get(offset..offset + 4)?;
+            Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+        };
+
+        let font = if data.starts_with(&PSF1_MAGIC) {
+            let mode = *data.get(2)?;
+            let height = *data.get(3)? as usize;
+            PsfFont {
+                glyphs: &data[4..],
+                glyph_count: if mode & PSF1_MODE_512 != 0 { 512 } else { 256 },
+                bytes_per_glyph: height,
+                width: 8,
+                height,
+            }
+        } else if data.starts_with(&PSF2_MAGIC) {
+            let header_size = read_u32(8)?;
+            PsfFont {
+                glyphs: data.get(header_size..)?,
+                glyph_count: read_u32(16)?,
+                bytes_per_glyph: read_u32(20)?,
+                height: read_u32(24)?,
+                width: read_u32(28)?,
+            }
+        } else {
+            return None;
+        };
+
+        let valid = font.width > 0
+            && font.height > 0
+            && font.bytes_per_glyph >= font.width.div_ceil(8) * font.height
+            && font.glyphs.len() >= font.glyph_count * font.bytes_per_glyph;
+
+        valid.then_some(font)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Characters outside of the font are drawn as '?'
+    fn glyph(&self, character: char) -> &[u8] {
+        let index = match character as usize {
+            index if index < self.glyph_count => index,
+            _ => '?' as usize,
+        };
+        let start = index * self.bytes_per_glyph;
+
+        &self.glyphs[start..start + self.bytes_per_glyph]
+    }
+}
+
+struct PixelFormat {
+    bytes_per_pixel: usize,
+    red: (u8, u8),
+    green: (u8, u8),
+    blue: (u8, u8),
+}
+
+impl PixelFormat {
+    // Scales each 8 bit channel down to its mask size and shifts it into position
+    fn encode(&self, (red, green, blue): (u8, u8, u8)) -> u32 {
+        let channel = |value: u8, (position, size): (u8, u8)| -> u32 {
+            let size = size.min(8);
+            ((value as u32) >> (8 - size)) << position
+        };
+
+        channel(red, self.red) | channel(green, self.green) | channel(blue, self.blue)
+    }
+}
+
+/// Text console drawn with a bitmap font on a linear RGB framebuffer.
+pub struct FramebufferConsole {
+    buffer: *mut u8,
+    pitch: usize,
+    width: usize,
+    height: usize,
+    format: PixelFormat,
+    font: PsfFont,
+    columns: usize,
+    rows: usize,
+    column: usize,
+    row: usize,
+    foreground: u32,
+    background: u32,
+}
+
+impl ConsoleSink for FramebufferConsole {
+    fn name(&self) -> &'static str {
+        "framebuffer"
+    }
+}
+
+impl Write for FramebufferConsole {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.write_text(s);
+
+        Ok(())
+    }
+}
+
+impl FramebufferConsole {
+    pub fn new(info: &FramebufferInfo) -> Result<FramebufferConsole, FramebufferInitError> {
+        use FramebufferInitError::*;
+
+        let FramebufferKind::Rgb {
+            red_position,
+            red_size,
+            green_position,
+            green_size,
+            blue_position,
+            blue_size,
+        } = info.kind
+        else {
+            return Err(NotRgb);
+        };
+
+        let bytes_per_pixel = match info.bpp {
+            24 => 3,
+            32 => 4,
+            bpp => return Err(UnsupportedDepth(bpp)),
+        };
+
+        let end = info.addr + info.pitch as u64 * info.height as u64;
+        if end > u32::MAX as u64 {
+            return Err(NotAddressable);
+        }
+
+        let font = PsfFont::builtin().ok_or(InvalidFont)?;
+        let format = PixelFormat {
+            bytes_per_pixel,
+            red: (red_position, red_size),
+            green: (green_position, green_size),
+            blue: (blue_position, blue_size),
+        };
+        let foreground = format.encode(rgb(VgaColor::LightGrey));
+        let background = format.encode(rgb(VgaColor::Black));
+
+        Ok(FramebufferConsole {
+            buffer: info.addr as usize as *mut u8,
+            pitch: info.pitch as usize,
+            width: info.width as usize,
+            height: info.height as usize,
+            columns: info.width as usize / font.width(),
+            rows: info.height as usize / font.height(),
+            format,
+            font,
+            column: 0,
+            row: 0,
+            foreground,
+            background,
+        })
+    }
+
+    pub fn init(&mut self) -> core::fmt::Result {
+        self.clear();
+
+        Ok(())
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn set_color(&mut self, fore_ground_color: VgaColor, back_ground_color: VgaColor) {
+        self.foreground = self.format.encode(rgb(fore_ground_color));
+        self.background = self.format.encode(rgb(back_ground_color));
+    }
+
+    pub fn clear(&mut self) {
+        self.fill_rect(0, 0, self.width, self.height, self.background);
+        self.column = 0;
+        self.row = 0;
+    }
+
+    pub fn write_text(&mut self, text: &str) {
+        for character in text.chars() {
+            match character {
+                '\n' => self.handle_new_line(),
+                '\r' => self.column = 0,
+                '\t' => {
+                    let next_stop = (self.column / TAB_WIDTH + 1) * TAB_WIDTH;
+                    while self.column < next_stop.min(self.columns) {
+                        self.write_character(' ');
+                    }
+                }
+                _ => self.write_character(character),
+            }
+        }
+    }
+
+    fn write_character(&mut self, character: char) {
+        if self.column == self.columns {
+            self.handle_new_line();
+        }
+
+        self.draw_glyph(self.column, self.row, character);
+        self.column += 1;
+    }
+
+    fn handle_new_line(&mut self) {
+        self.column = 0;
+        if self.row + 1 < self.rows {
+            self.row += 1;
+        } else {
+            self.scroll();
+        }
+    }
+
+    // Moves every text row but the first one up and clears the last one
+    fn scroll(&mut self) {
+        let row_bytes = self.pitch * self.font.height();
+        let text_bytes = row_bytes * self.rows;
+        unsafe {
+            core::ptr::copy(
+                self.buffer.add(row_bytes),
+                self.buffer,
+                text_bytes - row_bytes,
+            );
+        }
+
+        let last_row = (self.rows - 1) * self.font.height();
+        self.fill_rect(0, last_row, self.width, self.font.height(), self.background);
+    }
+
+    fn draw_glyph(&mut self, column: usize, row: usize, character: char) {
+        let (width, height) = (self.font.width(), self.font.height());
+        let bytes_per_row = width.div_ceil(8);
+        let x = column * width;
+        let y = row * height;
+
+        for glyph_y in 0..height {
+            for glyph_x in 0..width {
+                let byte = self.font.glyph(character)[glyph_y * bytes_per_row + glyph_x / 8];
+                let color = if byte & (0x80 >> (glyph_x % 8)) != 0 {
+                    self.foreground
+                } else {
+                    self.background
+                };
+
+                self.put_pixel(x + glyph_x, y + glyph_y, color);
+            }
+        }
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: u32) {
+        for pixel_y in y..(y + height).min(self.height) {
+            for pixel_x in x..(x + width).min(self.width) {
+                self.put_pixel(pixel_x, pixel_y, color);
+            }
+        }
+    }
+
+    fn put_pixel(&mut self, x: usize, y: usize, color: u32) {
+        let offset = y * self.pitch + x * self.format.bytes_per_pixel;
+        unsafe {
+            let pixel = self.buffer.add(offset);
+            match self.format.bytes_per_pixel {
+                4 => (pixel as *mut u32).write_volatile(color),
+                _ => {
+                    for (index, byte) in color.to_le_bytes().iter().take(3).enumerate() {
+                        pixel.add(index).write_volatile(*byte);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Standard VGA palette
+fn rgb(color: VgaColor) -> (u8, u8, u8) {
+    match color {
+        VgaColor::Black => (0x00, 0x00, 0x00),
+        VgaColor::Blue => (0x00, 0x00, 0xAA),
+        VgaColor::Green => (0x00, 0xAA, 0x00),
+        VgaColor::Cyan => (0x00, 0xAA, 0xAA),
+        VgaColor::Red => (0xAA, 0x00, 0x00),
+        VgaColor::Magneta => (0xAA, 0x00, 0xAA),
+        VgaColor::Brown => (0xAA, 0x55, 0x00),
+        VgaColor::LightGrey => (0xAA, 0xAA, 0xAA),
+        VgaColor::DarkGrey => (0x55, 0x55, 0x55),
+        VgaColor::LightBlue => (0x55, 0x55, 0xFF),
+        VgaColor::LightGreen => (0x55, 0xFF, 0x55),
+        VgaColor::LightCyan => (0x55, 0xFF, 0xFF),
+        VgaColor::LightRed => (0xFF, 0x55, 0x55),
+        VgaColor::LightMagneta => (0xFF, 0x55, 0xFF),
+        VgaColor::LightBrown => (0xFF, 0xFF, 0x55),
+        VgaColor::White => (0xFF, 0xFF, 0xFF),
+    }
+}
@@ -0,0 +1,754 @@
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::interrupt;
+use crate::io::keymap::{self, Keymap};
+use crate::io::port_manager::{Port, PortIo};
+use crate::io::ps2::{self, Ps2Controller, Ps2Error, Ps2Port};
+use crate::io::vga::{Terminal, VGA_HEIGHT};
+use crate::io::DISPLAY;
+use crate::pit;
+use crate::util::ring_buffer::RingBuffer;
+
+pub const IRQ: u8 = 1;
+pub const EVENT_BUFFER_SIZE: usize = 64;
+
+// Keyboard commands
+const SET_LEDS: u8 = 0xED;
+const SCANCODE_SET: u8 = 0xF0;
+const ENABLE_SCANNING: u8 = 0xF4;
+const DISABLE_SCANNING: u8 = 0xF5;
+const RESET: u8 = 0xFF;
+
+const SELF_TEST_PASSED: u8 = 0xAA;
+
+// Prefixes of multi byte scancodes
+const EXTENDED: u8 = 0xE0;
+const PAUSE: u8 = 0xE1;
+const SET2_RELEASE: u8 = 0xF0;
+
+static EVENTS: RingBuffer<KeyEvent, EVENT_BUFFER_SIZE> = RingBuffer::new();
+static KEYMAP: AtomicPtr<Keymap> = AtomicPtr::new(&keymap::US as *const Keymap as *mut Keymap);
+static IRQ_STATE: IrqState = IrqState {
+    inner: RefCell::new(None),
+};
+
+// Decoder, modifier and LED state, only touched by the IRQ handler once it is registered
+struct IrqState {
+    inner: RefCell<Option<(Decoder, ModifierState, LedUpdate)>>,
+}
+
+unsafe impl Sync for IrqState {}
+
+/// Physical key, named after its legend on a US keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    Backquote,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Digit0,
+    Minus,
+    Equal,
+    Backspace,
+    Tab,
+    Q,
+    W,
+    E,
+    R,
+    T,
+    Y,
+    U,
+    I,
+    O,
+    P,
+    LeftBracket,
+    RightBracket,
+    // '#' on UK keyboards
+    Backslash,
+    CapsLock,
+    A,
+    S,
+    D,
+    F,
+    G,
+    H,
+    J,
+    K,
+    L,
+    Semicolon,
+    Quote,
+    Enter,
+    LeftShift,
+    // Extra key left of Z on ISO keyboards
+    NonUsBackslash,
+    Z,
+    X,
+    C,
+    V,
+    B,
+    N,
+    M,
+    Comma,
+    Period,
+    Slash,
+    RightShift,
+    LeftCtrl,
+    LeftGui,
+    LeftAlt,
+    Space,
+    RightAlt,
+    RightGui,
+    Menu,
+    RightCtrl,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    NumLock,
+    KeypadDivide,
+    KeypadMultiply,
+    KeypadMinus,
+    KeypadPlus,
+    KeypadEnter,
+    KeypadPeriod,
+    Keypad0,
+    Keypad1,
+    Keypad2,
+    Keypad3,
+    Keypad4,
+    Keypad5,
+    Keypad6,
+    Keypad7,
+    Keypad8,
+    Keypad9,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScancodeSet {
+    Set1,
+    Set2,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    // Right Alt, which picks the third symbol of a key on most non US layouts
+    pub alt_gr: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
+    pub scroll_lock: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: KeyCode,
+    pub pressed: bool,
+    /// Modifiers after the event was applied
+    pub modifiers: Modifiers,
+}
+
+/// Turns the bytes of a scancode set into key presses and releases.
+pub struct Decoder {
+    set: ScancodeSet,
+    extended: bool,
+    release: bool,
+    // Bytes of the pause sequence that are still to come
+    pause_remaining: u8,
+}
+
+impl Decoder {
+    pub const fn new(set: ScancodeSet) -> Decoder {
+        Decoder {
+            set,
+            extended: false,
+            release: false,
+            pause_remaining: 0,
+        }
+    }
+
+    /// Feeds the next byte from the keyboard, returns the key and whether it was pressed once a
+    /// scancode is complete.
+    pub fn feed(&mut self, byte: u8) -> Option<(KeyCode, bool)> {
+        if self.pause_remaining > 0 {
+            self.pause_remaining -= 1;
+            return match self.pause_remaining {
+                0 => Some((KeyCode::Pause, true)),
+                _ => None,
+            };
+        }
+
+        match (self.set, byte) {
+            // Command responses that end up in the same buffer as the scancodes
+            (_, ps2::ACK | ps2::RESEND | 0x00 | 0xFF) | (ScancodeSet::Set2, SELF_TEST_PASSED) => {
+                None
+            }
+            (_, EXTENDED) => {
+                self.extended = true;
+                None
+            }
+            // Pause has no release, it is "E1 1D 45 E1 9D C5" in set 1 and 8 bytes in set 2
+            (ScancodeSet::Set1, PAUSE) => {
+                self.pause_remaining = 5;
+                None
+            }
+            (ScancodeSet::Set2, PAUSE) => {
+                self.pause_remaining = 7;
+                None
+            }
+            (ScancodeSet::Set2, SET2_RELEASE) => {
+                self.release = true;
+                None
+            }
+            (ScancodeSet::Set1, _) => {
+                let extended = core::mem::take(&mut self.extended);
+                set1_key(byte & 0x7F, extended).map(|key| (key, byte & 0x80 == 0))
+            }
+            (ScancodeSet::Set2, _) => {
+                let extended = core::mem::take(&mut self.extended);
+                let release = core::mem::take(&mut self.release);
+                set2_key(byte, extended).map(|key| (key, !release))
+            }
+        }
+    }
+}
+
+/// Modifier keys held and lock keys toggled so far.
+#[derive(Default)]
+pub struct ModifierState {
+    left_shift: bool,
+    right_shift: bool,
+    left_ctrl: bool,
+    right_ctrl: bool,
+    alt: bool,
+    alt_gr: bool,
+    caps_lock: bool,
+    num_lock: bool,
+    scroll_lock: bool,
+}
+
+impl ModifierState {
+    pub fn new() -> ModifierState {
+        ModifierState::default()
+    }
+
+    /// Applies a key event, returns true if a lock key toggled and the LEDs need an update.
+    pub fn update(&mut self, key: KeyCode, pressed: bool) -> bool {
+        match key {
+            KeyCode::LeftShift => self.left_shift = pressed,
+            KeyCode::RightShift => self.right_shift = pressed,
+            KeyCode::LeftCtrl => self.left_ctrl = pressed,
+            KeyCode::RightCtrl => self.right_ctrl = pressed,
+            KeyCode::LeftAlt => self.alt = pressed,
+            KeyCode::RightAlt => self.alt_gr = pressed,
+            KeyCode::CapsLock if pressed => self.caps_lock = !self.caps_lock,
+            KeyCode::NumLock if pressed => self.num_lock = !self.num_lock,
+            KeyCode::ScrollLock if pressed => self.scroll_lock = !self.scroll_lock,
+            _ => return false,
+        }
+
+        matches!(
+            key,
+            KeyCode::CapsLock | KeyCode::NumLock | KeyCode::ScrollLock
+        ) && pressed
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            shift: self.left_shift || self.right_shift,
+            ctrl: self.left_ctrl || self.right_ctrl,
+            alt: self.alt,
+            alt_gr: self.alt_gr,
+            caps_lock: self.caps_lock,
+            num_lock: self.num_lock,
+            scroll_lock: self.scroll_lock,
+        }
+    }
+
+    /// LED byte of the set LEDs command.
+    pub fn leds(&self) -> u8 {
+        (self.scroll_lock as u8) | (self.num_lock as u8) << 1 | (self.caps_lock as u8) << 2
+    }
+}
+
+/// Resets the keyboard on the first port of `controller` and starts queueing its key events.
+pub fn init<P: PortIo>(controller: &Ps2Controller<P>) -> Result<ScancodeSet, Ps2Error> {
+    controller.send_device(Ps2Port::First, RESET)?;
+    match controller.read_data_within(ps2::RESET_TIMEOUT_MS)? {
+        SELF_TEST_PASSED => {}
+        _ => {
+            return Err(Ps2Error::NoAck {
+                port: Ps2Port::First,
+                command: RESET,
+            })
+        }
+    }
+    controller.send_device(Ps2Port::First, DISABLE_SCANNING)?;
+
+    let set = scancode_set(controller)?;
+
+    controller.send_device(Ps2Port::First, SET_LEDS)?;
+    controller.send_device(Ps2Port::First, 0)?;
+
+    IRQ_STATE.inner.replace(Some((
+        Decoder::new(set),
+        ModifierState::new(),
+        LedUpdate::new(),
+    )));
+    EVENTS.clear();
+    controller.send_device(Ps2Port::First, ENABLE_SCANNING)?;
+    interrupt::register_irq_handler(IRQ, handle_irq);
+
+    Ok(set)
+}
+
+// Keeps sets 1 and 2, anything else is switched to set 2
+fn scancode_set<P: PortIo>(controller: &Ps2Controller<P>) -> Result<ScancodeSet, Ps2Error> {
+    controller.send_device(Ps2Port::First, SCANCODE_SET)?;
+    controller.send_device(Ps2Port::First, 0)?;
+
+    match controller.read_data()? {
+        1 => Ok(ScancodeSet::Set1),
+        2 => Ok(ScancodeSet::Set2),
+        _ => {
+            controller.send_device(Ps2Port::First, SCANCODE_SET)?;
+            controller.send_device(Ps2Port::First, 2)?;
+            Ok(ScancodeSet::Set2)
+        }
+    }
+}
+
+fn handle_irq() {
+    // The controller ports stay reserved by the `Ps2Controller`
+    let byte = Port::<u8>::new(ps2::DATA_PORT).read();
+
+    let mut state = IRQ_STATE.inner.borrow_mut();
+    let Some((decoder, modifiers, leds)) = state.as_mut() else {
+        return;
+    };
+
+    // The decoder drops the ACKs, so the LED update looks at them first
+    if let Some(next) = leds.response(byte, pit::uptime_ms()) {
+        send_byte(leds, next);
+    }
+
+    if let Some((key, pressed)) = decoder.feed(byte) {
+        if modifiers.update(key, pressed) {
+            if let Some(next) = leds.request(modifiers.leds(), pit::uptime_ms()) {
+                send_byte(leds, next);
+            }
+        }
+
+        EVENTS.push(KeyEvent {
+            key,
+            pressed,
+            modifiers: modifiers.modifiers(),
+        });
+    }
+}
+
+// Interrupts are off in the handler, so the wait for the input buffer is bounded
+fn send_byte(leds: &mut LedUpdate, byte: u8) {
+    let status = Port::<u8>::new(ps2::STATUS_COMMAND_PORT);
+
+    if (0..ps2::TIMEOUT_POLLS).any(|_| status.read() & ps2::STATUS_INPUT_FULL == 0) {
+        Port::<u8>::new(ps2::DATA_PORT).write(byte);
+    } else {
+        leds.abort();
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum LedStep {
+    #[default]
+    Idle,
+    // SET_LEDS was sent at the given uptime, the LED byte follows its ACK
+    CommandSent(u8, u32),
+    // The LED byte was sent at the given uptime
+    DataSent(u8, u32),
+}
+
+/// Sends `SET_LEDS` and its data byte one ACK at a time.
+///
+/// The ACKs arrive through IRQ1, so the caller feeds every byte to `response` and writes whatever
+/// it returns to the keyboard.
+#[derive(Debug, Default)]
+pub struct LedUpdate {
+    step: LedStep,
+    // Latest LEDs asked for while an update was in flight
+    queued: Option<u8>,
+}
+
+impl LedUpdate {
+    // An update without an answer for this long is dropped, e.g. a lost ACK
+    pub const TIMEOUT_MS: u32 = 100;
+
+    pub fn new() -> LedUpdate {
+        LedUpdate::default()
+    }
+
+    /// Asks for `leds` at uptime `now_ms`, returns the byte to send, if any.
+    pub fn request(&mut self, leds: u8, now_ms: u32) -> Option<u8> {
+        let sent = match self.step {
+            LedStep::Idle => None,
+            LedStep::CommandSent(_, sent) | LedStep::DataSent(_, sent) => Some(sent),
+        };
+
+        match sent {
+            Some(sent) if now_ms.wrapping_sub(sent) < Self::TIMEOUT_MS => {
+                self.queued = Some(leds);
+                None
+            }
+            _ => {
+                self.queued = None;
+                self.step = LedStep::CommandSent(leds, now_ms);
+                Some(SET_LEDS)
+            }
+        }
+    }
+
+    /// Takes a byte from the keyboard at uptime `now_ms`, returns the byte to send next, if any.
+    pub fn response(&mut self, byte: u8, now_ms: u32) -> Option<u8> {
+        match (self.step, byte) {
+            (LedStep::CommandSent(leds, _), ps2::ACK) => {
+                self.step = LedStep::DataSent(leds, now_ms);
+                Some(leds)
+            }
+            (LedStep::CommandSent(..), ps2::RESEND) => Some(SET_LEDS),
+            (LedStep::DataSent(..), ps2::ACK) => {
+                self.step = LedStep::Idle;
+                let leds = self.queued.take()?;
+                self.request(leds, now_ms)
+            }
+            (LedStep::DataSent(leds, _), ps2::RESEND) => Some(leds),
+            _ => None,
+        }
+    }
+
+    /// Drops the update in flight, e.g. when the keyboard could not take a byte.
+    pub fn abort(&mut self) {
+        self.step = LedStep::Idle;
+        self.queued = None;
+    }
+
+    /// Whether an update is waiting for an ACK.
+    pub fn in_flight(&self) -> bool {
+        self.step != LedStep::Idle
+    }
+}
+
+/// Returns the next key event, if any.
+///
+/// Shift+PageUp and Shift+PageDown are taken to scroll through the VGA scrollback.
+pub fn try_read_event() -> Option<KeyEvent> {
+    while let Some(event) = EVENTS.pop() {
+        if event.pressed && event.modifiers.shift {
+            let scroll = match event.key {
+                KeyCode::PageUp => Some(true),
+                KeyCode::PageDown => Some(false),
+                _ => None,
+            };
+
+            if let Some(up) = scroll {
+                if let Some(vga) = DISPLAY.borrow_mut().sinks.get_mut::<Terminal>() {
+                    match up {
+                        true => vga.scroll_view_up(VGA_HEIGHT - 1),
+                        false => vga.scroll_view_down(VGA_HEIGHT - 1),
+                    }
+                }
+                continue;
+            }
+        }
+
+        return Some(event);
+    }
+
+    None
+}
+
+/// Waits for the next key event, requires `init` to have succeeded.
+pub fn read_event() -> KeyEvent {
+    interrupt::wait_for(try_read_event)
+}
+
+/// Waits for a key press that produces a character in the current keymap.
+pub fn read_char() -> char {
+    loop {
+        let event = read_event();
+        if !event.pressed {
+            continue;
+        }
+
+        if let Some(character) = keymap().translate(event.key, &event.modifiers) {
+            return character;
+        }
+    }
+}
+
+pub fn keymap() -> &'static Keymap {
+    unsafe { &*KEYMAP.load(Ordering::Acquire) }
+}
+
+pub fn set_keymap(keymap: &'static Keymap) {
+    KEYMAP.store(keymap as *const Keymap as *mut Keymap, Ordering::Release);
+}
+
+fn set1_key(code: u8, extended: bool) -> Option<KeyCode> {
+    use KeyCode::*;
+
+    let key = match (extended, code) {
+        (false, 0x01) => Escape,
+        (false, 0x02) => Digit1,
+        (false, 0x03) => Digit2,
+        (false, 0x04) => Digit3,
+        (false, 0x05) => Digit4,
+        (false, 0x06) => Digit5,
+        (false, 0x07) => Digit6,
+        (false, 0x08) => Digit7,
+        (false, 0x09) => Digit8,
+        (false, 0x0A) => Digit9,
+        (false, 0x0B) => Digit0,
+        (false, 0x0C) => Minus,
+        (false, 0x0D) => Equal,
+        (false, 0x0E) => Backspace,
+        (false, 0x0F) => Tab,
+        (false, 0x10) => Q,
+        (false, 0x11) => W,
+        (false, 0x12) => E,
+        (false, 0x13) => R,
+        (false, 0x14) => T,
+        (false, 0x15) => Y,
+        (false, 0x16) => U,
+        (false, 0x17) => I,
+        (false, 0x18) => O,
+        (false, 0x19) => P,
+        (false, 0x1A) => LeftBracket,
+        (false, 0x1B) => RightBracket,
+        (false, 0x1C) => Enter,
+        (false, 0x1D) => LeftCtrl,
+        (false, 0x1E) => A,
+        (false, 0x1F) => S,
+        (false, 0x20) => D,
+        (false, 0x21) => F,
+        (false, 0x22) => G,
+        (false, 0x23) => H,
+        (false, 0x24) => J,
+        (false, 0x25) => K,
+        (false, 0x26) => L,
+        (false, 0x27) => Semicolon,
+        (false, 0x28) => Quote,
+        (false, 0x29) => Backquote,
+        (false, 0x2A) => LeftShift,
+        (false, 0x2B) => Backslash,
+        (false, 0x2C) => Z,
+        (false, 0x2D) => X,
+        (false, 0x2E) => C,
+        (false, 0x2F) => V,
+        (false, 0x30) => B,
+        (false, 0x31) => N,
+        (false, 0x32) => M,
+        (false, 0x33) => Comma,
+        (false, 0x34) => Period,
+        (false, 0x35) => Slash,
+        (false, 0x36) => RightShift,
+        (false, 0x37) => KeypadMultiply,
+        (false, 0x38) => LeftAlt,
+        (false, 0x39) => Space,
+        (false, 0x3A) => CapsLock,
+        (false, 0x3B) => F1,
+        (false, 0x3C) => F2,
+        (false, 0x3D) => F3,
+        (false, 0x3E) => F4,
+        (false, 0x3F) => F5,
+        (false, 0x40) => F6,
+        (false, 0x41) => F7,
+        (false, 0x42) => F8,
+        (false, 0x43) => F9,
+        (false, 0x44) => F10,
+        (false, 0x45) => NumLock,
+        (false, 0x46) => ScrollLock,
+        (false, 0x47) => Keypad7,
+        (false, 0x48) => Keypad8,
+        (false, 0x49) => Keypad9,
+        (false, 0x4A) => KeypadMinus,
+        (false, 0x4B) => Keypad4,
+        (false, 0x4C) => Keypad5,
+        (false, 0x4D) => Keypad6,
+        (false, 0x4E) => KeypadPlus,
+        (false, 0x4F) => Keypad1,
+        (false, 0x50) => Keypad2,
+        (false, 0x51) => Keypad3,
+        (false, 0x52) => Keypad0,
+        (false, 0x53) => KeypadPeriod,
+        (false, 0x56) => NonUsBackslash,
+        (false, 0x57) => F11,
+        (false, 0x58) => F12,
+        (true, 0x1C) => KeypadEnter,
+        (true, 0x1D) => RightCtrl,
+        (true, 0x35) => KeypadDivide,
+        // Follows the fake shift "E0 2A", which is dropped like every unknown code
+        (true, 0x37) => PrintScreen,
+        (true, 0x38) => RightAlt,
+        (true, 0x47) => Home,
+        (true, 0x48) => ArrowUp,
+        (true, 0x49) => PageUp,
+        (true, 0x4B) => ArrowLeft,
+        (true, 0x4D) => ArrowRight,
+        (true, 0x4F) => End,
+        (true, 0x50) => ArrowDown,
+        (true, 0x51) => PageDown,
+        (true, 0x52) => Insert,
+        (true, 0x53) => Delete,
+        (true, 0x5B) => LeftGui,
+        (true, 0x5C) => RightGui,
+        (true, 0x5D) => Menu,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+fn set2_key(code: u8, extended: bool) -> Option<KeyCode> {
+    use KeyCode::*;
+
+    let key = match (extended, code) {
+        (false, 0x01) => F9,
+        (false, 0x03) => F5,
+        (false, 0x04) => F3,
+        (false, 0x05) => F1,
+        (false, 0x06) => F2,
+        (false, 0x07) => F12,
+        (false, 0x09) => F10,
+        (false, 0x0A) => F8,
+        (false, 0x0B) => F6,
+        (false, 0x0C) => F4,
+        (false, 0x0D) => Tab,
+        (false, 0x0E) => Backquote,
+        (false, 0x11) => LeftAlt,
+        (false, 0x12) => LeftShift,
+        (false, 0x14) => LeftCtrl,
+        (false, 0x15) => Q,
+        (false, 0x16) => Digit1,
+        (false, 0x1A) => Z,
+        (false, 0x1B) => S,
+        (false, 0x1C) => A,
+        (false, 0x1D) => W,
+        (false, 0x1E) => Digit2,
+        (false, 0x21) => C,
+        (false, 0x22) => X,
+        (false, 0x23) => D,
+        (false, 0x24) => E,
+        (false, 0x25) => Digit4,
+        (false, 0x26) => Digit3,
+        (false, 0x29) => Space,
+        (false, 0x2A) => V,
+        (false, 0x2B) => F,
+        (false, 0x2C) => T,
+        (false, 0x2D) => R,
+        (false, 0x2E) => Digit5,
+        (false, 0x31) => N,
+        (false, 0x32) => B,
+        (false, 0x33) => H,
+        (false, 0x34) => G,
+        (false, 0x35) => Y,
+        (false, 0x36) => Digit6,
+        (false, 0x3A) => M,
+        (false, 0x3B) => J,
+        (false, 0x3C) => U,
+        (false, 0x3D) => Digit7,
+        (false, 0x3E) => Digit8,
+        (false, 0x41) => Comma,
+        (false, 0x42) => K,
+        (false, 0x43) => I,
+        (false, 0x44) => O,
+        (false, 0x45) => Digit0,
+        (false, 0x46) => Digit9,
+        (false, 0x49) => Period,
+        (false, 0x4A) => Slash,
+        (false, 0x4B) => L,
+        (false, 0x4C) => Semicolon,
+        (false, 0x4D) => P,
+        (false, 0x4E) => Minus,
+        (false, 0x52) => Quote,
+        (false, 0x54) => LeftBracket,
+        (false, 0x55) => Equal,
+        (false, 0x58) => CapsLock,
+        (false, 0x59) => RightShift,
+        (false, 0x5A) => Enter,
+        (false, 0x5B) => RightBracket,
+        (false, 0x5D) => Backslash,
+        (false, 0x61) => NonUsBackslash,
+        (false, 0x66) => Backspace,
+        (false, 0x69) => Keypad1,
+        (false, 0x6B) => Keypad4,
+        (false, 0x6C) => Keypad7,
+        (false, 0x70) => Keypad0,
+        (false, 0x71) => KeypadPeriod,
+        (false, 0x72) => Keypad2,
+        (false, 0x73) => Keypad5,
+        (false, 0x74) => Keypad6,
+        (false, 0x75) => Keypad8,
+        (false, 0x76) => Escape,
+        (false, 0x77) => NumLock,
+        (false, 0x78) => F11,
+        (false, 0x79) => KeypadPlus,
+        (false, 0x7A) => Keypad3,
+        (false, 0x7B) => KeypadMinus,
+        (false, 0x7C) => KeypadMultiply,
+        (false, 0x7D) => Keypad9,
+        (false, 0x7E) => ScrollLock,
+        (false, 0x83) => F7,
+        (true, 0x11) => RightAlt,
+        (true, 0x14) => RightCtrl,
+        (true, 0x1F) => LeftGui,
+        (true, 0x27) => RightGui,
+        (true, 0x2F) => Menu,
+        (true, 0x4A) => KeypadDivide,
+        (true, 0x5A) => KeypadEnter,
+        (true, 0x69) => End,
+        (true, 0x6B) => ArrowLeft,
+        (true, 0x6C) => Home,
+        (true, 0x70) => Insert,
+        (true, 0x71) => Delete,
+        (true, 0x72) => ArrowDown,
+        (true, 0x74) => ArrowRight,
+        (true, 0x75) => ArrowUp,
+        (true, 0x7A) => PageDown,
+        // Follows the fake shift "E0 12", which is dropped like every unknown code
+        (true, 0x7C) => PrintScreen,
+        (true, 0x7D) => PageUp,
+        _ => return None,
+    };
+
+    Some(key)
+}
@@ -0,0 +1,200 @@
+use crate::io::keyboard::{KeyCode, Modifiers};
+
+/// Keyboard layout, mapping keys to the characters printed on them.
+pub struct Keymap {
+    pub name: &'static str,
+    // Unshifted and shifted character of the layout specific keys
+    symbols: &'static [(KeyCode, char, char)],
+    // Characters typed while AltGr is held
+    alt_gr: &'static [(KeyCode, char)],
+}
+
+pub static US: Keymap = Keymap {
+    name: "us",
+    symbols: &[
+        (KeyCode::Backquote, '`', '~'),
+        (KeyCode::Digit1, '1', '!'),
+        (KeyCode::Digit2, '2', '@'),
+        (KeyCode::Digit3, '3', '#'),
+        (KeyCode::Digit4, '4', '$'),
+        (KeyCode::Digit5, '5', '%'),
+        (KeyCode::Digit6, '6', '^'),
+        (KeyCode::Digit7, '7', '&'),
+        (KeyCode::Digit8, '8', '*'),
+        (KeyCode::Digit9, '9', '('),
+        (KeyCode::Digit0, '0', ')'),
+        (KeyCode::Minus, '-', '_'),
+        (KeyCode::Equal, '=', '+'),
+        (KeyCode::LeftBracket, '[', '{'),
+        (KeyCode::RightBracket, ']', '}'),
+        (KeyCode::Backslash, '\\', '|'),
+        (KeyCode::Semicolon, ';', ':'),
+        (KeyCode::Quote, '\'', '"'),
+        (KeyCode::Comma, ',', '<'),
+        (KeyCode::Period, '.', '>'),
+        (KeyCode::Slash, '/', '?'),
+        (KeyCode::NonUsBackslash, '\\', '|'),
+    ],
+    alt_gr: &[],
+};
+
+pub static UK: Keymap = Keymap {
+    name: "uk",
+    symbols: &[
+        (KeyCode::Backquote, '`', '¬'),
+        (KeyCode::Digit1, '1', '!'),
+        (KeyCode::Digit2, '2', '"'),
+        (KeyCode::Digit3, '3', '£'),
+        (KeyCode::Digit4, '4', '$'),
+        (KeyCode::Digit5, '5', '%'),
+        (KeyCode::Digit6, '6', '^'),
+        (KeyCode::Digit7, '7', '&'),
+        (KeyCode::Digit8, '8', '*'),
+        (KeyCode::Digit9, '9', '('),
+        (KeyCode::Digit0, '0', ')'),
+        (KeyCode::Minus, '-', '_'),
+        (KeyCode::Equal, '=', '+'),
+        (KeyCode::LeftBracket, '[', '{'),
+        (KeyCode::RightBracket, ']', '}'),
+        (KeyCode::Backslash, '#', '~'),
+        (KeyCode::Semicolon, ';', ':'),
+        (KeyCode::Quote, '\'', '@'),
+        (KeyCode::Comma, ',', '<'),
+        (KeyCode::Period, '.', '>'),
+        (KeyCode::Slash, '/', '?'),
+        (KeyCode::NonUsBackslash, '\\', '|'),
+    ],
+    alt_gr: &[
+        (KeyCode::Backquote, '¦'),
+        (KeyCode::Digit4, '€'),
+        (KeyCode::A, 'á'),
+        (KeyCode::E, 'é'),
+        (KeyCode::I, 'í'),
+        (KeyCode::O, 'ó'),
+        (KeyCode::U, 'ú'),
+    ],
+};
+
+pub static KEYMAPS: [&Keymap; 2] = [&US, &UK];
+
+/// Looks a keymap up by its name, as used by `keymap=uk` on the command line.
+pub fn by_name(name: &str) -> Option<&'static Keymap> {
+    KEYMAPS
+        .iter()
+        .copied()
+        .find(|keymap| keymap.name.eq_ignore_ascii_case(name))
+}
+
+impl Keymap {
+    /// Returns the character `key` types with `modifiers`, control characters included.
+    pub fn translate(&self, key: KeyCode, modifiers: &Modifiers) -> Option<char> {
+        if modifiers.alt_gr {
+            return self
+                .alt_gr
+                .iter()
+                .find(|(alt_gr_key, _)| *alt_gr_key == key)
+                .map(|&(_, character)| character);
+        }
+
+        if let Some(letter) = letter(key) {
+            // Ctrl+A is 0x01 up to Ctrl+Z at 0x1A
+            if modifiers.ctrl {
+                return Some((letter as u8 - b'a' + 1) as char);
+            }
+
+            return Some(match modifiers.shift != modifiers.caps_lock {
+                true => letter.to_ascii_uppercase(),
+                false => letter,
+            });
+        }
+
+        if let Some(&(_, unshifted, shifted)) = self
+            .symbols
+            .iter()
+            .find(|(symbol_key, ..)| *symbol_key == key)
+        {
+            return Some(match modifiers.shift {
+                true => shifted,
+                false => unshifted,
+            });
+        }
+
+        if modifiers.num_lock {
+            if let Some(digit) = keypad_digit(key) {
+                return Some(digit);
+            }
+        }
+
+        let character = match key {
+            KeyCode::Space => ' ',
+            KeyCode::Enter | KeyCode::KeypadEnter => '\n',
+            KeyCode::Tab => '\t',
+            KeyCode::Backspace => '\x08',
+            KeyCode::Escape => '\x1b',
+            KeyCode::KeypadDivide => '/',
+            KeyCode::KeypadMultiply => '*',
+            KeyCode::KeypadMinus => '-',
+            KeyCode::KeypadPlus => '+',
+            _ => return None,
+        };
+
+        Some(character)
+    }
+}
+
+fn letter(key: KeyCode) -> Option<char> {
+    use KeyCode::*;
+
+    let letter = match key {
+        A => 'a',
+        B => 'b',
+        C => 'c',
+        D => 'd',
+        E => 'e',
+        F => 'f',
+        G => 'g',
+        H => 'h',
+        I => 'i',
+        J => 'j',
+        K => 'k',
+        L => 'l',
+        M => 'm',
+        N => 'n',
+        O => 'o',
+        P => 'p',
+        Q => 'q',
+        R => 'r',
+        S => 's',
+        T => 't',
+        U => 'u',
+        V => 'v',
+        W => 'w',
+        X => 'x',
+        Y => 'y',
+        Z => 'z',
+        _ => return None,
+    };
+
+    Some(letter)
+}
+
+fn keypad_digit(key: KeyCode) -> Option<char> {
+    use KeyCode::*;
+
+    let digit = match key {
+        Keypad0 => '0',
+        Keypad1 => '1',
+        Keypad2 => '2',
+        Keypad3 => '3',
+        Keypad4 => '4',
+        Keypad5 => '5',
+        Keypad6 => '6',
+        Keypad7 => '7',
+        Keypad8 => '8',
+        Keypad9 => '9',
+        KeypadPeriod => '.',
+        _ => return None,
+    };
+
+    Some(digit)
+}
@@ -1,37 +1,49 @@
+pub mod ansi; // Contains the VT100 escape sequence parser
+pub mod cp437; // Contains the code page 437 character table
+pub mod debugcon; // Contains the Bochs/QEMU port 0xE9 debug console
+#[cfg(test)]
+pub mod fake_port; // Contains fake devices for host tests
+pub mod framebuffer; // Contains framebuffer console related functions
+pub mod keyboard; // Contains the PS/2 keyboard driver
+pub mod keymap; // Contains keyboard layouts
+pub mod mouse; // Contains the PS/2 mouse driver
 pub mod port_manager; // Contains Port related functions
+pub mod ps2; // Contains the 8042 PS/2 controller driver
 pub mod rtc; // Contains RTC related functions
 pub mod serial; // Contains Serial related functions
+pub mod serial_config; // Contains Serial port and line settings
+pub mod sink; // Contains the console sink registry
 pub mod vga; // Contains VGA related functions
 
 use core::cell::RefCell;
+use log::warn;
 
-use port_manager::PortManager;
-use serial::Serial;
+use crate::boot_info::{FramebufferInfo, FramebufferKind};
+use crate::cmdline;
+use crate::logger;
+use framebuffer::FramebufferConsole;
+use port_manager::{PortIo, PortManager, PortRead};
+use serial::{Serial, SerialInitError};
+use serial_config::{parse_console, ComPort, SerialConfig};
+use sink::{ConsoleSink, RegistryFull, SinkCell, SinkId, SinkRegistry};
 use vga::{Terminal, VgaColor};
 
 pub static DISPLAY: Display = Display {
     inner: RefCell::new(DisplayInner {
-        vga: None,
-        serial: None,
+        sinks: SinkRegistry::new(),
+        debug_console: None,
     }),
 };
 
+// Storage of the sinks that are set up before the heap
+static VGA: SinkCell<Terminal> = SinkCell::new();
+static FRAMEBUFFER: SinkCell<FramebufferConsole> = SinkCell::new();
+static SERIAL: SinkCell<Serial> = SinkCell::new();
+
 #[macro_export]
 macro_rules! print {
     ( $ ( $arg:tt )* ) => {
-        #[allow(clippy::macro_metavars_in_unsafe)]
-        #[allow(unused_unsafe)]
-        unsafe {
-            use core::fmt::Write;
-            let mut display = $crate::io::DISPLAY.borrow_mut();
-            if let Some(vga) = &mut display.vga {
-                write!(vga, $($arg)*).expect("Not Written to VGA");
-            }
-
-            if let Some(serial) = &mut display.serial {
-                write!(serial, $($arg)*).expect("Not Written to Serial")
-            }
-        }
+        $crate::io::DISPLAY.borrow_mut().sinks.print(format_args!($($arg)*))
     };
 }
 
@@ -43,6 +55,17 @@ macro_rules! println {
     };
 }
 
+/// Writes to the display like `print!`, for code that takes a `core::fmt::Write`.
+pub struct DisplayWriter;
+
+impl core::fmt::Write for DisplayWriter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        print!("{}", s);
+
+        Ok(())
+    }
+}
+
 pub struct Display {
     inner: RefCell<DisplayInner>,
 }
@@ -58,25 +81,207 @@ impl core::ops::Deref for Display {
 }
 
 pub struct DisplayInner {
-    pub vga: Option<Terminal>,
-    pub serial: Option<Serial>,
+    pub sinks: SinkRegistry,
+    // Serial port for interactive use, it is not a sink so output does not go there
+    pub debug_console: Option<Serial>,
+}
+
+/// Adds `sink` to the display and writes the log messages so far to it.
+pub fn register_sink(sink: &'static mut dyn ConsoleSink) -> Result<SinkId, RegistryFull> {
+    let name = sink.name();
+    let id = DISPLAY.borrow_mut().sinks.register(sink)?;
+
+    let level = logger::apply_sink_level(id);
+    logger::replay(id);
+    if let Err(e) = level {
+        warn!("Invalid level for the {} sink: {}", name, e);
+    }
+
+    Ok(id)
+}
+
+const DEFAULT_SCROLLBACK_LINES: usize = 500;
+// 160 bytes a line, keeps the history around 1.6 MiB of heap
+const MAX_SCROLLBACK_LINES: usize = 10_000;
+
+/// Keeps VGA output that scrolled off the screen, `scrollback=<lines>` sets how much.
+///
+/// The history lives on the heap, so this has to wait for the allocator.
+pub fn enable_scrollback(cmdline: Option<&str>) {
+    let lines = match cmdline.and_then(|cmdline| cmdline::get(cmdline, "scrollback")) {
+        Some(lines) => lines.parse().unwrap_or_else(|_| {
+            warn!(
+                "Invalid scrollback option, using {} lines",
+                DEFAULT_SCROLLBACK_LINES
+            );
+            DEFAULT_SCROLLBACK_LINES
+        }),
+        None => DEFAULT_SCROLLBACK_LINES,
+    };
+    if lines > MAX_SCROLLBACK_LINES {
+        warn!(
+            "Scrollback of {} lines is too large, using {} lines",
+            lines, MAX_SCROLLBACK_LINES
+        );
+    }
+
+    if let Some(vga) = DISPLAY.borrow_mut().sinks.get_mut::<Terminal>() {
+        vga.enable_scrollback(lines.min(MAX_SCROLLBACK_LINES));
+    }
+}
+
+// A graphical framebuffer the console can't draw on leaves the serial console as the only output,
+// the VGA text buffer isn't shown then.
+fn init_screen(
+    port_manager: &'static PortManager,
+    framebuffer_info: Option<&FramebufferInfo>,
+) -> Option<&'static mut dyn ConsoleSink> {
+    let graphical = framebuffer_info
+        .filter(|framebuffer_info| framebuffer_info.kind != FramebufferKind::EgaText);
+
+    match graphical {
+        Some(framebuffer_info) => match FramebufferConsole::new(framebuffer_info) {
+            Ok(mut framebuffer) => {
+                framebuffer
+                    .init()
+                    .expect("Unable to initialize Framebuffer display");
+                Some(
+                    FRAMEBUFFER
+                        .put(framebuffer)
+                        .unwrap_or_else(|_| panic!("Framebuffer already initialized")),
+                )
+            }
+            Err(e) => {
+                warn!(
+                    "Framebuffer console unavailable, using the serial console: {}",
+                    e
+                );
+                None
+            }
+        },
+        None => {
+            let mut vga = Terminal::new(VgaColor::LightGrey, VgaColor::Black);
+            vga.init().expect("Unable to initialize VGA display");
+            if let Err(e) = vga.enable_cursor(port_manager) {
+                use core::fmt::Write;
+                let _ = writeln!(vga, "VGA cursor unavailable: {}", e);
+            }
+            Some(
+                VGA.put(vga)
+                    .unwrap_or_else(|_| panic!("VGA already initialized")),
+            )
+        }
+    }
 }
 
-pub fn init_display(port_manager: &mut PortManager) {
-    let mut vga = Terminal::new(VgaColor::LightGrey, VgaColor::Black);
-    vga.init().expect("Unable to initialize VGA display");
+// A missing UART, or one failing its loopback test, leaves the other sinks and the dmesg as the
+// record of the boot.
+fn init_serial_console<P, S, F>(
+    cmdline: &'static str,
+    cell: &'static SinkCell<Serial<P, S>>,
+    open: F,
+) -> Option<SinkId>
+where
+    P: PortIo + 'static,
+    S: PortRead + 'static,
+    F: FnOnce(ComPort, SerialConfig) -> Result<Serial<P, S>, SerialInitError>,
+{
+    let console = cmdline::get(cmdline, "console").map(parse_console);
+    let (com, config) = match console {
+        Some(Ok(console)) => console,
+        Some(Err(e)) => {
+            warn!("Invalid console option, using COM1: {}", e);
+            (ComPort::Com1, SerialConfig::default())
+        }
+        None => (ComPort::Com1, SerialConfig::default()),
+    };
 
-    let serial = Serial::new(port_manager).expect("Unable to create Serial");
-    serial.init().expect("Unable to initialize Serial Display");
+    let serial = match open(com, config) {
+        Ok(serial) => serial,
+        Err(e) => {
+            warn!("Serial console unavailable: {}", e);
+            return None;
+        }
+    };
+    let serial = cell
+        .put(serial)
+        .unwrap_or_else(|_| panic!("Serial already initialized"));
+    match register_sink(serial) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!("Serial console unavailable: {}", e);
+            None
+        }
+    }
+}
+
+// The boot loader either left the VGA text mode active or set up a graphical framebuffer.
+// The serial console is picked with `console=com1,38400,8n1`, `debug_console=com2,...` adds a
+// second port for interactive use.
+pub fn init_display(
+    port_manager: &'static PortManager,
+    framebuffer_info: Option<&FramebufferInfo>,
+    cmdline: Option<&'static str>,
+) {
+    if let Some(screen) = init_screen(port_manager, framebuffer_info) {
+        register_sink(screen).expect("No room for the screen sink");
+    }
+
+    let cmdline = cmdline.unwrap_or("");
+    init_serial_console(cmdline, &SERIAL, |com, config| {
+        Serial::open(port_manager, com, config)
+    });
 
-    let mut display = DISPLAY.borrow_mut();
-    display.vga = Some(vga);
-    display.serial = Some(serial);
+    if let Some(debug_console) = cmdline::get(cmdline, "debug_console") {
+        let debug_console = parse_console(debug_console)
+            .map_err(SerialInitError::Config)
+            .and_then(|(com, config)| Serial::open(port_manager, com, config));
+        match debug_console {
+            Ok(debug_console) => DISPLAY.borrow_mut().debug_console = Some(debug_console),
+            Err(e) => warn!("Debug console unavailable: {}", e),
+        }
+    }
 }
 
-pub fn exit(code: u8) {
-    let display = DISPLAY.borrow();
-    if let Some(serial) = &display.serial {
-        serial.exit.writeb(code);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::fake_port::{FakeBus, FakePort};
+    use crate::io::serial::{SerialPorts, SerialRx};
+
+    static RX: SerialRx = SerialRx::new();
+    static CONSOLE: SinkCell<Serial<FakePort, FakePort>> = SinkCell::new();
+
+    #[test]
+    fn serial_console_tolerates_missing_uart() {
+        // Nothing on the bus, the loopback test reads back 0xFF
+        let bus = FakeBus::new();
+        let mut opened = None;
+
+        let id = init_serial_console("console=com1,9600", &CONSOLE, |com, config| {
+            opened = Some((com, config.baud_rate));
+            let port = |offset| bus.port(com.base() + offset);
+            let serial = Serial::with_ports(
+                SerialPorts {
+                    data: port(0),
+                    enable_interrupt: port(1),
+                    interrupt_id_fifo_control: port(2),
+                    line_control: port(3),
+                    modem_control: port(4),
+                    line_status: port(5),
+                    modem_status: port(6),
+                    scratch: port(7),
+                },
+                config,
+                &RX,
+            );
+            let serial = serial.init().map(|()| serial);
+            assert!(matches!(serial, Err(SerialInitError::Loopback)));
+            serial
+        });
+
+        assert_eq!(id, None);
+        assert_eq!(opened, Some((ComPort::Com1, 9600)));
+        assert!(!bus.log().is_empty());
     }
 }
@@ -0,0 +1,288 @@
+use core::cell::RefCell;
+
+use crate::interrupt;
+use crate::io::port_manager::{Port, PortIo};
+use crate::io::ps2::{self, Ps2Controller, Ps2Error, Ps2Port};
+use crate::io::vga::{Terminal, VGA_HEIGHT, VGA_WIDTH};
+use crate::io::DISPLAY;
+use crate::util::ring_buffer::RingBuffer;
+
+pub const IRQ: u8 = 12;
+pub const EVENT_BUFFER_SIZE: usize = 64;
+
+// Mouse commands
+const SET_RESOLUTION: u8 = 0xE8;
+const GET_ID: u8 = 0xF2;
+const SET_SAMPLE_RATE: u8 = 0xF3;
+const ENABLE_REPORTING: u8 = 0xF4;
+const DISABLE_REPORTING: u8 = 0xF5;
+const RESET: u8 = 0xFF;
+
+const SELF_TEST_PASSED: u8 = 0xAA;
+const ID_STANDARD: u8 = 0x00;
+const ID_INTELLIMOUSE: u8 = 0x03;
+// Sample rates that unlock the scroll wheel of an IntelliMouse
+const INTELLIMOUSE_KNOCK: [u8; 3] = [200, 100, 80];
+
+// First byte of a packet
+const PACKET_LEFT: u8 = 0x01;
+const PACKET_RIGHT: u8 = 0x02;
+const PACKET_MIDDLE: u8 = 0x04;
+// Always set, used to find the start of a packet
+const PACKET_ALWAYS_ONE: u8 = 0x08;
+const PACKET_X_SIGN: u8 = 0x10;
+const PACKET_Y_SIGN: u8 = 0x20;
+const PACKET_OVERFLOW: u8 = 0xC0;
+
+static EVENTS: RingBuffer<MouseEvent, EVENT_BUFFER_SIZE> = RingBuffer::new();
+static IRQ_STATE: IrqState = IrqState {
+    inner: RefCell::new(None),
+};
+
+// Packet decoder, only touched by the IRQ handler once it is registered
+struct IrqState {
+    inner: RefCell<Option<PacketDecoder>>,
+}
+
+unsafe impl Sync for IrqState {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseKind {
+    Standard,
+    // Sends a fourth packet byte with the wheel movement
+    IntelliMouse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleRate {
+    Hz10 = 10,
+    Hz20 = 20,
+    Hz40 = 40,
+    Hz60 = 60,
+    Hz80 = 80,
+    Hz100 = 100,
+    Hz200 = 200,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    CountsPerMm1 = 0,
+    CountsPerMm2 = 1,
+    CountsPerMm4 = 2,
+    CountsPerMm8 = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseConfig {
+    pub sample_rate: SampleRate,
+    pub resolution: Resolution,
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        MouseConfig {
+            sample_rate: SampleRate::Hz100,
+            resolution: Resolution::CountsPerMm4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Buttons {
+    pub left: bool,
+    pub right: bool,
+    pub middle: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    /// Movement to the right
+    pub dx: i16,
+    /// Movement up, away from the user
+    pub dy: i16,
+    /// Wheel clicks, negative when scrolled up
+    pub wheel: i8,
+    pub buttons: Buttons,
+}
+
+/// Assembles the bytes from the mouse into packets.
+pub struct PacketDecoder {
+    packet: [u8; 4],
+    len: usize,
+    size: usize,
+}
+
+impl PacketDecoder {
+    pub const fn new(kind: MouseKind) -> PacketDecoder {
+        PacketDecoder {
+            packet: [0; 4],
+            len: 0,
+            size: match kind {
+                MouseKind::Standard => 3,
+                MouseKind::IntelliMouse => 4,
+            },
+        }
+    }
+
+    /// Feeds the next byte from the mouse, returns the event once its packet is complete.
+    ///
+    /// Bytes are dropped until one looks like the first byte of a packet, which gets the decoder
+    /// back in step after a lost byte.
+    pub fn feed(&mut self, byte: u8) -> Option<MouseEvent> {
+        if self.len == 0 && byte & PACKET_ALWAYS_ONE == 0 {
+            return None;
+        }
+
+        self.packet[self.len] = byte;
+        self.len += 1;
+        if self.len < self.size {
+            return None;
+        }
+        self.len = 0;
+
+        let [flags, x, y, extra] = self.packet;
+        // The counters overflowed, the movement is meaningless
+        if flags & PACKET_OVERFLOW != 0 {
+            return None;
+        }
+
+        Some(MouseEvent {
+            dx: nine_bit(x, flags & PACKET_X_SIGN != 0),
+            dy: nine_bit(y, flags & PACKET_Y_SIGN != 0),
+            // The wheel is a 4 bit two's complement value
+            wheel: match self.size {
+                4 => ((extra << 4) as i8) >> 4,
+                _ => 0,
+            },
+            buttons: Buttons {
+                left: flags & PACKET_LEFT != 0,
+                right: flags & PACKET_RIGHT != 0,
+                middle: flags & PACKET_MIDDLE != 0,
+            },
+        })
+    }
+}
+
+// Movement is a 9 bit two's complement value, with the sign in the first packet byte
+fn nine_bit(low: u8, negative: bool) -> i16 {
+    match negative {
+        true => low as i16 - 0x100,
+        false => low as i16,
+    }
+}
+
+/// Resets the mouse on the second port of `controller`, applies `config` and starts queueing its
+/// events.
+pub fn init<P: PortIo>(
+    controller: &Ps2Controller<P>,
+    config: MouseConfig,
+) -> Result<MouseKind, Ps2Error> {
+    controller.enable_second_port()?;
+
+    controller.send_device(Ps2Port::Second, RESET)?;
+    let self_test = controller.read_data_within(ps2::RESET_TIMEOUT_MS)?;
+    let id = controller.read_data()?;
+    if self_test != SELF_TEST_PASSED || id != ID_STANDARD {
+        return Err(Ps2Error::NoAck {
+            port: Ps2Port::Second,
+            command: RESET,
+        });
+    }
+    controller.send_device(Ps2Port::Second, DISABLE_REPORTING)?;
+
+    for rate in INTELLIMOUSE_KNOCK {
+        set_sample_rate(controller, rate)?;
+    }
+    controller.send_device(Ps2Port::Second, GET_ID)?;
+    let kind = match controller.read_data()? {
+        ID_INTELLIMOUSE => MouseKind::IntelliMouse,
+        _ => MouseKind::Standard,
+    };
+
+    set_sample_rate(controller, config.sample_rate as u8)?;
+    controller.send_device(Ps2Port::Second, SET_RESOLUTION)?;
+    controller.send_device(Ps2Port::Second, config.resolution as u8)?;
+
+    IRQ_STATE.inner.replace(Some(PacketDecoder::new(kind)));
+    EVENTS.clear();
+    controller.send_device(Ps2Port::Second, ENABLE_REPORTING)?;
+    interrupt::register_irq_handler(IRQ, handle_irq);
+
+    Ok(kind)
+}
+
+fn set_sample_rate<P: PortIo>(controller: &Ps2Controller<P>, rate: u8) -> Result<(), Ps2Error> {
+    controller.send_device(Ps2Port::Second, SET_SAMPLE_RATE)?;
+    controller.send_device(Ps2Port::Second, rate)
+}
+
+fn handle_irq() {
+    // The controller ports stay reserved by the `Ps2Controller`
+    let byte = Port::<u8>::new(ps2::DATA_PORT).read();
+
+    if let Some(decoder) = IRQ_STATE.inner.borrow_mut().as_mut() {
+        if let Some(event) = decoder.feed(byte) {
+            EVENTS.push(event);
+        }
+    }
+}
+
+pub fn try_read_event() -> Option<MouseEvent> {
+    EVENTS.pop()
+}
+
+/// Waits for the next mouse event, requires `init` to have succeeded.
+pub fn read_event() -> MouseEvent {
+    interrupt::wait_for(try_read_event)
+}
+
+/// Mouse pointer over the VGA text screen, moved by feeding it the mouse events.
+pub struct VgaPointer {
+    x: i32,
+    y: i32,
+}
+
+impl Default for VgaPointer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VgaPointer {
+    // Mouse counts per character cell, cells are twice as high as wide
+    const COUNTS_PER_COLUMN: i32 = 8;
+    const COUNTS_PER_ROW: i32 = 16;
+
+    /// Starts in the middle of the screen.
+    pub fn new() -> VgaPointer {
+        VgaPointer {
+            x: VGA_WIDTH as i32 * Self::COUNTS_PER_COLUMN / 2,
+            y: VGA_HEIGHT as i32 * Self::COUNTS_PER_ROW / 2,
+        }
+    }
+
+    /// Moves the pointer, returns its (row, column).
+    pub fn apply(&mut self, event: &MouseEvent) -> (usize, usize) {
+        self.x =
+            (self.x + event.dx as i32).clamp(0, VGA_WIDTH as i32 * Self::COUNTS_PER_COLUMN - 1);
+        // The mouse counts up when moving away from the user, rows count down the screen
+        self.y = (self.y - event.dy as i32).clamp(0, VGA_HEIGHT as i32 * Self::COUNTS_PER_ROW - 1);
+
+        self.position()
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (
+            (self.y / Self::COUNTS_PER_ROW) as usize,
+            (self.x / Self::COUNTS_PER_COLUMN) as usize,
+        )
+    }
+
+    /// Moves the pointer and draws it on the VGA terminal.
+    pub fn update(&mut self, event: &MouseEvent) {
+        let (row, column) = self.apply(event);
+        if let Some(vga) = DISPLAY.borrow_mut().sinks.get_mut::<Terminal>() {
+            vga.show_pointer(row, column);
+        }
+    }
+}
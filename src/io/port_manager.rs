@@ -1,73 +1,509 @@
 use core::arch::asm;
-use hashbrown::HashSet;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use thiserror_no_std::Error;
+
+// One bit per port of the 16 bit I/O space
+const PORT_COUNT: usize = 0x10000;
+pub const MAX_RESERVATIONS: usize = 64;
+
+/// Port manager shared by every driver, usable before the heap is initialized.
+pub static PORT_MANAGER: PortManager = PortManager::new();
+
+#[derive(Debug, Error)]
+pub enum PortError {
+    #[error("Empty port range")]
+    Empty,
+    #[error("Port range {start:#x}+{len:#x} exceeds the I/O space")]
+    OutOfRange { start: u16, len: u16 },
+    #[error("Port {port:#x} is held by {owner}")]
+    Conflict { port: u16, owner: &'static str },
+    #[error("Too many port reservations")]
+    TooManyReservations,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Reservation {
+    pub start: u16,
+    pub len: u16,
+    pub owner: &'static str,
+}
+
+impl Reservation {
+    fn contains(&self, port: u16) -> bool {
+        port >= self.start && port - self.start < self.len
+    }
+}
 
 pub struct PortManager {
-    allocated_ports: HashSet<u16>,
+    inner: RefCell<PortManagerInner>,
+}
+
+unsafe impl Sync for PortManager {}
+
+struct PortManagerInner {
+    allocated_ports: [u32; PORT_COUNT / 32],
+    reservations: [Option<Slot>; MAX_RESERVATIONS],
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+    reservation: Reservation,
+    // The range and every port taken from it, the ports are released once all of them are gone
+    holders: u16,
+}
+
+impl PortManagerInner {
+    fn is_allocated(&self, port: u16) -> bool {
+        let port = port as usize;
+        self.allocated_ports[port / 32] & (1 << (port % 32)) != 0
+    }
+
+    fn slot_mut(&mut self, start: u16) -> Option<&mut Slot> {
+        self.reservations
+            .iter_mut()
+            .flatten()
+            .find(|slot| slot.reservation.start == start)
+    }
+
+    fn set_allocated(&mut self, port: u16, allocated: bool) {
+        let port = port as usize;
+        if allocated {
+            self.allocated_ports[port / 32] |= 1 << (port % 32);
+        } else {
+            self.allocated_ports[port / 32] &= !(1 << (port % 32));
+        }
+    }
 }
 
 impl PortManager {
     #[allow(clippy::new_without_default)]
-    pub fn new() -> PortManager {
+    pub const fn new() -> PortManager {
         PortManager {
-            allocated_ports: HashSet::new(),
+            inner: RefCell::new(PortManagerInner {
+                allocated_ports: [0; PORT_COUNT / 32],
+                reservations: [None; MAX_RESERVATIONS],
+            }),
+        }
+    }
+
+    /// Reserves the `len` ports starting at `start` until the returned range and every port taken
+    /// from it are dropped.
+    pub fn request_range(
+        &self,
+        start: u16,
+        len: u16,
+        owner: &'static str,
+    ) -> Result<PortRange<'_>, PortError> {
+        use PortError::*;
+
+        if len == 0 {
+            return Err(Empty);
+        }
+        let end = start
+            .checked_add(len - 1)
+            .ok_or(OutOfRange { start, len })?;
+
+        let mut inner = self.inner.borrow_mut();
+        if let Some(port) = (start..=end).find(|&port| inner.is_allocated(port)) {
+            let owner = inner
+                .reservations
+                .iter()
+                .flatten()
+                .find(|slot| slot.reservation.contains(port))
+                .map_or("unknown", |slot| slot.reservation.owner);
+            return Err(Conflict { port, owner });
+        }
+
+        let slot = inner
+            .reservations
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(TooManyReservations)?;
+        *slot = Some(Slot {
+            reservation: Reservation { start, len, owner },
+            holders: 1,
+        });
+
+        for port in start..=end {
+            inner.set_allocated(port, true);
+        }
+
+        Ok(PortRange {
+            manager: self,
+            start,
+            len,
+        })
+    }
+
+    /// Returns the owner of the range containing `port`.
+    pub fn owner(&self, port: u16) -> Option<&'static str> {
+        self.reservations()
+            .find(|reservation| reservation.contains(port))
+            .map(|reservation| reservation.owner)
+    }
+
+    pub fn reservations(&self) -> impl Iterator<Item = Reservation> {
+        let reservations = self.inner.borrow().reservations;
+        reservations
+            .into_iter()
+            .flatten()
+            .map(|slot| slot.reservation)
+    }
+
+    fn hold(&self, start: u16) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(slot) = inner.slot_mut(start) {
+            slot.holders += 1;
+        }
+    }
+
+    fn release(&self, start: u16) {
+        let mut inner = self.inner.borrow_mut();
+        let Some(slot) = inner.slot_mut(start) else {
+            return;
+        };
+        slot.holders -= 1;
+        if slot.holders > 0 {
+            return;
+        }
+
+        let Reservation { start, len, .. } = slot.reservation;
+        for port in start..=start + (len - 1) {
+            inner.set_allocated(port, false);
+        }
+        if let Some(slot) = inner
+            .reservations
+            .iter_mut()
+            .find(|slot| matches!(slot, Some(slot) if slot.reservation.start == start))
+        {
+            *slot = None;
+        }
+    }
+}
+
+/// Contiguous ports reserved from a [`PortManager`].
+///
+/// The ports taken from the range keep the reservation too, it is released once the range and
+/// all of its ports are dropped.
+pub struct PortRange<'a> {
+    manager: &'a PortManager,
+    start: u16,
+    len: u16,
+}
+
+impl PortRange<'_> {
+    pub fn start(&self) -> u16 {
+        self.start
+    }
+
+    pub fn len(&self) -> u16 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Keeps the ports reserved for the rest of the kernel's lifetime.
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+
+    fn addr<T: PortWidth>(&self, offset: u16) -> u16 {
+        let width = core::mem::size_of::<T>() as u16;
+        assert!(
+            offset < self.len && self.len - offset >= width,
+            "Port offset {:#x} outside of range {:#x}+{:#x}",
+            offset,
+            self.start,
+            self.len
+        );
+
+        self.start + offset
+    }
+}
+
+impl PortRange<'static> {
+    /// Returns the port at `offset` into the range.
+    ///
+    /// Panics if a `T` wide access at `offset` does not fit into the range.
+    pub fn port<T: PortWidth>(&self, offset: u16) -> Port<T> {
+        Port {
+            addr: self.addr::<T>(offset),
+            width: PhantomData,
+            _hold: Some(self.hold()),
+        }
+    }
+
+    pub fn read_only_port<T: PortWidth>(&self, offset: u16) -> ReadOnlyPort<T> {
+        ReadOnlyPort {
+            addr: self.addr::<T>(offset),
+            width: PhantomData,
+            _hold: Some(self.hold()),
+        }
+    }
+
+    pub fn write_only_port<T: PortWidth>(&self, offset: u16) -> WriteOnlyPort<T> {
+        WriteOnlyPort {
+            addr: self.addr::<T>(offset),
+            width: PhantomData,
+            _hold: Some(self.hold()),
+        }
+    }
+
+    fn hold(&self) -> Hold {
+        self.manager.hold(self.start);
+        Hold {
+            manager: self.manager,
+            start: self.start,
         }
     }
+}
+
+impl Drop for PortRange<'_> {
+    fn drop(&mut self) {
+        self.manager.release(self.start);
+    }
+}
+
+// Share of a port in the reservation of its range
+struct Hold {
+    manager: &'static PortManager,
+    start: u16,
+}
+
+impl Drop for Hold {
+    fn drop(&mut self) {
+        self.manager.release(self.start);
+    }
+}
+
+/// Access width of a port, implemented for `u8`, `u16` and `u32`.
+pub trait PortWidth: Copy {
+    #[allow(clippy::missing_safety_doc)]
+    unsafe fn read_from(addr: u16) -> Self;
+
+    #[allow(clippy::missing_safety_doc)]
+    unsafe fn write_to(addr: u16, val: Self);
+
+    /// Fills `buf` with consecutive reads from the port (`rep ins`).
+    #[allow(clippy::missing_safety_doc)]
+    unsafe fn read_buf_from(addr: u16, buf: &mut [Self]);
+
+    /// Writes every element of `buf` to the port (`rep outs`).
+    #[allow(clippy::missing_safety_doc)]
+    unsafe fn write_buf_to(addr: u16, buf: &[Self]);
+}
+
+macro_rules! impl_port_width {
+    ($type:ty, $reg:tt, $in:literal, $out:literal, $ins:literal, $outs:literal) => {
+        impl PortWidth for $type {
+            unsafe fn read_from(addr: u16) -> $type {
+                let ret;
+                asm!(
+                    $in,
+                    in("dx") addr,
+                    out($reg) ret,
+                    options(att_syntax, nomem, nostack, preserves_flags)
+                );
+
+                ret
+            }
+
+            unsafe fn write_to(addr: u16, val: $type) {
+                asm!(
+                    $out,
+                    in("dx") addr,
+                    in($reg) val,
+                    options(att_syntax, nomem, nostack, preserves_flags)
+                );
+            }
 
-    pub fn request_port(&mut self, addr: u16) -> Option<Port> {
-        if self.allocated_ports.contains(&addr) {
-            return None;
+            // The direction flag is clear as required by the calling convention, so `edi` and
+            // `esi` walk the buffer upwards
+            unsafe fn read_buf_from(addr: u16, buf: &mut [$type]) {
+                asm!(
+                    $ins,
+                    in("dx") addr,
+                    inout("edi") buf.as_mut_ptr() => _,
+                    inout("ecx") buf.len() => _,
+                    options(att_syntax, nostack, preserves_flags)
+                );
+            }
+
+            unsafe fn write_buf_to(addr: u16, buf: &[$type]) {
+                asm!(
+                    $outs,
+                    in("dx") addr,
+                    inout("esi") buf.as_ptr() => _,
+                    inout("ecx") buf.len() => _,
+                    options(att_syntax, readonly, nostack, preserves_flags)
+                );
+            }
+        }
+    };
+}
+
+impl_port_width!(
+    u8,
+    "al",
+    "in %dx, %al",
+    "out %al, %dx",
+    "rep insb (%dx), %es:(%edi)",
+    "rep outsb %ds:(%esi), (%dx)"
+);
+impl_port_width!(
+    u16,
+    "ax",
+    "in %dx, %ax",
+    "out %ax, %dx",
+    "rep insw (%dx), %es:(%edi)",
+    "rep outsw %ds:(%esi), (%dx)"
+);
+impl_port_width!(
+    u32,
+    "eax",
+    "in %dx, %eax",
+    "out %eax, %dx",
+    "rep insl (%dx), %es:(%edi)",
+    "rep outsl %ds:(%esi), (%dx)"
+);
+
+/// Read access the drivers are written against, so tests can swap in fake devices.
+pub trait PortRead<T: PortWidth = u8> {
+    fn read(&self) -> T;
+}
+
+/// Write access the drivers are written against, so tests can swap in fake devices.
+pub trait PortWrite<T: PortWidth = u8> {
+    fn write(&self, val: T);
+}
+
+/// Ports that are both read and written, e.g. a data register.
+pub trait PortIo<T: PortWidth = u8>: PortRead<T> + PortWrite<T> {}
+
+impl<T: PortWidth, P: PortRead<T> + PortWrite<T>> PortIo<T> for P {}
+
+/// Readable and writable port accessed `T` at a time.
+pub struct Port<T: PortWidth = u8> {
+    addr: u16,
+    width: PhantomData<T>,
+    // Set for ports taken from a `PortRange`
+    _hold: Option<Hold>,
+}
+
+impl<T: PortWidth> Port<T> {
+    /// Accesses `addr` without a reservation.
+    pub fn new(addr: u16) -> Port<T> {
+        Port {
+            addr,
+            width: PhantomData,
+            _hold: None,
         }
+    }
 
-        self.allocated_ports.insert(addr);
+    pub fn get_addr(&self) -> u16 {
+        self.addr
+    }
 
-        Some(Port::new(addr))
+    pub fn read(&self) -> T {
+        unsafe { T::read_from(self.addr) }
+    }
+
+    pub fn write(&self, val: T) {
+        unsafe { T::write_to(self.addr, val) }
+    }
+
+    pub fn read_buf(&self, buf: &mut [T]) {
+        unsafe { T::read_buf_from(self.addr, buf) }
+    }
+
+    pub fn write_buf(&self, buf: &[T]) {
+        unsafe { T::write_buf_to(self.addr, buf) }
+    }
+}
+
+impl<T: PortWidth> PortRead<T> for Port<T> {
+    fn read(&self) -> T {
+        Port::read(self)
     }
 }
 
-pub struct Port {
+impl<T: PortWidth> PortWrite<T> for Port<T> {
+    fn write(&self, val: T) {
+        Port::write(self, val)
+    }
+}
+
+/// Port whose writes have no meaning or a harmful side effect, e.g. a status register.
+pub struct ReadOnlyPort<T: PortWidth = u8> {
     addr: u16,
+    width: PhantomData<T>,
+    _hold: Option<Hold>,
 }
 
-impl Port {
-    pub fn new(addr: u16) -> Port {
-        Port { addr }
+impl<T: PortWidth> ReadOnlyPort<T> {
+    /// Accesses `addr` without a reservation.
+    pub fn new(addr: u16) -> ReadOnlyPort<T> {
+        ReadOnlyPort {
+            addr,
+            width: PhantomData,
+            _hold: None,
+        }
     }
 
     pub fn get_addr(&self) -> u16 {
         self.addr
     }
 
-    // Store val in reg `al`
-    // Store addr in reg `dx`
-    // Write the value of `al` to `dx`
-    pub fn writeb(&self, val: u8) {
-        unsafe {
-            asm!(r#"
-	        out %al, %dx
-	        "#,
-                in("dx") self.addr,
-                in("al") val,
-                options(att_syntax)
-            );
-        }
-    }
-
-    // Store addr in `dx`
-    // Set `al` to out register
-    // Read the value of dx into al
-    // Store the value of `al` into ret
-    pub fn readb(&self) -> u8 {
-        unsafe {
-            let mut ret;
-            asm!(r#"
-                in %dx, %al
-                "#,
-            in("dx") self.addr,
-            out("al") ret,
-            options(att_syntax)
-            );
-
-            ret
+    pub fn read(&self) -> T {
+        unsafe { T::read_from(self.addr) }
+    }
+
+    pub fn read_buf(&self, buf: &mut [T]) {
+        unsafe { T::read_buf_from(self.addr, buf) }
+    }
+}
+
+impl<T: PortWidth> PortRead<T> for ReadOnlyPort<T> {
+    fn read(&self) -> T {
+        ReadOnlyPort::read(self)
+    }
+}
+
+/// Port that can not be read back, e.g. an index or command register.
+pub struct WriteOnlyPort<T: PortWidth = u8> {
+    addr: u16,
+    width: PhantomData<T>,
+    _hold: Option<Hold>,
+}
+
+impl<T: PortWidth> WriteOnlyPort<T> {
+    /// Accesses `addr` without a reservation.
+    pub fn new(addr: u16) -> WriteOnlyPort<T> {
+        WriteOnlyPort {
+            addr,
+            width: PhantomData,
+            _hold: None,
         }
     }
+
+    pub fn get_addr(&self) -> u16 {
+        self.addr
+    }
+
+    pub fn write(&self, val: T) {
+        unsafe { T::write_to(self.addr, val) }
+    }
+
+    pub fn write_buf(&self, buf: &[T]) {
+        unsafe { T::write_buf_to(self.addr, buf) }
+    }
+}
+
+impl<T: PortWidth> PortWrite<T> for WriteOnlyPort<T> {
+    fn write(&self, val: T) {
+        WriteOnlyPort::write(self, val)
+    }
 }
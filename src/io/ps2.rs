@@ -0,0 +1,447 @@
+use core::cell::Cell;
+use log::info;
+use thiserror_no_std::Error;
+
+use crate::io::port_manager::{Port, PortError, PortIo, PortManager};
+use crate::pit;
+
+pub const DATA_PORT: u16 = 0x60;
+pub const STATUS_COMMAND_PORT: u16 = 0x64;
+
+// Status register
+pub const STATUS_OUTPUT_FULL: u8 = 0x01;
+pub const STATUS_INPUT_FULL: u8 = 0x02;
+// The byte in the output buffer came from the second port
+pub const STATUS_AUX_DATA: u8 = 0x20;
+
+// Controller commands
+const READ_CONFIG: u8 = 0x20;
+const WRITE_CONFIG: u8 = 0x60;
+const DISABLE_SECOND_PORT: u8 = 0xA7;
+const ENABLE_SECOND_PORT: u8 = 0xA8;
+const TEST_SECOND_PORT: u8 = 0xA9;
+const SELF_TEST: u8 = 0xAA;
+const TEST_FIRST_PORT: u8 = 0xAB;
+const DISABLE_FIRST_PORT: u8 = 0xAD;
+const ENABLE_FIRST_PORT: u8 = 0xAE;
+const WRITE_SECOND_PORT: u8 = 0xD4;
+
+const SELF_TEST_PASSED: u8 = 0x55;
+const PORT_TEST_PASSED: u8 = 0x00;
+
+// Controller configuration byte
+const CONFIG_FIRST_IRQ: u8 = 0x01;
+const CONFIG_SECOND_IRQ: u8 = 0x02;
+const CONFIG_SECOND_CLOCK_DISABLED: u8 = 0x20;
+const CONFIG_TRANSLATION: u8 = 0x40;
+
+// Device responses
+pub const ACK: u8 = 0xFA;
+pub const RESEND: u8 = 0xFE;
+
+const DEVICE_RETRIES: usize = 3;
+// Status polls before a missing response counts as a timeout
+pub const TIMEOUT_POLLS: usize = 100_000;
+// Devices answer a reset after their self test, which takes 500 ms or more on some keyboards
+pub const RESET_TIMEOUT_MS: u32 = 1000;
+
+#[derive(Debug, Error)]
+pub enum Ps2Error {
+    #[error("PS/2 ports unavailable: {0}")]
+    PortsReserved(PortError),
+    #[error("PS/2 controller did not respond")]
+    Timeout,
+    #[error("PS/2 controller self test failed: {0:#x}")]
+    SelfTest(u8),
+    #[error("PS/2 {port} port test failed: {code:#x}")]
+    PortTest { port: Ps2Port, code: u8 },
+    #[error("PS/2 {port} port device rejected command {command:#x}")]
+    NoAck { port: Ps2Port, command: u8 },
+    #[error("PS/2 controller has no {0} port")]
+    NoSuchPort(Ps2Port),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ps2Port {
+    // Usually the keyboard, on IRQ1
+    First,
+    // Usually the mouse, on IRQ12
+    Second,
+}
+
+impl core::fmt::Display for Ps2Port {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Ps2Port::First => f.write_str("first"),
+            Ps2Port::Second => f.write_str("second"),
+        }
+    }
+}
+
+/// Intel 8042 PS/2 controller.
+pub struct Ps2Controller<P: PortIo = Port> {
+    data: P,
+    status_command: P,
+    dual_channel: Cell<bool>,
+}
+
+impl Ps2Controller {
+    pub fn new(port_manager: &'static PortManager) -> Result<Ps2Controller, Ps2Error> {
+        let data = port_manager
+            .request_range(DATA_PORT, 1, "ps2")
+            .map_err(Ps2Error::PortsReserved)?;
+        let status_command = port_manager
+            .request_range(STATUS_COMMAND_PORT, 1, "ps2")
+            .map_err(Ps2Error::PortsReserved)?;
+
+        Ok(Ps2Controller::with_ports(
+            data.port(0),
+            status_command.port(0),
+        ))
+    }
+}
+
+impl<P: PortIo> Ps2Controller<P> {
+    /// Drives the controller through its `data` (0x60) and `status_command` (0x64) ports.
+    pub fn with_ports(data: P, status_command: P) -> Ps2Controller<P> {
+        Ps2Controller {
+            data,
+            status_command,
+            dual_channel: Cell::new(false),
+        }
+    }
+
+    /// Tests the controller and enables the first port with its IRQ.
+    ///
+    /// Scancode translation is turned off, so the keyboard reports its own scancode set. The
+    /// second port, if there is one, is left disabled.
+    pub fn init(&self) -> Result<(), Ps2Error> {
+        // Keep the devices quiet while the controller is set up
+        self.send_command(DISABLE_FIRST_PORT)?;
+        self.send_command(DISABLE_SECOND_PORT)?;
+        self.flush();
+
+        let config =
+            self.read_config()? & !(CONFIG_FIRST_IRQ | CONFIG_SECOND_IRQ | CONFIG_TRANSLATION);
+        self.write_config(config)?;
+
+        self.send_command(SELF_TEST)?;
+        match self.read_data()? {
+            SELF_TEST_PASSED => {}
+            code => return Err(Ps2Error::SelfTest(code)),
+        }
+        // The self test resets some controllers
+        self.write_config(config)?;
+
+        // A second port has its clock enabled once the port is
+        if config & CONFIG_SECOND_CLOCK_DISABLED != 0 {
+            self.send_command(ENABLE_SECOND_PORT)?;
+            self.dual_channel
+                .set(self.read_config()? & CONFIG_SECOND_CLOCK_DISABLED == 0);
+            self.send_command(DISABLE_SECOND_PORT)?;
+        }
+
+        self.test_port(Ps2Port::First)?;
+        if self.dual_channel.get() {
+            self.test_port(Ps2Port::Second)?;
+        }
+
+        self.send_command(ENABLE_FIRST_PORT)?;
+        self.write_config(config | CONFIG_FIRST_IRQ)?;
+
+        info!(
+            "PS/2 controller initialized, {} channel",
+            if self.dual_channel.get() {
+                "dual"
+            } else {
+                "single"
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Whether `init` found a second port.
+    pub fn has_second_port(&self) -> bool {
+        self.dual_channel.get()
+    }
+
+    /// Enables the second port and its IRQ.
+    pub fn enable_second_port(&self) -> Result<(), Ps2Error> {
+        if !self.dual_channel.get() {
+            return Err(Ps2Error::NoSuchPort(Ps2Port::Second));
+        }
+
+        self.send_command(ENABLE_SECOND_PORT)?;
+        let config = self.read_config()?;
+        self.write_config(config | CONFIG_SECOND_IRQ)
+    }
+
+    /// Sends `byte` to the device on `port` and waits for it to be acknowledged.
+    pub fn send_device(&self, port: Ps2Port, byte: u8) -> Result<(), Ps2Error> {
+        for _ in 0..DEVICE_RETRIES {
+            if port == Ps2Port::Second {
+                self.send_command(WRITE_SECOND_PORT)?;
+            }
+            self.write_data(byte)?;
+
+            match self.read_data()? {
+                ACK => return Ok(()),
+                RESEND => continue,
+                _ => break,
+            }
+        }
+
+        Err(Ps2Error::NoAck {
+            port,
+            command: byte,
+        })
+    }
+
+    /// Waits for the next byte in the output buffer.
+    pub fn read_data(&self) -> Result<u8, Ps2Error> {
+        for _ in 0..TIMEOUT_POLLS {
+            if self.status_command.read() & STATUS_OUTPUT_FULL != 0 {
+                return Ok(self.data.read());
+            }
+        }
+
+        Err(Ps2Error::Timeout)
+    }
+
+    /// Waits up to `timeout_ms` by the PIT for the next byte in the output buffer. Without a
+    /// running timer it gives up after `TIMEOUT_POLLS` polls.
+    pub fn read_data_within(&self, timeout_ms: u32) -> Result<u8, Ps2Error> {
+        let start = pit::uptime_ms();
+        let mut polls = 0;
+
+        loop {
+            if self.status_command.read() & STATUS_OUTPUT_FULL != 0 {
+                return Ok(self.data.read());
+            }
+
+            let elapsed = pit::uptime_ms().wrapping_sub(start);
+            polls += 1;
+            if elapsed >= timeout_ms || (elapsed == 0 && polls >= TIMEOUT_POLLS) {
+                return Err(Ps2Error::Timeout);
+            }
+        }
+    }
+
+    pub fn write_data(&self, val: u8) -> Result<(), Ps2Error> {
+        self.wait_input_empty()?;
+        self.data.write(val);
+
+        Ok(())
+    }
+
+    /// Discards whatever the devices sent so far.
+    pub fn flush(&self) {
+        while self.status_command.read() & STATUS_OUTPUT_FULL != 0 {
+            self.data.read();
+        }
+    }
+
+    fn test_port(&self, port: Ps2Port) -> Result<(), Ps2Error> {
+        self.send_command(match port {
+            Ps2Port::First => TEST_FIRST_PORT,
+            Ps2Port::Second => TEST_SECOND_PORT,
+        })?;
+
+        match self.read_data()? {
+            PORT_TEST_PASSED => Ok(()),
+            code => Err(Ps2Error::PortTest { port, code }),
+        }
+    }
+
+    fn read_config(&self) -> Result<u8, Ps2Error> {
+        self.send_command(READ_CONFIG)?;
+        self.read_data()
+    }
+
+    fn write_config(&self, config: u8) -> Result<(), Ps2Error> {
+        self.send_command(WRITE_CONFIG)?;
+        self.write_data(config)
+    }
+
+    fn send_command(&self, command: u8) -> Result<(), Ps2Error> {
+        self.wait_input_empty()?;
+        self.status_command.write(command);
+
+        Ok(())
+    }
+
+    fn wait_input_empty(&self) -> Result<(), Ps2Error> {
+        for _ in 0..TIMEOUT_POLLS {
+            if self.status_command.read() & STATUS_INPUT_FULL == 0 {
+                return Ok(());
+            }
+        }
+
+        Err(Ps2Error::Timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::VecDeque;
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::io::fake_port::{FakeBus, FakeDevice, FakePort};
+
+    // Controller with a keyboard on the first port and an optional mouse on the second
+    struct Fake8042 {
+        config: u8,
+        dual_channel: bool,
+        self_test: u8,
+        output: VecDeque<u8>,
+        // Controller command waiting for its data byte
+        pending: Option<u8>,
+        keyboard: Vec<u8>,
+        mouse: Vec<u8>,
+        // Bytes to answer the next device write with, instead of an ACK
+        replies: VecDeque<u8>,
+    }
+
+    impl Fake8042 {
+        fn new(dual_channel: bool) -> Fake8042 {
+            Fake8042 {
+                config: CONFIG_TRANSLATION | CONFIG_FIRST_IRQ | CONFIG_SECOND_CLOCK_DISABLED,
+                dual_channel,
+                self_test: SELF_TEST_PASSED,
+                output: VecDeque::new(),
+                pending: None,
+                keyboard: Vec::new(),
+                mouse: Vec::new(),
+                replies: VecDeque::new(),
+            }
+        }
+
+        fn device_reply(&mut self) {
+            let reply = self.replies.pop_front().unwrap_or(ACK);
+            self.output.push_back(reply);
+        }
+    }
+
+    impl FakeDevice for Fake8042 {
+        fn read(&mut self, addr: u16) -> u8 {
+            match addr {
+                DATA_PORT => self.output.pop_front().unwrap_or(0),
+                _ => match self.output.is_empty() {
+                    true => 0,
+                    false => STATUS_OUTPUT_FULL,
+                },
+            }
+        }
+
+        fn write(&mut self, addr: u16, val: u8) {
+            match (addr, self.pending.take()) {
+                (DATA_PORT, Some(WRITE_CONFIG)) => self.config = val,
+                (DATA_PORT, Some(WRITE_SECOND_PORT)) => {
+                    self.mouse.push(val);
+                    self.device_reply();
+                }
+                (DATA_PORT, _) => {
+                    self.keyboard.push(val);
+                    self.device_reply();
+                }
+                (_, _) => match val {
+                    READ_CONFIG => self.output.push_back(self.config),
+                    WRITE_CONFIG | WRITE_SECOND_PORT => self.pending = Some(val),
+                    SELF_TEST => self.output.push_back(self.self_test),
+                    TEST_FIRST_PORT | TEST_SECOND_PORT => self.output.push_back(PORT_TEST_PASSED),
+                    ENABLE_SECOND_PORT if self.dual_channel => {
+                        self.config &= !CONFIG_SECOND_CLOCK_DISABLED
+                    }
+                    DISABLE_SECOND_PORT => self.config |= CONFIG_SECOND_CLOCK_DISABLED,
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn fake_controller(dual_channel: bool) -> (Rc<RefCell<Fake8042>>, Ps2Controller<FakePort>) {
+        let bus = FakeBus::new();
+        let device = Rc::new(RefCell::new(Fake8042::new(dual_channel)));
+        bus.attach(DATA_PORT, 1, device.clone());
+        bus.attach(STATUS_COMMAND_PORT, 1, device.clone());
+
+        let controller =
+            Ps2Controller::with_ports(bus.port(DATA_PORT), bus.port(STATUS_COMMAND_PORT));
+
+        (device, controller)
+    }
+
+    #[test]
+    fn init_enables_first_port_without_translation() {
+        let (device, controller) = fake_controller(false);
+        device.borrow_mut().output.push_back(0x1C); // Stale scancode
+
+        controller.init().unwrap();
+
+        let config = device.borrow().config;
+        assert_eq!(config & CONFIG_FIRST_IRQ, CONFIG_FIRST_IRQ);
+        assert_eq!(config & (CONFIG_TRANSLATION | CONFIG_SECOND_IRQ), 0);
+        assert!(!controller.has_second_port());
+        assert!(matches!(
+            controller.enable_second_port(),
+            Err(Ps2Error::NoSuchPort(Ps2Port::Second))
+        ));
+    }
+
+    #[test]
+    fn init_detects_second_port() {
+        let (device, controller) = fake_controller(true);
+
+        controller.init().unwrap();
+        assert!(controller.has_second_port());
+        assert_ne!(device.borrow().config & CONFIG_SECOND_CLOCK_DISABLED, 0);
+
+        controller.enable_second_port().unwrap();
+        let config = device.borrow().config;
+        assert_eq!(config & CONFIG_SECOND_CLOCK_DISABLED, 0);
+        assert_eq!(config & CONFIG_SECOND_IRQ, CONFIG_SECOND_IRQ);
+    }
+
+    #[test]
+    fn init_reports_failed_self_test() {
+        let (device, controller) = fake_controller(false);
+        device.borrow_mut().self_test = 0xFC;
+
+        assert!(matches!(controller.init(), Err(Ps2Error::SelfTest(0xFC))));
+    }
+
+    #[test]
+    fn send_device_retries_on_resend() {
+        let (device, controller) = fake_controller(true);
+        controller.init().unwrap();
+        device.borrow_mut().replies.push_back(RESEND);
+
+        controller.send_device(Ps2Port::Second, 0xF4).unwrap();
+        assert_eq!(device.borrow().mouse, [0xF4, 0xF4]);
+
+        device.borrow_mut().replies.extend([RESEND, RESEND, RESEND]);
+        assert!(matches!(
+            controller.send_device(Ps2Port::First, 0xED),
+            Err(Ps2Error::NoAck {
+                port: Ps2Port::First,
+                command: 0xED
+            })
+        ));
+    }
+
+    #[test]
+    fn read_data_times_out() {
+        let (_, controller) = fake_controller(false);
+
+        assert!(matches!(controller.read_data(), Err(Ps2Error::Timeout)));
+        // The timer does not run in host tests
+        assert!(matches!(
+            controller.read_data_within(RESET_TIMEOUT_MS),
+            Err(Ps2Error::Timeout)
+        ));
+    }
+}
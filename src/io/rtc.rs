@@ -1,18 +1,65 @@
-use crate::io::port_manager::{Port, PortManager};
+use crate::io::port_manager::{
+    Port, PortError, PortIo, PortManager, PortRead, PortWrite, WriteOnlyPort,
+};
+use crate::time::{DateTime, DateTimeError};
 use thiserror_no_std::Error;
 
 const NMI_ENABLE: bool = true;
 
-#[derive(Debug)]
-pub struct DateTime {
-    pub seconds: u8,
-    pub minutes: u8,
-    pub hours: u8,
-    pub weekday: u8,
-    pub day_of_moth: u8,
-    pub month: u8,
-    pub year: u8,
-    pub century: u8,
+// Status Register B
+const STATUS_B_24_HOUR: u8 = 1 << 1;
+const STATUS_B_BINARY: u8 = 1 << 2;
+// Set in the hours of the 12 hour format after noon
+const HOURS_PM: u8 = 1 << 7;
+
+// Earlier years would be stored with a century of 0, which is taken as a missing century register
+pub const MIN_YEAR: u16 = 100;
+
+// The time registers, in binary and 24 hour format once decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RawDateTime {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    weekday: u8,
+    day_of_month: u8,
+    month: u8,
+    year: u8,
+    century: u8,
+}
+
+impl RawDateTime {
+    fn from_date_time(date_time: &DateTime) -> RawDateTime {
+        RawDateTime {
+            seconds: date_time.seconds(),
+            minutes: date_time.minutes(),
+            hours: date_time.hours(),
+            weekday: date_time.weekday().days_from_sunday() + 1,
+            day_of_month: date_time.day(),
+            month: date_time.month(),
+            year: (date_time.year() % 100) as u8,
+            century: (date_time.year() / 100) as u8,
+        }
+    }
+
+    // The weekday register is ignored, `DateTime` computes it
+    fn to_date_time(self) -> Result<DateTime, DateTimeError> {
+        // Not every RTC keeps the century, 00-69 are taken as 20xx then
+        let century = match (self.century, self.year) {
+            (0, 0..=69) => 20,
+            (0, _) => 19,
+            (century, _) => century,
+        };
+
+        DateTime::new(
+            century as u16 * 100 + self.year as u16,
+            self.month,
+            self.day_of_month,
+            self.hours,
+            self.minutes,
+            self.seconds,
+        )
+    }
 }
 
 enum TimeRegister {
@@ -42,46 +89,147 @@ impl UpdateStatus {
     }
 }
 
+/// How the RTC stores the date and time, as reported by Status Register B.
+///
+/// The firmware picks it and other systems reading the clock rely on it, so it is left as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataFormat {
+    /// Binary values, BCD otherwise
+    pub binary: bool,
+    /// Hours from 0 to 23, otherwise from 1 to 12 with the PM bit
+    pub hour_24: bool,
+}
+
+impl DataFormat {
+    fn from_status_b(status: u8) -> DataFormat {
+        DataFormat {
+            binary: status & STATUS_B_BINARY != 0,
+            hour_24: status & STATUS_B_24_HOUR != 0,
+        }
+    }
+
+    fn decode(&self, value: u8) -> u8 {
+        match self.binary {
+            true => value,
+            false => (value >> 4) * 10 + (value & 0x0F),
+        }
+    }
+
+    fn encode(&self, value: u8) -> u8 {
+        match self.binary {
+            true => value,
+            false => ((value / 10) << 4) | (value % 10),
+        }
+    }
+
+    // 12 AM is midnight and 12 PM is noon
+    fn decode_hours(&self, hours: u8) -> u8 {
+        if self.hour_24 {
+            return self.decode(hours);
+        }
+
+        let hour = self.decode(hours & !HOURS_PM) % 12;
+        match hours & HOURS_PM != 0 {
+            true => hour + 12,
+            false => hour,
+        }
+    }
+
+    fn encode_hours(&self, hours: u8) -> u8 {
+        if self.hour_24 {
+            return self.encode(hours);
+        }
+
+        let hour = match hours % 12 {
+            0 => 12,
+            hour => hour,
+        };
+        match hours >= 12 {
+            true => self.encode(hour) | HOURS_PM,
+            false => self.encode(hour),
+        }
+    }
+
+    fn decode_date_time(&self, raw: &RawDateTime) -> RawDateTime {
+        RawDateTime {
+            seconds: self.decode(raw.seconds),
+            minutes: self.decode(raw.minutes),
+            hours: self.decode_hours(raw.hours),
+            weekday: self.decode(raw.weekday),
+            day_of_month: self.decode(raw.day_of_month),
+            month: self.decode(raw.month),
+            year: self.decode(raw.year),
+            century: self.decode(raw.century),
+        }
+    }
+
+    fn encode_date_time(&self, date_time: &RawDateTime) -> RawDateTime {
+        RawDateTime {
+            seconds: self.encode(date_time.seconds),
+            minutes: self.encode(date_time.minutes),
+            hours: self.encode_hours(date_time.hours),
+            weekday: self.encode(date_time.weekday),
+            day_of_month: self.encode(date_time.day_of_month),
+            month: self.encode(date_time.month),
+            year: self.encode(date_time.year),
+            century: self.encode(date_time.century),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum RtcInitError {
-    #[error("Failed to get cmos control port")]
-    FailedToGetCmosControlPort,
-    #[error("Failed to get cmos data port")]
-    FailedToGetCmosDataPort,
+    #[error("CMOS ports unavailable: {0}")]
+    PortsReserved(PortError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum RtcWriteError {
+    // A century of 0 reads back as 19xx or 20xx
+    #[error("Year {0} is outside the {MIN_YEAR}-9999 the RTC can hold")]
+    YearOutOfRange(u16),
 }
 
-pub struct Rtc {
-    cmos_control_port: Port,
-    cmos_data_port: Port,
+pub struct Rtc<C: PortWrite = WriteOnlyPort, D: PortIo = Port> {
+    cmos_control_port: C,
+    cmos_data_port: D,
 }
 
 impl Rtc {
-    pub fn new(port_manager: &mut PortManager) -> Result<Rtc, RtcInitError> {
-        use RtcInitError::*;
-
-        let cmos_control_port = port_manager
-            .request_port(0x70)
-            .ok_or(FailedToGetCmosControlPort)?;
-        let cmos_data_port = port_manager
-            .request_port(0x71)
-            .ok_or(FailedToGetCmosDataPort)?;
-
-        set_data_format(
-            &cmos_control_port,
-            &cmos_data_port,
-            NMI_ENABLE,
-            TimeRegister::StatusRegisterB as u8,
-        );
+    pub fn new(port_manager: &'static PortManager) -> Result<Rtc, RtcInitError> {
+        let ports = port_manager
+            .request_range(0x70, 2, "rtc")
+            .map_err(RtcInitError::PortsReserved)?;
+
+        Ok(Rtc::with_ports(ports.write_only_port(0), ports.port(1)))
+    }
+}
 
-        Ok(Rtc {
+impl<C: PortWrite, D: PortIo> Rtc<C, D> {
+    /// Drives the CMOS through `cmos_control_port` (0x70) and `cmos_data_port` (0x71).
+    pub fn with_ports(cmos_control_port: C, cmos_data_port: D) -> Rtc<C, D> {
+        Rtc {
             cmos_control_port,
             cmos_data_port,
-        })
+        }
+    }
+
+    pub fn data_format(&self) -> DataFormat {
+        DataFormat::from_status_b(read_cmos_reg(
+            &self.cmos_control_port,
+            &self.cmos_data_port,
+            NMI_ENABLE,
+            TimeRegister::StatusRegisterB as u8,
+        ))
     }
 
-    pub fn read(&self) -> DateTime {
+    /// Reads the date and time, decoded from whatever format the RTC uses.
+    ///
+    /// Fails if the registers do not hold a valid date, e.g. after the CMOS battery ran out.
+    pub fn read(&self) -> Result<DateTime, DateTimeError> {
         use TimeRegister::*;
-        update_guarded_op(
+        let format = self.data_format();
+        let raw = update_guarded_op(
             &self.cmos_control_port,
             &self.cmos_data_port,
             StatusRegisterA as u8,
@@ -90,28 +238,40 @@ impl Rtc {
                 let minutes = read_cmos_reg(control_port, data_port, NMI_ENABLE, Minutes as u8);
                 let hours = read_cmos_reg(control_port, data_port, NMI_ENABLE, Hours as u8);
                 let weekday = read_cmos_reg(control_port, data_port, NMI_ENABLE, Weekday as u8);
-                let day_of_moth =
+                let day_of_month =
                     read_cmos_reg(control_port, data_port, NMI_ENABLE, DayOfMonth as u8);
                 let month = read_cmos_reg(control_port, data_port, NMI_ENABLE, Month as u8);
                 let year = read_cmos_reg(control_port, data_port, NMI_ENABLE, Year as u8);
                 let century = read_cmos_reg(control_port, data_port, NMI_ENABLE, Century as u8);
 
-                DateTime {
+                RawDateTime {
                     seconds,
                     minutes,
                     hours,
                     weekday,
-                    day_of_moth,
+                    day_of_month,
                     month,
                     year,
                     century,
                 }
             },
-        )
+        );
+
+        format.decode_date_time(&raw).to_date_time()
     }
 
-    pub fn write(&self, date_time: &DateTime) {
+    /// Sets the date and time, encoded in the format the RTC uses.
+    ///
+    /// Refuses years before `MIN_YEAR`, they would not read back.
+    pub fn write(&self, date_time: &DateTime) -> Result<(), RtcWriteError> {
         use TimeRegister::*;
+        if date_time.year() < MIN_YEAR {
+            return Err(RtcWriteError::YearOutOfRange(date_time.year()));
+        }
+
+        let date_time = &self
+            .data_format()
+            .encode_date_time(&RawDateTime::from_date_time(date_time));
         update_guarded_op(
             &self.cmos_control_port,
             &self.cmos_data_port,
@@ -150,7 +310,7 @@ impl Rtc {
                     data_port,
                     NMI_ENABLE,
                     DayOfMonth as u8,
-                    date_time.day_of_moth,
+                    date_time.day_of_month,
                 );
                 write_cmos_reg(
                     control_port,
@@ -174,13 +334,17 @@ impl Rtc {
                     date_time.century,
                 );
             },
-        )
+        );
+
+        Ok(())
     }
 }
 
-fn update_guarded_op<F, R>(control_port: &Port, data_port: &Port, register: u8, f: F) -> R
+fn update_guarded_op<C, D, F, R>(control_port: &C, data_port: &D, register: u8, f: F) -> R
 where
-    F: Fn(&Port, &Port) -> R,
+    C: PortWrite,
+    D: PortIo,
+    F: Fn(&C, &D) -> R,
 {
     let mut date_time;
 
@@ -203,22 +367,14 @@ where
     date_time
 }
 
-fn set_data_format(control_port: &Port, data_port: &Port, nmi_enable: bool, register: u8) {
-    let mut status_reg = read_cmos_reg(control_port, data_port, nmi_enable, register);
-    status_reg |= 1 << 1; // Enables 24H mode
-    status_reg |= 1 << 2; // Enables Binary mode
-
-    write_cmos_reg(control_port, data_port, nmi_enable, register, status_reg)
-}
-
 fn update_in_progress(
-    control_port: &Port,
-    data_port: &Port,
+    control_port: &impl PortWrite,
+    data_port: &impl PortRead,
     nmi_enable: bool,
     register: u8,
 ) -> bool {
     select_reg(control_port, nmi_enable, register);
-    let progress_status = UpdateStatus::new(data_port.readb() >> 7);
+    let progress_status = UpdateStatus::new(data_port.read() >> 7);
 
     match progress_status {
         UpdateStatus::Set => true,
@@ -226,24 +382,29 @@ fn update_in_progress(
     }
 }
 
-fn read_cmos_reg(control_port: &Port, data_port: &Port, nmi_enable: bool, register: u8) -> u8 {
+fn read_cmos_reg(
+    control_port: &impl PortWrite,
+    data_port: &impl PortRead,
+    nmi_enable: bool,
+    register: u8,
+) -> u8 {
     select_reg(control_port, nmi_enable, register);
-    data_port.readb()
+    data_port.read()
 }
 
 pub fn write_cmos_reg(
-    control_port: &Port,
-    data_port: &Port,
+    control_port: &impl PortWrite,
+    data_port: &impl PortWrite,
     nmi_enable: bool,
     register: u8,
     val: u8,
 ) {
     select_reg(control_port, nmi_enable, register);
-    data_port.writeb(val);
+    data_port.write(val);
 }
 
-fn select_reg(control_port: &Port, nmi_enable: bool, register: u8) {
-    control_port.writeb(get_nmi_mask(nmi_enable) | register)
+fn select_reg(control_port: &impl PortWrite, nmi_enable: bool, register: u8) {
+    control_port.write(get_nmi_mask(nmi_enable) | register)
 }
 
 fn get_nmi_mask(nmi_enable: bool) -> u8 {
@@ -253,3 +414,341 @@ fn get_nmi_mask(nmi_enable: bool) -> u8 {
         1 << 7
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::io::fake_port::{Access, FakeBus, FakeDevice, FakePort, Registers};
+
+    // 0x70 selects one of the CMOS registers behind 0x71, bit 7 of the index masks NMIs
+    #[derive(Default)]
+    struct Cmos {
+        index: u8,
+        registers: Registers,
+    }
+
+    impl FakeDevice for Cmos {
+        fn read(&mut self, addr: u16) -> u8 {
+            match addr {
+                0x71 => FakeDevice::read(&mut self.registers, self.index as u16),
+                _ => 0xFF,
+            }
+        }
+
+        fn write(&mut self, addr: u16, val: u8) {
+            match addr {
+                0x70 => self.index = val & 0x7F,
+                _ => FakeDevice::write(&mut self.registers, self.index as u16, val),
+            }
+        }
+    }
+
+    // 2024-07-24T12:34:56, a Wednesday
+    const REGISTERS: RawDateTime = RawDateTime {
+        seconds: 56,
+        minutes: 34,
+        hours: 12,
+        weekday: 4,
+        day_of_month: 24,
+        month: 7,
+        year: 24,
+        century: 20,
+    };
+
+    const BCD_REGISTERS: RawDateTime = RawDateTime {
+        seconds: 0x56,
+        minutes: 0x34,
+        hours: 0x12,
+        weekday: 0x04,
+        day_of_month: 0x24,
+        month: 0x07,
+        year: 0x24,
+        century: 0x20,
+    };
+
+    // Status Register B, the date as stored in that format and the hours as (stored, decoded)
+    const FORMATS: [(u8, RawDateTime, &[(u8, u8)]); 4] = [
+        (
+            STATUS_B_BINARY | STATUS_B_24_HOUR,
+            REGISTERS,
+            &[(0, 0), (9, 9), (12, 12), (23, 23)],
+        ),
+        (
+            STATUS_B_24_HOUR,
+            BCD_REGISTERS,
+            &[(0x00, 0), (0x09, 9), (0x12, 12), (0x23, 23)],
+        ),
+        (
+            STATUS_B_BINARY,
+            REGISTERS,
+            &[
+                (12, 0),
+                (9, 9),
+                (0x80 | 12, 12),
+                (0x80 | 1, 13),
+                (0x80 | 11, 23),
+            ],
+        ),
+        (
+            0,
+            BCD_REGISTERS,
+            &[(0x12, 0), (0x09, 9), (0x92, 12), (0x81, 13), (0x91, 23)],
+        ),
+    ];
+
+    type FakeRtc = Rtc<FakePort, FakePort>;
+
+    fn date_time(hours: u8) -> DateTime {
+        DateTime::new(2024, 7, 24, hours, 34, 56).unwrap()
+    }
+
+    fn fake_rtc() -> (Rc<FakeBus>, Rc<RefCell<Cmos>>, FakeRtc) {
+        fake_rtc_with_status_b(STATUS_B_BINARY | STATUS_B_24_HOUR)
+    }
+
+    fn fake_rtc_with_status_b(status_b: u8) -> (Rc<FakeBus>, Rc<RefCell<Cmos>>, FakeRtc) {
+        let bus = FakeBus::new();
+        let cmos = Rc::new(RefCell::new(Cmos::default()));
+        cmos.borrow_mut()
+            .registers
+            .set(TimeRegister::StatusRegisterB as u16, status_b);
+        bus.attach(0x70, 2, cmos.clone());
+
+        let rtc = Rtc::with_ports(bus.port(0x70), bus.port(0x71));
+        bus.clear_log();
+
+        (bus, cmos, rtc)
+    }
+
+    fn set_registers(cmos: &RefCell<Cmos>, raw: &RawDateTime) {
+        use TimeRegister::*;
+
+        let registers = &mut cmos.borrow_mut().registers;
+        registers.set(Seconds as u16, raw.seconds);
+        registers.set(Minutes as u16, raw.minutes);
+        registers.set(Hours as u16, raw.hours);
+        registers.set(Weekday as u16, raw.weekday);
+        registers.set(DayOfMonth as u16, raw.day_of_month);
+        registers.set(Month as u16, raw.month);
+        registers.set(Year as u16, raw.year);
+        registers.set(Century as u16, raw.century);
+    }
+
+    fn get_registers(cmos: &RefCell<Cmos>) -> RawDateTime {
+        use TimeRegister::*;
+
+        let registers = &cmos.borrow().registers;
+        RawDateTime {
+            seconds: registers.get(Seconds as u16),
+            minutes: registers.get(Minutes as u16),
+            hours: registers.get(Hours as u16),
+            weekday: registers.get(Weekday as u16),
+            day_of_month: registers.get(DayOfMonth as u16),
+            month: registers.get(Month as u16),
+            year: registers.get(Year as u16),
+            century: registers.get(Century as u16),
+        }
+    }
+
+    fn status_a_polls(bus: &FakeBus) -> usize {
+        bus.log()
+            .iter()
+            .filter(|&&access| access == Access::Write(0x70, TimeRegister::StatusRegisterA as u8))
+            .count()
+    }
+
+    #[test]
+    fn status_register_b_is_left_alone() {
+        // BCD and 12 hour with the update-ended interrupt enabled
+        let status_b = 0x10;
+        let (_, cmos, rtc) = fake_rtc_with_status_b(status_b);
+
+        rtc.write(&date_time(12)).unwrap();
+        let _ = rtc.read();
+
+        assert_eq!(
+            cmos.borrow()
+                .registers
+                .get(TimeRegister::StatusRegisterB as u16),
+            status_b
+        );
+    }
+
+    #[test]
+    fn read_decodes_every_format() {
+        for (status_b, stored, hours) in FORMATS {
+            for &(stored_hours, decoded_hours) in hours {
+                let (_, cmos, rtc) = fake_rtc_with_status_b(status_b);
+                set_registers(
+                    &cmos,
+                    &RawDateTime {
+                        hours: stored_hours,
+                        ..stored
+                    },
+                );
+
+                assert_eq!(
+                    rtc.read(),
+                    Ok(date_time(decoded_hours)),
+                    "status B {:#04x}",
+                    status_b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn write_encodes_every_format() {
+        for (status_b, stored, hours) in FORMATS {
+            for &(stored_hours, decoded_hours) in hours {
+                let (_, cmos, rtc) = fake_rtc_with_status_b(status_b);
+
+                rtc.write(&date_time(decoded_hours)).unwrap();
+
+                let expected = RawDateTime {
+                    hours: stored_hours,
+                    ..stored
+                };
+                assert_eq!(get_registers(&cmos), expected, "status B {:#04x}", status_b);
+            }
+        }
+    }
+
+    #[test]
+    fn read_returns_cmos_registers() {
+        let (bus, cmos, rtc) = fake_rtc();
+        set_registers(&cmos, &REGISTERS);
+
+        assert_eq!(rtc.read(), Ok(date_time(12)));
+        // One poll before and one after reading the registers
+        assert_eq!(status_a_polls(&bus), 2);
+    }
+
+    #[test]
+    fn read_waits_for_update_in_progress() {
+        let (bus, cmos, rtc) = fake_rtc();
+        set_registers(&cmos, &REGISTERS);
+        cmos.borrow_mut()
+            .registers
+            .script(TimeRegister::StatusRegisterA as u16, &[0x80, 0x80, 0x80]);
+
+        assert_eq!(rtc.read(), Ok(date_time(12)));
+        assert_eq!(status_a_polls(&bus), 5);
+    }
+
+    #[test]
+    fn read_retries_when_update_starts_mid_read() {
+        use TimeRegister::*;
+
+        let (bus, cmos, rtc) = fake_rtc();
+        set_registers(&cmos, &REGISTERS);
+        {
+            let registers = &mut cmos.borrow_mut().registers;
+            // The update starts right after the seconds were read as 59 and rolls them over
+            registers.script(StatusRegisterA as u16, &[0x00, 0x80]);
+            registers.script(Seconds as u16, &[59]);
+        }
+
+        assert_eq!(rtc.read(), Ok(date_time(12)));
+        assert_eq!(status_a_polls(&bus), 4);
+    }
+
+    #[test]
+    fn write_sets_cmos_registers() {
+        let (bus, _, rtc) = fake_rtc();
+
+        rtc.write(&date_time(12)).unwrap();
+
+        let written = bus
+            .log()
+            .iter()
+            .filter(|access| matches!(access, Access::Write(0x71, _)))
+            .count();
+        assert_eq!(written, 8);
+        assert_eq!(rtc.read(), Ok(date_time(12)));
+    }
+
+    #[test]
+    fn write_sets_computed_weekday() {
+        let (_, cmos, rtc) = fake_rtc();
+
+        // A Sunday
+        rtc.write(&DateTime::new(2024, 7, 28, 0, 0, 0).unwrap())
+            .unwrap();
+
+        assert_eq!(get_registers(&cmos).weekday, 1);
+    }
+
+    #[test]
+    fn write_rejects_years_without_century() {
+        let (bus, _, rtc) = fake_rtc();
+
+        assert_eq!(
+            rtc.write(&DateTime::new(99, 12, 31, 0, 0, 0).unwrap()),
+            Err(RtcWriteError::YearOutOfRange(99))
+        );
+        assert!(bus.log().is_empty());
+
+        rtc.write(&DateTime::new(100, 1, 1, 0, 0, 0).unwrap())
+            .unwrap();
+        assert_eq!(rtc.read(), Ok(DateTime::new(100, 1, 1, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn read_rejects_invalid_registers() {
+        let (_, cmos, rtc) = fake_rtc();
+        set_registers(
+            &cmos,
+            &RawDateTime {
+                month: 13,
+                ..REGISTERS
+            },
+        );
+
+        assert_eq!(rtc.read(), Err(DateTimeError::InvalidMonth(13)));
+    }
+
+    #[test]
+    fn read_guesses_missing_century() {
+        let (_, cmos, rtc) = fake_rtc();
+        set_registers(
+            &cmos,
+            &RawDateTime {
+                century: 0,
+                ..REGISTERS
+            },
+        );
+        assert_eq!(rtc.read(), Ok(date_time(12)));
+
+        set_registers(
+            &cmos,
+            &RawDateTime {
+                century: 0,
+                year: 99,
+                ..REGISTERS
+            },
+        );
+        assert_eq!(rtc.read().map(|date_time| date_time.year()), Ok(1999));
+    }
+
+    #[test]
+    fn registers_are_selected_with_nmi_enabled() {
+        let (bus, _, rtc) = fake_rtc();
+
+        let _ = rtc.read();
+
+        let selects: Vec<u8> = bus
+            .log()
+            .iter()
+            .filter_map(|access| match access {
+                Access::Write(0x70, val) => Some(*val),
+                _ => None,
+            })
+            .collect();
+        assert!(selects.iter().all(|val| val & 0x80 == 0));
+    }
+}
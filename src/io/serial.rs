@@ -1,79 +1,280 @@
-use core::fmt::Write;
+use core::cell::Cell;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU16, AtomicU8, Ordering};
+use log::info;
 use thiserror_no_std::Error;
 
-use crate::io::port_manager::{Port, PortManager};
-use crate::println;
+use crate::interrupt;
+use crate::io::port_manager::{
+    Port, PortError, PortIo, PortManager, PortRange, PortRead, ReadOnlyPort,
+};
+use crate::io::serial_config::{ComPort, SerialConfig, SerialConfigError};
+use crate::io::sink::ConsoleSink;
+use crate::util::ring_buffer::RingBuffer;
 
-const BASE_ADDR: u16 = 0x3f8; // COM1
-const ISA_DEBUG_EXIT_PORT_NUM: u16 = 0xf4; // EXIT PORT
+pub const RX_BUFFER_SIZE: usize = 256;
+
+// Interrupt enable register
+const IER_RECEIVED_DATA: u8 = 0x01;
+const IER_LINE_STATUS: u8 = 0x04;
+
+// FIFO control register, 0xE7 also asks a 16750 for its 64 byte FIFO during detection
+const FCR_ENABLE: u8 = 0x01;
+const FCR_CLEAR_RX: u8 = 0x02;
+const FCR_CLEAR_TX: u8 = 0x04;
+const FCR_DETECT: u8 = 0xE7;
+
+// Line control register
+const LCR_DLAB: u8 = 0x80;
+
+// Line status register
+const LSR_DATA_READY: u8 = 0x01;
+const LSR_OVERRUN: u8 = 0x02;
+const LSR_PARITY: u8 = 0x04;
+const LSR_FRAMING: u8 = 0x08;
+const LSR_BREAK: u8 = 0x10;
+const LSR_TRANSMIT_EMPTY: u8 = 0x20;
+const LSR_ERRORS: u8 = LSR_OVERRUN | LSR_PARITY | LSR_FRAMING | LSR_BREAK;
+
+// Polls of the line status before `write_raw` gives up on the transmitter
+const RAW_WRITE_SPINS: usize = 100_000;
+
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7F;
+
+static RX: [SerialRx; 4] = [
+    SerialRx::new(),
+    SerialRx::new(),
+    SerialRx::new(),
+    SerialRx::new(),
+];
+
+// Base address of every port with a live `Serial`, 0 if unused
+#[allow(clippy::declare_interior_mutable_const)]
+const INACTIVE: AtomicU16 = AtomicU16::new(0);
+static ACTIVE_BASES: [AtomicU16; 4] = [INACTIVE; 4];
 
 #[derive(Debug, Error)]
 pub enum SerialInitError {
-    #[error("Data port reserved")]
-    DataReserved,
-    #[error("Enable port reserved")]
-    EnableReserved,
-    #[error("Interrupt Id port reserved")]
-    InterruptIdReserved,
-    #[error("Line Control port reserved")]
-    LineControlReserved,
-    #[error("Modem Control port reserved")]
-    ModemControlReserved,
-    #[error("Line Staus port reserved")]
-    LineStatusReserved,
-    #[error("Modem Staus port reserved")]
-    ModemStatusReserved,
-    #[error("Scratch port reserved")]
-    ScratchReserved,
+    #[error("Serial ports unavailable: {0}")]
+    Reserved(PortError),
+    #[error("Invalid configuration: {0}")]
+    Config(SerialConfigError),
     #[error("Loopback test failed")]
     Loopback,
-    #[error("Port is reserved")]
-    Exit,
 }
 
-pub struct Serial {
-    data: Port,
-    enable_interrupt: Port,
-    interrupt_id_fifo_control: Port,
-    line_control: Port,
-    modem_control: Port,
-    line_status: Port,
-    _modem_status: Port,
-    _scratch: Port,
-    pub exit: Port,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UartKind {
+    Uart8250,
+    Uart16450,
+    // FIFO is unusable
+    Uart16550,
+    Uart16550A,
+}
+
+impl fmt::Display for UartKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            UartKind::Uart8250 => "8250",
+            UartKind::Uart16450 => "16450",
+            UartKind::Uart16550 => "16550",
+            UartKind::Uart16550A => "16550A",
+        };
+
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum SerialReadError {
+    #[error("Received data was lost")]
+    Overrun,
+    #[error("Parity error")]
+    Parity,
+    #[error("Framing error")]
+    Framing,
+    #[error("Break received")]
+    Break,
+}
+
+/// Bytes received by the IRQ handler and the line status errors seen since they were last read.
+pub struct SerialRx {
+    bytes: RingBuffer<u8, RX_BUFFER_SIZE>,
+    errors: AtomicU8,
+}
+
+impl SerialRx {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> SerialRx {
+        SerialRx {
+            bytes: RingBuffer::new(),
+            errors: AtomicU8::new(0),
+        }
+    }
+
+    // Pending errors are reported once, the most severe one first
+    fn take_error(&self) -> Option<SerialReadError> {
+        let errors = self.errors.swap(0, Ordering::AcqRel);
+        if errors & LSR_OVERRUN != 0 {
+            Some(SerialReadError::Overrun)
+        } else if errors & LSR_PARITY != 0 {
+            Some(SerialReadError::Parity)
+        } else if errors & LSR_FRAMING != 0 {
+            Some(SerialReadError::Framing)
+        } else if errors & LSR_BREAK != 0 {
+            Some(SerialReadError::Break)
+        } else {
+            None
+        }
+    }
+}
+
+/// Drains the receiver FIFO of the UART into `rx`.
+pub fn receive(data: &impl PortRead, line_status: &impl PortRead, rx: &SerialRx) {
+    loop {
+        let status = line_status.read();
+        rx.errors.fetch_or(status & LSR_ERRORS, Ordering::AcqRel);
+        if status & LSR_DATA_READY == 0 {
+            break;
+        }
+
+        // The status bits describe the byte at the head of the FIFO, a damaged one is dropped
+        let byte = data.read();
+        if status & (LSR_PARITY | LSR_FRAMING | LSR_BREAK) != 0 {
+            continue;
+        }
+
+        if !rx.bytes.push(byte) {
+            rx.errors.fetch_or(LSR_OVERRUN, Ordering::AcqRel);
+        }
+    }
+}
+
+/// Handler to register for the IRQ of `com`, it serves every active port sharing the line.
+pub fn irq_handler(com: ComPort) -> fn() {
+    match com.irq() {
+        4 => || handle_irq(4),
+        _ => || handle_irq(3),
+    }
+}
+
+fn handle_irq(irq: u8) {
+    for com in ComPort::ALL.into_iter().filter(|com| com.irq() == irq) {
+        // The ports stay reserved while the `Serial` of the port is alive
+        let base = ACTIVE_BASES[com.index()].load(Ordering::Acquire);
+        if base != 0 {
+            receive(
+                &Port::<u8>::new(base),
+                &ReadOnlyPort::<u8>::new(base + 5),
+                &RX[com.index()],
+            );
+        }
+    }
+}
+
+/// The eight UART registers, the status registers `S` are only read.
+pub struct SerialPorts<P: PortIo, S: PortRead> {
+    pub data: P,
+    pub enable_interrupt: P,
+    pub interrupt_id_fifo_control: P,
+    pub line_control: P,
+    pub modem_control: P,
+    pub line_status: S,
+    pub modem_status: S,
+    pub scratch: P,
+}
+
+impl SerialPorts<Port, ReadOnlyPort> {
+    /// Takes the registers from the eight ports of `range`.
+    pub fn from_range(range: &PortRange<'static>) -> SerialPorts<Port, ReadOnlyPort> {
+        SerialPorts {
+            data: range.port(0),
+            enable_interrupt: range.port(1),
+            interrupt_id_fifo_control: range.port(2),
+            line_control: range.port(3),
+            modem_control: range.port(4),
+            line_status: range.read_only_port(5),
+            modem_status: range.read_only_port(6),
+            scratch: range.port(7),
+        }
+    }
+}
+
+pub struct Serial<P: PortIo = Port, S: PortRead = ReadOnlyPort> {
+    com: Option<ComPort>,
+    data: P,
+    enable_interrupt: P,
+    interrupt_id_fifo_control: P,
+    line_control: P,
+    modem_control: P,
+    line_status: S,
+    _modem_status: S,
+    scratch: P,
+    config: SerialConfig,
+    kind: Cell<Option<UartKind>>,
+    rx: &'static SerialRx,
+    // Swallows the '\n' of a "\r\n" line ending
+    after_carriage_return: Cell<bool>,
 }
 
 impl Serial {
-    pub fn new(port_manager: &mut PortManager) -> Result<Serial, SerialInitError> {
-        use SerialInitError::*;
-
-        let data = port_manager.request_port(BASE_ADDR).ok_or(DataReserved)?;
-        let enable_interrupt = port_manager
-            .request_port(BASE_ADDR + 1)
-            .ok_or(EnableReserved)?;
-        let interrupt_id_fifo_control = port_manager
-            .request_port(BASE_ADDR + 2)
-            .ok_or(InterruptIdReserved)?;
-        let line_control = port_manager
-            .request_port(BASE_ADDR + 3)
-            .ok_or(LineControlReserved)?;
-        let modem_control = port_manager
-            .request_port(BASE_ADDR + 4)
-            .ok_or(ModemControlReserved)?;
-        let line_status = port_manager
-            .request_port(BASE_ADDR + 5)
-            .ok_or(LineStatusReserved)?;
-        let modem_status = port_manager
-            .request_port(BASE_ADDR + 6)
-            .ok_or(ModemStatusReserved)?;
-        let scratch = port_manager
-            .request_port(BASE_ADDR + 7)
-            .ok_or(ScratchReserved)?;
-        let exit = port_manager
-            .request_port(ISA_DEBUG_EXIT_PORT_NUM)
-            .ok_or(Exit)?;
-
-        Ok(Serial {
+    pub fn new(
+        port_manager: &'static PortManager,
+        com: ComPort,
+        base: u16,
+        config: SerialConfig,
+    ) -> Result<Serial, SerialInitError> {
+        let ports = port_manager
+            .request_range(base, 8, "serial")
+            .map_err(SerialInitError::Reserved)?;
+
+        let mut serial =
+            Serial::with_ports(SerialPorts::from_range(&ports), config, &RX[com.index()]);
+        serial.com = Some(com);
+        ACTIVE_BASES[com.index()].store(base, Ordering::Release);
+
+        Ok(serial)
+    }
+
+    /// Creates and initializes `com` at the base address the BIOS reported, and registers its IRQ
+    /// handler.
+    pub fn open(
+        port_manager: &'static PortManager,
+        com: ComPort,
+        config: SerialConfig,
+    ) -> Result<Serial, SerialInitError> {
+        let base = unsafe { com.bios_base() }.unwrap_or(com.base());
+        let serial = Serial::new(port_manager, com, base, config)?;
+        serial.init()?;
+        interrupt::register_irq_handler(com.irq(), irq_handler(com));
+
+        Ok(serial)
+    }
+}
+
+impl<P: PortIo, S: PortRead> Serial<P, S> {
+    /// Drives the UART whose registers are `registers`.
+    ///
+    /// `rx` is filled by the IRQ handler of the UART.
+    pub fn with_ports(
+        registers: SerialPorts<P, S>,
+        config: SerialConfig,
+        rx: &'static SerialRx,
+    ) -> Serial<P, S> {
+        let SerialPorts {
+            data,
+            enable_interrupt,
+            interrupt_id_fifo_control,
+            line_control,
+            modem_control,
+            line_status,
+            modem_status,
+            scratch,
+        } = registers;
+
+        Serial {
+            com: None,
             data,
             enable_interrupt,
             interrupt_id_fifo_control,
@@ -81,57 +282,503 @@ impl Serial {
             modem_control,
             line_status,
             _modem_status: modem_status,
-            _scratch: scratch,
-            exit,
-        })
+            scratch,
+            config,
+            kind: Cell::new(None),
+            rx,
+            after_carriage_return: Cell::new(false),
+        }
     }
 
     pub fn init(&self) -> Result<(), SerialInitError> {
-        self.enable_interrupt.writeb(0x00); // Disable all interrupts
-        self.line_control.writeb(0x80); // Enable DLAB (set baud rate divisor)
-        self.data.writeb(0x03); // Set divisor to 3 (lo byte) 38400 baud
-        self.enable_interrupt.writeb(0x00); //     (hi byte)
-        self.line_control.writeb(0x03); // 8 bits, no parity, one stop bit
-        self.interrupt_id_fifo_control.writeb(0xC7); // Enable FIFO, clear them, with 14-byte threshold
-        self.modem_control.writeb(0x0B); // IRQs enabled, RTS/DSR set
-        self.modem_control.writeb(0x1E); // Set in loopback mode, test the serial chip
-        self.data.writeb(0xAE); // Test serial chip (send byte 0xAE and check if serial returns same byte)
+        let divisor = self.config.divisor().map_err(SerialInitError::Config)?;
+
+        self.enable_interrupt.write(0x00); // Disable all interrupts
+        let kind = self.detect();
+        self.kind.set(Some(kind));
+
+        self.line_control.write(LCR_DLAB); // Enable DLAB (set baud rate divisor)
+        self.data.write(divisor as u8); // Divisor lo byte
+        self.enable_interrupt.write((divisor >> 8) as u8); // Divisor hi byte
+        self.line_control.write(self.config.line_control()); // Data bits, parity and stop bits
+
+        // Enable and clear the FIFO with the configured threshold, the 16550 FIFO is broken
+        let fifo_control = match kind {
+            UartKind::Uart16550A => {
+                FCR_ENABLE | FCR_CLEAR_RX | FCR_CLEAR_TX | self.config.fifo_control()
+            }
+            _ => 0x00,
+        };
+        self.interrupt_id_fifo_control.write(fifo_control);
+
+        self.modem_control.write(0x0B); // IRQs enabled, RTS/DSR set
+        self.modem_control.write(0x1E); // Set in loopback mode, test the serial chip
+        self.data.write(0xAE); // Test serial chip (send byte 0xAE and check if serial returns same byte)
 
         // Check if serial is faulty (i.e: not same byte as sent)
-        if self.data.readb() != 0xAE {
+        if self.data.read() != 0xAE {
             return Err(SerialInitError::Loopback);
         }
 
         // If serial is not faulty set it in normal operation mode
         // (not-loopback with IRQs enabled and OUT#1 and OUT#2 bits enabled)
-        self.modem_control.writeb(0x0F);
+        self.modem_control.write(0x0F);
+
+        // Raise an IRQ when data arrives or the line status reports an error
+        self.enable_interrupt
+            .write(IER_RECEIVED_DATA | IER_LINE_STATUS);
 
-        println!("Serial driver initialized");
+        info!("Serial driver initialized, {} UART", kind);
 
         Ok(())
     }
 
+    /// UART model found by `init`.
+    pub fn kind(&self) -> Option<UartKind> {
+        self.kind.get()
+    }
+
+    pub fn config(&self) -> &SerialConfig {
+        &self.config
+    }
+
+    // The 8250 has no scratch register, FIFO support shows in the top bits of the IIR
+    fn detect(&self) -> UartKind {
+        for pattern in [0x55, 0xAA] {
+            self.scratch.write(pattern);
+            if self.scratch.read() != pattern {
+                return UartKind::Uart8250;
+            }
+        }
+
+        self.interrupt_id_fifo_control.write(FCR_DETECT);
+        let interrupt_id = self.interrupt_id_fifo_control.read();
+        self.interrupt_id_fifo_control.write(0x00);
+
+        match interrupt_id >> 6 {
+            0b11 => UartKind::Uart16550A,
+            0b10 => UartKind::Uart16550,
+            _ => UartKind::Uart16450,
+        }
+    }
+
     pub fn write(&self, text: &str) {
         for &character in text.as_bytes() {
             self.write_serial(character)
         }
     }
 
+    /// Returns the next received byte, or the line status error that occurred before it.
+    pub fn try_read(&self) -> Result<Option<u8>, SerialReadError> {
+        if let Some(error) = self.rx.take_error() {
+            return Err(error);
+        }
+
+        Ok(self.rx.bytes.pop())
+    }
+
+    /// Waits for the next received byte, requires the IRQ handler to be registered.
+    pub fn read_byte(&self) -> Result<u8, SerialReadError> {
+        interrupt::wait_for(|| self.try_read().transpose())
+    }
+
+    /// Reads a line of printable ASCII into `buf`, echoing it back and handling backspace.
+    ///
+    /// The line ends at '\r' or '\n' and is returned without it, characters past the end of `buf`
+    /// are dropped. On an error the partial line is discarded.
+    pub fn read_line<'a>(&self, buf: &'a mut [u8]) -> Result<&'a str, SerialReadError> {
+        let mut len = 0;
+        loop {
+            let byte = self.read_byte()?;
+            let after_carriage_return = self.after_carriage_return.replace(byte == b'\r');
+
+            match byte {
+                b'\n' if after_carriage_return => continue,
+                b'\r' | b'\n' => {
+                    self.write("\r\n");
+                    break;
+                }
+                BACKSPACE | DELETE => {
+                    if len > 0 {
+                        len -= 1;
+                        self.write("\x08 \x08");
+                    }
+                }
+                b' '..=b'~' if len < buf.len() => {
+                    buf[len] = byte;
+                    len += 1;
+                    self.write_serial(byte);
+                }
+                _ => {}
+            }
+        }
+
+        // Only printable ASCII was stored
+        Ok(core::str::from_utf8(&buf[..len]).unwrap_or_default())
+    }
+
+    /// Moves the bytes waiting in the UART into the receive buffer.
+    ///
+    /// Only for polling while the IRQ handler is not registered, the buffer takes a single producer.
+    pub fn receive(&self) {
+        receive(&self.data, &self.line_status, self.rx);
+    }
+
     fn is_transmit_full(&self) -> bool {
-        self.line_status.readb() & 0x20 == 0
+        self.line_status.read() & LSR_TRANSMIT_EMPTY == 0
     }
 
     fn write_serial(&self, character: u8) {
         while self.is_transmit_full() {}
 
-        self.data.writeb(character);
+        self.data.write(character);
+    }
+}
+
+impl<P: PortIo, S: PortRead> Drop for Serial<P, S> {
+    fn drop(&mut self) {
+        if let Some(com) = self.com {
+            ACTIVE_BASES[com.index()].store(0, Ordering::Release);
+        }
+    }
+}
+
+/// Writes `text` to the first open serial port, without going through its `Serial`.
+///
+/// Meant for the panic path, which cannot wait for whoever holds the display. A transmitter
+/// that never gets ready is given up on.
+pub fn write_raw(text: &str) {
+    let Some(base) = ACTIVE_BASES
+        .iter()
+        .map(|base| base.load(Ordering::Acquire))
+        .find(|&base| base != 0)
+    else {
+        return;
+    };
+
+    let data = Port::<u8>::new(base);
+    let line_status = ReadOnlyPort::<u8>::new(base + 5);
+    for &byte in text.as_bytes() {
+        let ready = (0..RAW_WRITE_SPINS).any(|_| line_status.read() & LSR_TRANSMIT_EMPTY != 0);
+        if !ready {
+            return;
+        }
+        data.write(byte);
     }
 }
 
-impl Write for Serial {
+impl<P: PortIo, S: PortRead> Write for Serial<P, S> {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
         self.write(s);
 
         Ok(())
     }
 }
+
+impl<P: PortIo + 'static, S: PortRead + 'static> ConsoleSink for Serial<P, S> {
+    fn name(&self) -> &'static str {
+        "serial"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::io::fake_port::{Access, FakeBus, FakePort, Registers};
+
+    const BASE_ADDR: u16 = 0x3F8;
+    const INTERRUPT_ID: u16 = BASE_ADDR + 2;
+    const LINE_CONTROL: u16 = BASE_ADDR + 3;
+    const LINE_STATUS: u16 = BASE_ADDR + 5;
+    const SCRATCH: u16 = BASE_ADDR + 7;
+
+    type FakeSerial = Serial<FakePort, FakePort>;
+
+    fn fake_serial(bus: &Rc<FakeBus>) -> FakeSerial {
+        fake_serial_with_config(bus, SerialConfig::default())
+    }
+
+    fn fake_serial_with_config(bus: &Rc<FakeBus>, config: SerialConfig) -> FakeSerial {
+        let port = |offset| bus.port(BASE_ADDR + offset);
+        Serial::with_ports(
+            SerialPorts {
+                data: port(0),
+                enable_interrupt: port(1),
+                interrupt_id_fifo_control: port(2),
+                line_control: port(3),
+                modem_control: port(4),
+                line_status: port(5),
+                modem_status: port(6),
+                scratch: port(7),
+            },
+            config,
+            Box::leak(Box::new(SerialRx::new())),
+        )
+    }
+
+    fn written(bus: &FakeBus) -> Vec<u8> {
+        bus.log()
+            .iter()
+            .filter_map(|access| match access {
+                Access::Write(BASE_ADDR, byte) => Some(*byte),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn attach_uart(bus: &FakeBus) -> Rc<RefCell<Registers>> {
+        let uart = Rc::new(RefCell::new(Registers::new()));
+        bus.attach(BASE_ADDR, 8, uart.clone());
+
+        uart
+    }
+
+    #[test]
+    fn init_programs_uart() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+
+        fake_serial(&bus).init().unwrap();
+
+        let uart = uart.borrow();
+        assert_eq!(uart.get(LINE_CONTROL), 0x03);
+        assert_eq!(uart.get(INTERRUPT_ID), 0xC7);
+        assert_eq!(uart.get(BASE_ADDR + 4), 0x0F);
+    }
+
+    #[test]
+    fn init_applies_config() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        let config = SerialConfig::parse("9600,7e2,fifo4").unwrap();
+
+        let serial = fake_serial_with_config(&bus, config);
+        serial.init().unwrap();
+
+        let writes: Vec<Access> = bus
+            .log()
+            .into_iter()
+            .filter(|access| matches!(access, Access::Write(BASE_ADDR..=LINE_CONTROL, _)))
+            .skip_while(|&access| access != Access::Write(LINE_CONTROL, LCR_DLAB))
+            .take(5)
+            .collect();
+        assert_eq!(
+            writes,
+            [
+                Access::Write(LINE_CONTROL, LCR_DLAB),
+                Access::Write(BASE_ADDR, 12),
+                Access::Write(BASE_ADDR + 1, 0),
+                Access::Write(LINE_CONTROL, 0x1E),
+                Access::Write(INTERRUPT_ID, 0x47),
+            ]
+        );
+        assert_eq!(uart.borrow().get(LINE_CONTROL), 0x1E);
+    }
+
+    #[test]
+    fn init_rejects_invalid_baud_rate() {
+        let bus = FakeBus::new();
+        attach_uart(&bus);
+        let config = SerialConfig {
+            baud_rate: 1000,
+            ..SerialConfig::default()
+        };
+
+        assert!(matches!(
+            fake_serial_with_config(&bus, config).init(),
+            Err(SerialInitError::Config(SerialConfigError::InvalidBaudRate(
+                1000
+            )))
+        ));
+        assert!(bus.log().is_empty());
+    }
+
+    #[test]
+    fn detects_16550a() {
+        let bus = FakeBus::new();
+        attach_uart(&bus);
+        let serial = fake_serial(&bus);
+
+        serial.init().unwrap();
+
+        assert_eq!(serial.kind(), Some(UartKind::Uart16550A));
+    }
+
+    #[test]
+    fn detects_16550_and_leaves_fifo_off() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        uart.borrow_mut().script(INTERRUPT_ID, &[0x81]);
+        let serial = fake_serial(&bus);
+
+        serial.init().unwrap();
+
+        assert_eq!(serial.kind(), Some(UartKind::Uart16550));
+        assert_eq!(uart.borrow().get(INTERRUPT_ID), 0x00);
+    }
+
+    #[test]
+    fn detects_16450() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        uart.borrow_mut().script(INTERRUPT_ID, &[0x01]);
+        let serial = fake_serial(&bus);
+
+        serial.init().unwrap();
+
+        assert_eq!(serial.kind(), Some(UartKind::Uart16450));
+    }
+
+    #[test]
+    fn detects_8250_without_scratch_register() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        uart.borrow_mut().script(SCRATCH, &[0x00]);
+        let serial = fake_serial(&bus);
+
+        serial.init().unwrap();
+
+        assert_eq!(serial.kind(), Some(UartKind::Uart8250));
+        assert_eq!(uart.borrow().get(INTERRUPT_ID), 0x00);
+    }
+
+    #[test]
+    fn init_fails_without_uart() {
+        // Nothing answers on the bus, the loopback byte reads back as 0xFF
+        let bus = FakeBus::new();
+
+        assert!(matches!(
+            fake_serial(&bus).init(),
+            Err(SerialInitError::Loopback)
+        ));
+    }
+
+    #[test]
+    fn init_fails_on_loopback_mismatch() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        uart.borrow_mut().script(BASE_ADDR, &[0x00]);
+
+        assert!(matches!(
+            fake_serial(&bus).init(),
+            Err(SerialInitError::Loopback)
+        ));
+    }
+
+    #[test]
+    fn write_waits_for_empty_transmitter() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        {
+            let mut uart = uart.borrow_mut();
+            uart.set(LINE_STATUS, 0x20);
+            uart.script(LINE_STATUS, &[0x00, 0x00]);
+        }
+        let serial = fake_serial(&bus);
+
+        serial.write("ok");
+
+        let accesses: Vec<Access> = bus.log();
+        assert_eq!(
+            accesses,
+            [
+                Access::Read(LINE_STATUS, 0x00),
+                Access::Read(LINE_STATUS, 0x00),
+                Access::Read(LINE_STATUS, 0x20),
+                Access::Write(BASE_ADDR, b'o'),
+                Access::Read(LINE_STATUS, 0x20),
+                Access::Write(BASE_ADDR, b'k'),
+            ]
+        );
+    }
+
+    #[test]
+    fn receive_drops_damaged_bytes() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        {
+            let mut uart = uart.borrow_mut();
+            uart.script(LINE_STATUS, &[LSR_DATA_READY | LSR_PARITY, LSR_DATA_READY]);
+            uart.script(BASE_ADDR, &[b'?', b'a']);
+        }
+        let serial = fake_serial(&bus);
+
+        serial.receive();
+
+        assert_eq!(serial.try_read(), Err(SerialReadError::Parity));
+        assert_eq!(serial.try_read(), Ok(Some(b'a')));
+        assert_eq!(serial.try_read(), Ok(None));
+    }
+
+    #[test]
+    fn receive_reports_overrun() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        {
+            let mut uart = uart.borrow_mut();
+            uart.script(LINE_STATUS, &[LSR_DATA_READY | LSR_OVERRUN]);
+            uart.script(BASE_ADDR, &[b'a']);
+        }
+        let serial = fake_serial(&bus);
+
+        serial.receive();
+
+        // The byte itself is intact, the ones before it were lost
+        assert_eq!(serial.try_read(), Err(SerialReadError::Overrun));
+        assert_eq!(serial.try_read(), Ok(Some(b'a')));
+    }
+
+    #[test]
+    fn receive_reports_full_buffer_as_overrun() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        {
+            let mut uart = uart.borrow_mut();
+            uart.script(LINE_STATUS, &[LSR_DATA_READY; RX_BUFFER_SIZE + 1]);
+            uart.script(BASE_ADDR, &[b'x'; RX_BUFFER_SIZE + 1]);
+        }
+        let serial = fake_serial(&bus);
+
+        serial.receive();
+
+        assert_eq!(serial.try_read(), Err(SerialReadError::Overrun));
+        assert_eq!(serial.rx.bytes.len(), RX_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn read_line_edits_and_echoes() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        uart.borrow_mut().set(LINE_STATUS, LSR_TRANSMIT_EMPTY);
+        let serial = fake_serial(&bus);
+        for &byte in b"ls\x7fs -l\r\nab\n" {
+            serial.rx.bytes.push(byte);
+        }
+
+        let mut buf = [0; 16];
+        assert_eq!(serial.read_line(&mut buf), Ok("ls -l"));
+        assert_eq!(written(&bus), b"ls\x08 \x08s -l\r\n");
+
+        // The '\n' of the "\r\n" does not end an empty line
+        assert_eq!(serial.read_line(&mut buf), Ok("ab"));
+    }
+
+    #[test]
+    fn read_line_truncates_to_buffer() {
+        let bus = FakeBus::new();
+        let uart = attach_uart(&bus);
+        uart.borrow_mut().set(LINE_STATUS, LSR_TRANSMIT_EMPTY);
+        let serial = fake_serial(&bus);
+        for &byte in b"abcdef\r" {
+            serial.rx.bytes.push(byte);
+        }
+
+        let mut buf = [0; 4];
+        assert_eq!(serial.read_line(&mut buf), Ok("abcd"));
+    }
+}
@@ -0,0 +1,250 @@
+use core::fmt;
+use core::str::FromStr;
+use thiserror_no_std::Error;
+
+// Frequency of the UART clock divided by 16
+const MAX_BAUD_RATE: u32 = 115200;
+
+// COM port table of the BIOS Data Area
+const BDA_COM_PORTS: usize = 0x400;
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum SerialConfigError {
+    #[error("Unknown serial port")]
+    UnknownPort,
+    #[error("Unsupported baud rate {0}")]
+    InvalidBaudRate(u32),
+    #[error("Baud rate \"{0}\" is not a number")]
+    MalformedBaudRate(&'static str),
+    #[error("Invalid data bits, parity or stop bits")]
+    InvalidFormat,
+    #[error("Invalid FIFO threshold")]
+    InvalidFifoThreshold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComPort {
+    Com1,
+    Com2,
+    Com3,
+    Com4,
+}
+
+impl ComPort {
+    pub const ALL: [ComPort; 4] = [ComPort::Com1, ComPort::Com2, ComPort::Com3, ComPort::Com4];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Conventional I/O base address.
+    pub fn base(self) -> u16 {
+        match self {
+            ComPort::Com1 => 0x3F8,
+            ComPort::Com2 => 0x2F8,
+            ComPort::Com3 => 0x3E8,
+            ComPort::Com4 => 0x2E8,
+        }
+    }
+
+    /// I/O base address the BIOS found for the port, if any.
+    ///
+    /// Only meaningful on BIOS boots, where the BIOS Data Area is left intact.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn bios_base(self) -> Option<u16> {
+        let base = (BDA_COM_PORTS as *const u16)
+            .add(self.index())
+            .read_volatile();
+
+        (base != 0).then_some(base)
+    }
+
+    /// COM1 and COM3 share IRQ4, COM2 and COM4 share IRQ3.
+    pub fn irq(self) -> u8 {
+        match self {
+            ComPort::Com1 | ComPort::Com3 => 4,
+            ComPort::Com2 | ComPort::Com4 => 3,
+        }
+    }
+}
+
+impl fmt::Display for ComPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "COM{}", self.index() + 1)
+    }
+}
+
+impl FromStr for ComPort {
+    type Err = SerialConfigError;
+
+    fn from_str(name: &str) -> Result<ComPort, SerialConfigError> {
+        ComPort::ALL
+            .into_iter()
+            .find(|port| {
+                let number = (b'1' + port.index() as u8) as char;
+                // Bytes, the name may have a multi-byte character where the number should be
+                name.len() == 4
+                    && name.as_bytes()[..3].eq_ignore_ascii_case(b"com")
+                    && name.ends_with(number)
+            })
+            .ok_or(SerialConfigError::UnknownPort)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataBits {
+    Five,
+    Six,
+    Seven,
+    Eight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+    Mark,
+    Space,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopBits {
+    One,
+    Two,
+}
+
+/// Number of received bytes in the FIFO that raise an interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FifoThreshold {
+    Bytes1,
+    Bytes4,
+    Bytes8,
+    Bytes14,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialConfig {
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub fifo_threshold: FifoThreshold,
+}
+
+impl Default for SerialConfig {
+    fn default() -> SerialConfig {
+        SerialConfig {
+            baud_rate: 38400,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            fifo_threshold: FifoThreshold::Bytes14,
+        }
+    }
+}
+
+impl SerialConfig {
+    /// Divisor latch value, only exact divisions of 115200 are supported.
+    pub fn divisor(&self) -> Result<u16, SerialConfigError> {
+        MAX_BAUD_RATE
+            .checked_div(self.baud_rate)
+            .filter(|divisor| divisor * self.baud_rate == MAX_BAUD_RATE)
+            .map(|divisor| divisor as u16)
+            .ok_or(SerialConfigError::InvalidBaudRate(self.baud_rate))
+    }
+
+    /// Value of the line control register, with the divisor latch disabled.
+    pub fn line_control(&self) -> u8 {
+        let data_bits = self.data_bits as u8;
+        let stop_bits = match self.stop_bits {
+            StopBits::One => 0,
+            StopBits::Two => 1 << 2,
+        };
+        let parity = match self.parity {
+            Parity::None => 0b000,
+            Parity::Odd => 0b001,
+            Parity::Even => 0b011,
+            Parity::Mark => 0b101,
+            Parity::Space => 0b111,
+        };
+
+        data_bits | stop_bits | parity << 3
+    }
+
+    /// Trigger level bits of the FIFO control register.
+    pub fn fifo_control(&self) -> u8 {
+        (self.fifo_threshold as u8) << 6
+    }
+}
+
+impl SerialConfig {
+    /// Parses `<baud>[,<data bits><parity><stop bits>[,fifo<threshold>]]`, e.g.
+    /// `115200,8n1,fifo14`.
+    ///
+    /// Parity is one of `n`, `o`, `e`, `m` or `s`, omitted parts keep their default. The spec
+    /// comes from the command line, so errors can point into it.
+    pub fn parse(spec: &'static str) -> Result<SerialConfig, SerialConfigError> {
+        use SerialConfigError::*;
+
+        let mut config = SerialConfig::default();
+        let mut parts = spec.split(',');
+
+        if let Some(baud_rate) = parts.next().filter(|part| !part.is_empty()) {
+            config.baud_rate = baud_rate
+                .parse()
+                .map_err(|_| MalformedBaudRate(baud_rate))?;
+            config.divisor()?;
+        }
+
+        if let Some(format) = parts.next() {
+            let &[data_bits, parity, stop_bits] = format.as_bytes() else {
+                return Err(InvalidFormat);
+            };
+
+            config.data_bits = match data_bits {
+                b'5' => DataBits::Five,
+                b'6' => DataBits::Six,
+                b'7' => DataBits::Seven,
+                b'8' => DataBits::Eight,
+                _ => return Err(InvalidFormat),
+            };
+            config.parity = match parity.to_ascii_lowercase() {
+                b'n' => Parity::None,
+                b'o' => Parity::Odd,
+                b'e' => Parity::Even,
+                b'm' => Parity::Mark,
+                b's' => Parity::Space,
+                _ => return Err(InvalidFormat),
+            };
+            config.stop_bits = match stop_bits {
+                b'1' => StopBits::One,
+                b'2' => StopBits::Two,
+                _ => return Err(InvalidFormat),
+            };
+        }
+
+        if let Some(fifo) = parts.next() {
+            config.fifo_threshold = match fifo.strip_prefix("fifo") {
+                Some("1") => FifoThreshold::Bytes1,
+                Some("4") => FifoThreshold::Bytes4,
+                Some("8") => FifoThreshold::Bytes8,
+                Some("14") => FifoThreshold::Bytes14,
+                _ => return Err(InvalidFifoThreshold),
+            };
+        }
+
+        match parts.next() {
+            Some(_) => Err(InvalidFormat),
+            None => Ok(config),
+        }
+    }
+}
+
+/// Parses a serial console option such as `com2,115200,8n1`, see [`SerialConfig`] for the
+/// format following the port.
+pub fn parse_console(spec: &'static str) -> Result<(ComPort, SerialConfig), SerialConfigError> {
+    let (port, config) = spec.split_once(',').unwrap_or((spec, ""));
+
+    Ok((port.parse()?, SerialConfig::parse(config)?))
+}
@@ -0,0 +1,233 @@
+use alloc::string::String;
+use core::any::Any;
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use log::{Level, LevelFilter};
+use thiserror_no_std::Error;
+
+pub const MAX_SINKS: usize = 8;
+
+/// Output of the display, `print!` and the logger write to every enabled sink.
+pub trait ConsoleSink: Write + Any {
+    /// Short name, `log.<name>=<level>` on the command line sets the level of the sink.
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("No room for more than {} console sinks", MAX_SINKS)]
+pub struct RegistryFull;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinkId(usize);
+
+/// Settings and state of a registered sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinkInfo {
+    pub id: SinkId,
+    pub name: &'static str,
+    pub enabled: bool,
+    /// Least severe log level written to the sink, `print!` output is not filtered
+    pub level: LevelFilter,
+    /// Writes that returned an error, the output they held is lost to this sink
+    pub errors: usize,
+}
+
+struct Registered {
+    sink: &'static mut dyn ConsoleSink,
+    enabled: bool,
+    level: LevelFilter,
+    errors: usize,
+}
+
+impl Registered {
+    fn write(&mut self, write: &mut dyn FnMut(&mut dyn Write) -> fmt::Result) {
+        if write(self.sink).is_err() {
+            self.errors += 1;
+        }
+    }
+}
+
+/// Sinks that are registered with the display.
+///
+/// Sinks are kept by `'static` reference so they can be added before the heap is up, see
+/// [`SinkCell`].
+pub struct SinkRegistry {
+    sinks: [Option<Registered>; MAX_SINKS],
+}
+
+impl Default for SinkRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SinkRegistry {
+    pub const fn new() -> SinkRegistry {
+        SinkRegistry {
+            sinks: [const { None }; MAX_SINKS],
+        }
+    }
+
+    /// Adds an enabled sink that takes every log level.
+    pub fn register(&mut self, sink: &'static mut dyn ConsoleSink) -> Result<SinkId, RegistryFull> {
+        let (index, slot) = self
+            .sinks
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| slot.is_none())
+            .ok_or(RegistryFull)?;
+
+        *slot = Some(Registered {
+            sink,
+            enabled: true,
+            level: LevelFilter::Trace,
+            errors: 0,
+        });
+
+        Ok(SinkId(index))
+    }
+
+    /// Removes the sink and hands it back.
+    pub fn unregister(&mut self, id: SinkId) -> Option<&'static mut dyn ConsoleSink> {
+        self.sinks[id.0].take().map(|registered| registered.sink)
+    }
+
+    pub fn info(&self, id: SinkId) -> Option<SinkInfo> {
+        let registered = self.sinks.get(id.0)?.as_ref()?;
+
+        Some(SinkInfo {
+            id,
+            name: registered.sink.name(),
+            enabled: registered.enabled,
+            level: registered.level,
+            errors: registered.errors,
+        })
+    }
+
+    pub fn sinks(&self) -> impl Iterator<Item = SinkInfo> + '_ {
+        (0..MAX_SINKS).filter_map(|index| self.info(SinkId(index)))
+    }
+
+    /// First sink called `name`.
+    pub fn find(&self, name: &str) -> Option<SinkId> {
+        self.sinks()
+            .find(|info| info.name == name)
+            .map(|info| info.id)
+    }
+
+    pub fn set_enabled(&mut self, id: SinkId, enabled: bool) {
+        if let Some(registered) = &mut self.sinks[id.0] {
+            registered.enabled = enabled;
+        }
+    }
+
+    pub fn set_level(&mut self, id: SinkId, level: LevelFilter) {
+        if let Some(registered) = &mut self.sinks[id.0] {
+            registered.level = level;
+        }
+    }
+
+    /// First sink of type `T`, for driver specific functions such as reading from the serial port.
+    pub fn get<T: ConsoleSink>(&self) -> Option<&T> {
+        self.sinks
+            .iter()
+            .flatten()
+            .find_map(|registered| (registered.sink as &dyn Any).downcast_ref::<T>())
+    }
+
+    /// First sink of type `T`, for driver specific functions such as VGA scrolling.
+    pub fn get_mut<T: ConsoleSink>(&mut self) -> Option<&mut T> {
+        self.sinks
+            .iter_mut()
+            .flatten()
+            .find_map(|registered| (registered.sink as &mut dyn Any).downcast_mut::<T>())
+    }
+
+    pub fn sink_mut(&mut self, id: SinkId) -> Option<&mut dyn ConsoleSink> {
+        self.sinks[id.0]
+            .as_mut()
+            .map(|registered| &mut *registered.sink)
+    }
+
+    /// Writes `args` to every enabled sink, a sink that fails does not keep the others from
+    /// getting it.
+    pub fn print(&mut self, args: fmt::Arguments) {
+        for registered in self.enabled() {
+            registered.write(&mut |out| out.write_fmt(args));
+        }
+    }
+
+    /// Calls `write` for every enabled sink that takes `level`.
+    pub fn log(&mut self, level: Level, mut write: impl FnMut(&mut dyn Write) -> fmt::Result) {
+        for registered in self
+            .enabled()
+            .filter(|registered| level <= registered.level)
+        {
+            registered.write(&mut write);
+        }
+    }
+
+    fn enabled(&mut self) -> impl Iterator<Item = &mut Registered> {
+        self.sinks
+            .iter_mut()
+            .flatten()
+            .filter(|registered| registered.enabled)
+    }
+}
+
+/// Static storage for a sink, so it can be registered before there is a heap.
+pub struct SinkCell<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    taken: AtomicBool,
+}
+
+unsafe impl<T> Sync for SinkCell<T> {}
+
+impl<T> Default for SinkCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SinkCell<T> {
+    pub const fn new() -> SinkCell<T> {
+        SinkCell {
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            taken: AtomicBool::new(false),
+        }
+    }
+
+    /// Moves `value` into the cell, fails if the cell already holds one.
+    // The value is only ever handed out once, so the reference is unique
+    #[allow(clippy::mut_from_ref)]
+    pub fn put(&'static self, value: T) -> Result<&'static mut T, T> {
+        if self.taken.swap(true, Ordering::AcqRel) {
+            return Err(value);
+        }
+
+        Ok(unsafe { (*self.value.get()).write(value) })
+    }
+}
+
+/// Sink that collects its output in memory, e.g. to check the output in tests.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    pub text: String,
+}
+
+impl Write for MemorySink {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.text.push_str(s);
+
+        Ok(())
+    }
+}
+
+impl ConsoleSink for MemorySink {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+}
@@ -1,10 +1,48 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
 use core::fmt::Write; // Write Formatted arguments
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::io::ansi::{Action, Csi, Parser};
+use crate::io::cp437;
+use crate::io::port_manager::{Port, PortError, PortManager};
+use crate::io::sink::ConsoleSink;
 
 // VGA text mode color constants
-const VGA_WIDTH: usize = 80;
-const VGA_HEIGHT: usize = 25;
+pub const VGA_WIDTH: usize = 80;
+pub const VGA_HEIGHT: usize = 25;
+const VGA_BUFFER: usize = 0xB8000;
+
+// Set once a `Terminal` draws into the VGA memory, the panic path only writes there then
+static TEXT_MODE: AtomicBool = AtomicBool::new(false);
+
+const TAB_WIDTH: usize = 8;
+const BACKSPACE: u8 = 0x08;
+
+// CRT controller index and data ports, and the registers behind them
+const CRTC_INDEX: u16 = 0x3D4;
+const CURSOR_START: u8 = 0x0A;
+const CURSOR_END: u8 = 0x0B;
+const CURSOR_LOCATION_HIGH: u8 = 0x0E;
+const CURSOR_LOCATION_LOW: u8 = 0x0F;
+// Underline cursor on the bottom two scan lines of the 16 line character cell
+const CURSOR_SCANLINES: (u8, u8) = (14, 15);
+
+// The font lives in plane 2, which the sequencer and graphics controller map to 0xA0000
+const SEQUENCER_INDEX: u16 = 0x3C4;
+const SEQUENCER_MAP_MASK: u8 = 0x02;
+const SEQUENCER_MEMORY_MODE: u8 = 0x04;
+const GRAPHICS_INDEX: u16 = 0x3CE;
+const GRAPHICS_READ_MAP: u8 = 0x04;
+const GRAPHICS_MODE: u8 = 0x05;
+const GRAPHICS_MISC: u8 = 0x06;
+const FONT_BUFFER: usize = 0xA0000;
+// Every character has a 32 byte slot, text mode only shows the first 16 scan lines
+const GLYPH_SLOT: usize = 32;
+pub const GLYPH_HEIGHT: usize = 16;
 
 #[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VgaColor {
     Black,
     Blue,
@@ -24,76 +62,545 @@ pub enum VgaColor {
     White,
 }
 
+// Indexed by the color numbers of SGR 30-37 and 40-47, bright variants are 8 further
+const ANSI_COLORS: [VgaColor; 16] = [
+    VgaColor::Black,
+    VgaColor::Red,
+    VgaColor::Green,
+    VgaColor::Brown,
+    VgaColor::Blue,
+    VgaColor::Magneta,
+    VgaColor::Cyan,
+    VgaColor::LightGrey,
+    VgaColor::DarkGrey,
+    VgaColor::LightRed,
+    VgaColor::LightGreen,
+    VgaColor::LightBrown,
+    VgaColor::LightBlue,
+    VgaColor::LightMagneta,
+    VgaColor::LightCyan,
+    VgaColor::White,
+];
+
+impl VgaColor {
+    // Bold text is drawn in the bright variant of the color
+    fn bright(self) -> VgaColor {
+        ANSI_COLORS
+            .iter()
+            .position(|&color| color == self)
+            .filter(|&index| index < 8)
+            .map_or(self, |index| ANSI_COLORS[index + 8])
+    }
+}
+
+struct Cursor {
+    index: Port,
+    data: Port,
+}
+
+impl Cursor {
+    fn write_register(&self, register: u8, val: u8) {
+        self.index.write(register);
+        self.data.write(val);
+    }
+
+    fn read_register(&self, register: u8) -> u8 {
+        self.index.write(register);
+        self.data.read()
+    }
+}
+
+// Lines that scrolled off the top of the screen, oldest first
+struct Scrollback {
+    lines: VecDeque<[u16; VGA_WIDTH]>,
+    capacity: usize,
+    // How many lines the view is scrolled back, 0 shows the live screen
+    offset: usize,
+    // The live screen, saved while the view shows the history
+    live: Vec<u16>,
+}
+
+/// Replaces the VGA font glyphs from character `first` on, one byte per scan line.
+///
+/// Only valid while the VGA is in text mode, glyphs past character 0xFF are ignored.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn load_glyphs(
+    port_manager: &'static PortManager,
+    first: u8,
+    glyphs: &[[u8; GLYPH_HEIGHT]],
+) -> Result<(), PortError> {
+    let sequencer = port_manager.request_range(SEQUENCER_INDEX, 2, "vga sequencer")?;
+    let graphics = port_manager.request_range(GRAPHICS_INDEX, 2, "vga graphics")?;
+    let write_sequencer = |register: u8, val: u8| {
+        sequencer.port::<u8>(0).write(register);
+        sequencer.port::<u8>(1).write(val);
+    };
+    let write_graphics = |register: u8, val: u8| {
+        graphics.port::<u8>(0).write(register);
+        graphics.port::<u8>(1).write(val);
+    };
+
+    // Sequential access to plane 2 at 0xA0000
+    write_sequencer(SEQUENCER_MAP_MASK, 0x04);
+    write_sequencer(SEQUENCER_MEMORY_MODE, 0x07);
+    write_graphics(GRAPHICS_READ_MAP, 0x02);
+    write_graphics(GRAPHICS_MODE, 0x00);
+    write_graphics(GRAPHICS_MISC, 0x04);
+
+    for (character, glyph) in (first..=u8::MAX).zip(glyphs) {
+        let slot = (FONT_BUFFER + character as usize * GLYPH_SLOT) as *mut u8;
+        for (line, &bits) in glyph.iter().enumerate() {
+            slot.add(line).write_volatile(bits);
+        }
+    }
+
+    // Back to the odd/even text mode layout of planes 0 and 1 at 0xB8000
+    write_sequencer(SEQUENCER_MAP_MASK, 0x03);
+    write_sequencer(SEQUENCER_MEMORY_MODE, 0x03);
+    write_graphics(GRAPHICS_READ_MAP, 0x00);
+    write_graphics(GRAPHICS_MODE, 0x10);
+    write_graphics(GRAPHICS_MISC, 0x0E);
+
+    Ok(())
+}
+
 pub struct Terminal {
     terminal_row: usize,
     terminal_column: usize,
     terminal_color: u8,
     terminal_buffer: *mut u16,
+    cursor: Option<Cursor>,
+    parser: Parser,
+    foreground: VgaColor,
+    background: VgaColor,
+    bold: bool,
+    // Colors restored by SGR 0, 39 and 49
+    default_colors: (VgaColor, VgaColor),
+    scrollback: Option<Scrollback>,
+    // Mouse pointer cell, drawn by swapping its foreground and background colors
+    pointer: Option<(usize, usize)>,
+    pointer_drawn: bool,
 }
 
 impl Write for Terminal {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        self.write_text(s.as_bytes());
+        self.undraw_pointer();
+        self.reset_view();
+        for character in s.chars() {
+            self.write_byte(cp437::from_char(character).unwrap_or(cp437::REPLACEMENT));
+        }
+        self.update_cursor();
+        self.draw_pointer();
 
         Ok(())
     }
 }
 
+impl ConsoleSink for Terminal {
+    fn name(&self) -> &'static str {
+        "vga"
+    }
+}
+
 impl Terminal {
     pub fn new(fore_ground_color: VgaColor, back_ground_color: VgaColor) -> Terminal {
-        let terminal_row = 0;
-        let terminal_column = 0;
-        let terminal_color = Terminal::set_color(fore_ground_color, back_ground_color);
-        let terminal_buffer = 0xB8000 as *mut u16;
+        TEXT_MODE.store(true, Ordering::Release);
+        unsafe {
+            Terminal::with_buffer(VGA_BUFFER as *mut u16, fore_ground_color, back_ground_color)
+        }
+    }
 
+    /// Draws into `buffer` instead of the VGA memory, it has to hold `VGA_WIDTH * VGA_HEIGHT`
+    /// characters.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn with_buffer(
+        buffer: *mut u16,
+        fore_ground_color: VgaColor,
+        back_ground_color: VgaColor,
+    ) -> Terminal {
         Terminal {
-            terminal_row,
-            terminal_column,
-            terminal_color,
-            terminal_buffer,
+            terminal_row: 0,
+            terminal_column: 0,
+            terminal_color: Terminal::color_code(fore_ground_color, back_ground_color),
+            terminal_buffer: buffer,
+            cursor: None,
+            parser: Parser::new(),
+            foreground: fore_ground_color,
+            background: back_ground_color,
+            bold: false,
+            default_colors: (fore_ground_color, back_ground_color),
+            scrollback: None,
+            pointer: None,
+            pointer_drawn: false,
         }
     }
 
     pub fn init(&mut self) -> core::fmt::Result {
-        for y in 0..VGA_HEIGHT {
-            for x in 0..VGA_WIDTH {
-                let index = y * VGA_WIDTH + x;
-                unsafe {
-                    *self.terminal_buffer.add(index) = self.set_screen_character(b' ');
+        self.clear();
+
+        Ok(())
+    }
+
+    /// Takes over the blinking hardware cursor, which then follows the text.
+    pub fn enable_cursor(&mut self, port_manager: &'static PortManager) -> Result<(), PortError> {
+        let ports = port_manager.request_range(CRTC_INDEX, 2, "vga crtc")?;
+        let cursor = Cursor {
+            index: ports.port(0),
+            data: ports.port(1),
+        };
+
+        // Bit 5 of the start register disables the cursor, the top bits are reserved
+        let start = cursor.read_register(CURSOR_START) & 0xC0;
+        cursor.write_register(CURSOR_START, start | CURSOR_SCANLINES.0);
+        let end = cursor.read_register(CURSOR_END) & 0xE0;
+        cursor.write_register(CURSOR_END, end | CURSOR_SCANLINES.1);
+
+        self.cursor = Some(cursor);
+        self.update_cursor();
+
+        Ok(())
+    }
+
+    /// Keeps up to `lines` lines scrolled off the screen on the heap so they can be viewed again.
+    pub fn enable_scrollback(&mut self, lines: usize) {
+        self.reset_view();
+        self.scrollback = Some(Scrollback {
+            lines: VecDeque::with_capacity(lines),
+            capacity: lines,
+            offset: 0,
+            live: Vec::new(),
+        });
+    }
+
+    /// Number of lines kept in the scrollback history.
+    pub fn history_len(&self) -> usize {
+        self.scrollback
+            .as_ref()
+            .map_or(0, |scrollback| scrollback.lines.len())
+    }
+
+    /// Returns how many lines the view is scrolled back into the history.
+    pub fn view_offset(&self) -> usize {
+        self.scrollback
+            .as_ref()
+            .map_or(0, |scrollback| scrollback.offset)
+    }
+
+    /// Shows the history `offset` lines above the live screen, clamped to the history length.
+    ///
+    /// Writing to the terminal returns the view to the live screen.
+    pub fn set_view_offset(&mut self, offset: usize) {
+        self.undraw_pointer();
+        self.move_view(offset);
+        self.draw_pointer();
+    }
+
+    fn move_view(&mut self, offset: usize) {
+        let buffer = self.terminal_buffer;
+        let Some(scrollback) = &mut self.scrollback else {
+            return;
+        };
+
+        let offset = offset.min(scrollback.lines.len());
+        if offset == scrollback.offset {
+            return;
+        }
+
+        let screen = unsafe { core::slice::from_raw_parts_mut(buffer, VGA_WIDTH * VGA_HEIGHT) };
+        if scrollback.offset == 0 {
+            scrollback.live = screen.to_vec();
+        }
+        scrollback.offset = offset;
+
+        // The view is a window over the history followed by the live screen
+        let first_line = scrollback.lines.len() - offset;
+        for (row, line) in screen.chunks_exact_mut(VGA_WIDTH).enumerate() {
+            let index = first_line + row;
+            match scrollback.lines.get(index) {
+                Some(history) => line.copy_from_slice(history),
+                None => {
+                    let live_row = index - scrollback.lines.len();
+                    line.copy_from_slice(
+                        &scrollback.live[live_row * VGA_WIDTH..(live_row + 1) * VGA_WIDTH],
+                    );
                 }
             }
         }
 
-        Ok(())
+        if offset == 0 {
+            scrollback.live = Vec::new();
+        }
+    }
+
+    pub fn scroll_view_up(&mut self, lines: usize) {
+        self.set_view_offset(self.view_offset().saturating_add(lines));
+    }
+
+    pub fn scroll_view_down(&mut self, lines: usize) {
+        self.set_view_offset(self.view_offset().saturating_sub(lines));
+    }
+
+    fn reset_view(&mut self) {
+        self.move_view(0);
+    }
+
+    pub fn clear(&mut self) {
+        self.undraw_pointer();
+        self.reset_view();
+        self.erase(0, VGA_WIDTH * VGA_HEIGHT);
+        self.set_position(0, 0);
+        self.draw_pointer();
+    }
+
+    /// Draws the mouse pointer at a cell, positions outside of the screen are clamped to its edges.
+    pub fn show_pointer(&mut self, row: usize, column: usize) {
+        self.undraw_pointer();
+        self.pointer = Some((row.min(VGA_HEIGHT - 1), column.min(VGA_WIDTH - 1)));
+        self.draw_pointer();
+    }
+
+    pub fn hide_pointer(&mut self) {
+        self.undraw_pointer();
+        self.pointer = None;
+    }
+
+    fn draw_pointer(&mut self) {
+        if !self.pointer_drawn {
+            self.pointer_drawn = self.invert_pointer_cell();
+        }
+    }
+
+    fn undraw_pointer(&mut self) {
+        if self.pointer_drawn {
+            self.invert_pointer_cell();
+            self.pointer_drawn = false;
+        }
+    }
+
+    // Swaps the colors of the pointer cell, returns false without a pointer
+    fn invert_pointer_cell(&mut self) -> bool {
+        let Some((row, column)) = self.pointer else {
+            return false;
+        };
+
+        unsafe {
+            let cell = self.terminal_buffer.add(row * VGA_WIDTH + column);
+            let entry = cell.read_volatile();
+            let color = (entry >> 8) as u8;
+            cell.write_volatile(entry & 0xFF | (color.rotate_left(4) as u16) << 8);
+        }
+
+        true
+    }
+
+    /// Moves the cursor, positions outside of the screen are clamped to its edges.
+    pub fn set_position(&mut self, row: usize, column: usize) {
+        self.terminal_row = row.min(VGA_HEIGHT - 1);
+        self.terminal_column = column.min(VGA_WIDTH - 1);
+        self.update_cursor();
+    }
+
+    /// Returns the cursor position as (row, column).
+    pub fn position(&self) -> (usize, usize) {
+        (self.terminal_row, self.terminal_column)
     }
 
+    /// Color of the text written from now on.
+    pub fn set_color(&mut self, fore_ground_color: VgaColor, back_ground_color: VgaColor) {
+        self.foreground = fore_ground_color;
+        self.background = back_ground_color;
+        self.bold = false;
+        self.update_color();
+    }
+
+    /// Writes `text` as code page 437, interpreting '\n', '\r', '\t', backspace and VT100
+    /// escape sequences.
     pub fn write_text(&mut self, text: &[u8]) {
+        self.undraw_pointer();
+        self.reset_view();
         for &character in text {
-            match character {
-                b'\n' => self.handle_new_line(),
-                _ => self.write_character(character),
+            self.write_byte(character);
+        }
+        self.update_cursor();
+        self.draw_pointer();
+    }
+
+    /// Returns the code page 437 character and the color byte at a position.
+    pub fn read_character(&self, row: usize, column: usize) -> (u8, u8) {
+        let entry = unsafe {
+            self.terminal_buffer
+                .add(row * VGA_WIDTH + column)
+                .read_volatile()
+        };
+
+        (entry as u8, (entry >> 8) as u8)
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        match self.parser.advance(byte) {
+            Some(Action::Print(character)) => self.execute(character),
+            Some(Action::Csi(csi)) => self.execute_csi(&csi),
+            None => {}
+        }
+    }
+
+    fn execute(&mut self, character: u8) {
+        match character {
+            b'\n' => self.handle_new_line(),
+            b'\r' => self.terminal_column = 0,
+            b'\t' => {
+                let spaces = TAB_WIDTH - self.terminal_column % TAB_WIDTH;
+                for _ in 0..spaces {
+                    self.write_character(b' ');
+                }
+            }
+            // Moves back without erasing, "\x08 \x08" erases the previous character
+            BACKSPACE => self.terminal_column = self.terminal_column.saturating_sub(1),
+            _ => self.write_character(character),
+        }
+    }
+
+    fn execute_csi(&mut self, csi: &Csi) {
+        if csi.private {
+            return;
+        }
+
+        let count = csi.param(0, 1) as usize;
+        let (row, column) = self.position();
+        match csi.final_byte {
+            // Cursor up, down, forward and back
+            b'A' => self.set_position(row.saturating_sub(count), column),
+            b'B' => self.set_position(row + count, column),
+            b'C' => self.set_position(row, column + count),
+            b'D' => self.set_position(row, column.saturating_sub(count)),
+            // Cursor position, 1 based
+            b'H' | b'f' => {
+                self.set_position(csi.param(0, 1) as usize - 1, csi.param(1, 1) as usize - 1)
             }
+            // Cursor horizontal absolute
+            b'G' => self.set_position(row, count - 1),
+            b'J' => {
+                let cursor = row * VGA_WIDTH + column;
+                match csi.params().first().copied().unwrap_or(0) {
+                    0 => self.erase(cursor, VGA_WIDTH * VGA_HEIGHT),
+                    1 => self.erase(0, cursor + 1),
+                    2 => self.erase(0, VGA_WIDTH * VGA_HEIGHT),
+                    _ => {}
+                }
+            }
+            b'K' => {
+                let line = row * VGA_WIDTH;
+                match csi.params().first().copied().unwrap_or(0) {
+                    0 => self.erase(line + column, line + VGA_WIDTH),
+                    1 => self.erase(line, line + column + 1),
+                    2 => self.erase(line, line + VGA_WIDTH),
+                    _ => {}
+                }
+            }
+            b'm' => self.select_graphic_rendition(csi),
+            _ => {}
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, csi: &Csi) {
+        // "ESC [ m" is the same as "ESC [ 0 m"
+        let params = match csi.params() {
+            [] => &[0][..],
+            params => params,
+        };
+
+        for &param in params {
+            match param {
+                0 => {
+                    (self.foreground, self.background) = self.default_colors;
+                    self.bold = false;
+                }
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.foreground = ANSI_COLORS[param as usize - 30],
+                39 => self.foreground = self.default_colors.0,
+                40..=47 => self.background = ANSI_COLORS[param as usize - 40],
+                49 => self.background = self.default_colors.1,
+                90..=97 => self.foreground = ANSI_COLORS[param as usize - 90 + 8],
+                100..=107 => self.background = ANSI_COLORS[param as usize - 100 + 8],
+                _ => {}
+            }
+        }
+
+        self.update_color();
+    }
+
+    fn update_color(&mut self) {
+        let foreground = match self.bold {
+            true => self.foreground.bright(),
+            false => self.foreground,
+        };
+        self.terminal_color = Terminal::color_code(foreground, self.background);
+    }
+
+    // Blanks the characters from `start` up to `end` in the current color
+    fn erase(&mut self, start: usize, end: usize) {
+        for index in start..end {
+            self.put(index, b' ');
         }
     }
 
     fn handle_new_line(&mut self) {
         self.terminal_column = 0;
-        self.terminal_row += 1;
-        if self.terminal_row == VGA_HEIGHT {
-            self.terminal_row = 0;
+        if self.terminal_row + 1 < VGA_HEIGHT {
+            self.terminal_row += 1;
+        } else {
+            self.scroll();
+        }
+    }
+
+    // Moves every row up by one and blanks the last one
+    fn scroll(&mut self) {
+        if let Some(scrollback) = &mut self.scrollback {
+            if scrollback.capacity > 0 {
+                if scrollback.lines.len() == scrollback.capacity {
+                    scrollback.lines.pop_front();
+                }
+                let mut line = [0; VGA_WIDTH];
+                let first_row =
+                    unsafe { core::slice::from_raw_parts(self.terminal_buffer, VGA_WIDTH) };
+                line.copy_from_slice(first_row);
+                scrollback.lines.push_back(line);
+            }
+        }
+
+        unsafe {
+            core::ptr::copy(
+                self.terminal_buffer.add(VGA_WIDTH),
+                self.terminal_buffer,
+                VGA_WIDTH * (VGA_HEIGHT - 1),
+            );
+        }
+
+        for column in 0..VGA_WIDTH {
+            self.put((VGA_HEIGHT - 1) * VGA_WIDTH + column, b' ');
         }
     }
 
     fn write_character(&mut self, character: u8) {
         let index = self.terminal_row * VGA_WIDTH + self.terminal_column;
-        unsafe {
-            *self.terminal_buffer.add(index) = self.set_screen_character(character);
+        self.put(index, character);
+
+        self.terminal_column += 1;
+        if self.terminal_column == VGA_WIDTH {
+            self.handle_new_line();
         }
+    }
 
-        self.set_cursor()
+    fn put(&mut self, index: usize, character: u8) {
+        unsafe {
+            self.terminal_buffer
+                .add(index)
+                .write_volatile(self.set_screen_character(character));
+        }
     }
 
-    const fn set_color(fore_ground_color: VgaColor, back_ground_color: VgaColor) -> u8 {
+    const fn color_code(fore_ground_color: VgaColor, back_ground_color: VgaColor) -> u8 {
         fore_ground_color as u8 | (back_ground_color as u8) << 4
     }
 
@@ -101,14 +608,83 @@ impl Terminal {
         character as u16 | (self.terminal_color as u16) << 8
     }
 
-    fn set_cursor(&mut self) {
-        self.terminal_column += 1;
-        if self.terminal_column == VGA_WIDTH {
-            self.terminal_column = 0;
-            self.terminal_row += 1;
-            if self.terminal_row == VGA_HEIGHT {
-                self.terminal_row = 0;
+    fn update_cursor(&self) {
+        let Some(cursor) = &self.cursor else {
+            return;
+        };
+
+        let position = (self.terminal_row * VGA_WIDTH + self.terminal_column) as u16;
+        cursor.write_register(CURSOR_LOCATION_LOW, position as u8);
+        cursor.write_register(CURSOR_LOCATION_HIGH, (position >> 8) as u8);
+    }
+}
+
+/// Writes straight into the VGA memory, behind the back of the `Terminal`.
+///
+/// Meant for the panic path, which cannot wait for whoever holds the display. Output starts on
+/// a fresh line at the bottom of the screen and scrolls the screen up.
+pub struct RawWriter {
+    buffer: *mut u16,
+    column: usize,
+}
+
+impl RawWriter {
+    const COLOR: u8 = Terminal::color_code(VgaColor::White, VgaColor::Red);
+
+    /// Returns `None` unless the screen is in the VGA text mode.
+    pub fn new() -> Option<RawWriter> {
+        if !TEXT_MODE.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let mut writer = RawWriter {
+            buffer: VGA_BUFFER as *mut u16,
+            column: 0,
+        };
+        writer.new_line();
+
+        Some(writer)
+    }
+
+    fn new_line(&mut self) {
+        unsafe {
+            core::ptr::copy(
+                self.buffer.add(VGA_WIDTH),
+                self.buffer,
+                VGA_WIDTH * (VGA_HEIGHT - 1),
+            );
+        }
+        for column in 0..VGA_WIDTH {
+            self.put(column, b' ');
+        }
+        self.column = 0;
+    }
+
+    fn put(&self, column: usize, character: u8) {
+        let index = (VGA_HEIGHT - 1) * VGA_WIDTH + column;
+        let entry = character as u16 | (Self::COLOR as u16) << 8;
+        unsafe { self.buffer.add(index).write_volatile(entry) };
+    }
+}
+
+impl Write for RawWriter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for character in s.chars() {
+            if character == '\n' {
+                self.new_line();
+                continue;
+            }
+
+            if self.column == VGA_WIDTH {
+                self.new_line();
             }
+            self.put(
+                self.column,
+                cp437::from_char(character).unwrap_or(cp437::REPLACEMENT),
+            );
+            self.column += 1;
         }
+
+        Ok(())
     }
 }
@@ -1,15 +1,29 @@
 // Disable standard library
 #![no_std]
-// Convert raw pointer to &str
-#![feature(str_from_raw_parts)]
 // Interrupt
 #![feature(abi_x86_interrupt)]
 
 extern crate alloc;
+pub mod acpi; // Contains ACPI table lookup and power off
 pub mod allocator; // Contains Memory allocator functions
+pub mod backtrace; // Contains stack unwinding functions
+pub mod boot_info; // Contains the boot loader independent boot information
+pub mod cmdline; // Contains kernel command line parsing
+pub mod dmesg; // Contains the kernel log ring buffer
+pub mod elf; // Contains ELF section and symbol definitions
 pub mod gdt; // Contains Global Descriptor Table related functions
 pub mod interrupt;
 pub mod io; // Contains IO related functions;
 pub mod libc; // Contains C related functions
+pub mod logger; // Contains the kernel logger behind the log facade
+pub mod memory_map; // Contains the sanitized physical memory map
 pub mod multiboot; // Contains Multiboot specification related functions
+pub mod multiboot2; // Contains Multiboot2 specification related functions
+pub mod panic; // Contains the panic path
+pub mod pic; // Contains 8259 PIC related functions
+pub mod pit; // Contains the 8254 PIT system timer
+pub mod qemu; // Contains QEMU debug exit and shutdown
+pub mod shell; // Contains the interactive kernel shell
+pub mod symbols; // Contains kernel symbol lookup functions
+pub mod time; // Contains calendar date and time arithmetic
 pub mod util; // Contains utilities and helper functions // Contains Interrupt Descriptor Table related functions:
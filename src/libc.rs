@@ -2,10 +2,17 @@ extern "C" {
     pub static KERNEL_START: u32;
     pub static mut KERNEL_END: u32; // Mutable due to the initialization of the the free segement
     pub fn get_esp() -> u32;
+
+    // Bounds of the kernel stack set up in boot.s
+    #[link_name = "stack_bottom"]
+    pub static STACK_BOTTOM: u8;
+    #[link_name = "stack_top"]
+    pub static STACK_TOP: u8;
 }
 
+// Host tests link against the platform libc, which must keep its own symbols
 #[allow(clippy::missing_safety_doc)]
-#[no_mangle]
+#[cfg_attr(not(test), no_mangle)]
 pub unsafe extern "C" fn memset(ptr: *mut u8, character: u8, size: usize) {
     for index in 0..size {
         *ptr.add(index) = character;
@@ -13,7 +20,7 @@ pub unsafe extern "C" fn memset(ptr: *mut u8, character: u8, size: usize) {
 }
 
 #[allow(clippy::missing_safety_doc)]
-#[no_mangle]
+#[cfg_attr(not(test), no_mangle)]
 pub unsafe extern "C" fn memcpy(dest: *mut u8, src: *const u8, size: usize) {
     for index in 0..size {
         *dest.add(index) = *src.add(index);
@@ -22,7 +29,7 @@ pub unsafe extern "C" fn memcpy(dest: *mut u8, src: *const u8, size: usize) {
 
 #[allow(clippy::missing_safety_doc)]
 #[allow(clippy::comparison_chain)]
-#[no_mangle]
+#[cfg_attr(not(test), no_mangle)]
 pub unsafe extern "C" fn memcmp(string1: *const u8, string2: *const u8, size: usize) -> i8 {
     for index in 0..size {
         if *string1.add(index) < *string2.add(index) {
@@ -36,7 +43,7 @@ pub unsafe extern "C" fn memcmp(string1: *const u8, string2: *const u8, size: us
 }
 
 #[allow(clippy::missing_safety_doc)]
-#[no_mangle]
+#[cfg_attr(not(test), no_mangle)]
 pub unsafe extern "C" fn memmove(dest: *mut u8, src: *const u8, size: usize) {
     for index in 0..size {
         *dest.add(index) = *src.add(index);
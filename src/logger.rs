@@ -0,0 +1,236 @@
+use core::cell::RefCell;
+
+use log::{LevelFilter, Log, Metadata, Record};
+use thiserror_no_std::Error;
+
+use crate::cmdline;
+use crate::dmesg::{self, Entry, DMESG};
+use crate::io::sink::{SinkId, SinkInfo, MAX_SINKS};
+use crate::io::DISPLAY;
+use crate::pit;
+
+pub const MAX_DIRECTIVES: usize = 16;
+pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+static LOGGER: KernelLogger = KernelLogger {
+    filter: RefCell::new(Filter::new(DEFAULT_LEVEL)),
+    cmdline: RefCell::new(""),
+};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FilterError {
+    #[error("Invalid log level {0}")]
+    InvalidLevel(&'static str),
+    #[error("More than {} log directives", MAX_DIRECTIVES)]
+    TooManyDirectives,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Directive {
+    module: &'static str,
+    level: LevelFilter,
+}
+
+/// Level per module path, parsed from `info,kratos::io::ps2=trace,kratos::allocator=off`.
+///
+/// A bare level sets the default, the longest module path matching the target of a record
+/// decides over it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Filter {
+    default: LevelFilter,
+    directives: [Option<Directive>; MAX_DIRECTIVES],
+}
+
+impl Filter {
+    pub const fn new(default: LevelFilter) -> Filter {
+        Filter {
+            default,
+            directives: [None; MAX_DIRECTIVES],
+        }
+    }
+
+    pub fn parse(spec: &'static str) -> Result<Filter, FilterError> {
+        let mut filter = Filter::new(DEFAULT_LEVEL);
+        let mut directives = filter.directives.iter_mut();
+
+        for entry in spec.split(',').filter(|entry| !entry.is_empty()) {
+            match entry.split_once('=') {
+                Some((module, level)) => {
+                    let directive = directives.next().ok_or(FilterError::TooManyDirectives)?;
+                    *directive = Some(Directive {
+                        module,
+                        level: parse_level(level)?,
+                    });
+                }
+                None => filter.default = parse_level(entry)?,
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Level records from the module `target` are let through at.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .flatten()
+            .filter(|directive| is_within(target, directive.module))
+            .max_by_key(|directive| directive.module.len())
+            .map_or(self.default, |directive| directive.level)
+    }
+
+    /// Most verbose level of any module.
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .flatten()
+            .map(|directive| directive.level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+// `kratos::io` covers `kratos::io` and `kratos::io::ps2` but not `kratos::iommu`
+fn is_within(target: &str, module: &str) -> bool {
+    match target.strip_prefix(module) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
+
+fn parse_level(level: &'static str) -> Result<LevelFilter, FilterError> {
+    level.parse().map_err(|_| FilterError::InvalidLevel(level))
+}
+
+struct KernelLogger {
+    filter: RefCell<Filter>,
+    // Kept for the `log.<sink>` options of sinks that register later
+    cmdline: RefCell<&'static str>,
+}
+
+unsafe impl Sync for KernelLogger {}
+
+impl Log for KernelLogger {
+    // The dmesg takes every record, the filter only decides what the sinks show
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let uptime_ms = pit::uptime_ms();
+        let message = format_args!("{}: {}", record.target(), record.args());
+        DMESG.push(record.level(), uptime_ms, message);
+
+        if record.level() > self.filter.borrow().level_for(record.target()) {
+            return;
+        }
+
+        // Records from code that is already writing to the display only end up in the dmesg,
+        // waiting would never end
+        let Ok(mut display) = DISPLAY.try_borrow_mut() else {
+            return;
+        };
+
+        display.sinks.log(record.level(), |out| {
+            dmesg::write_line(out, uptime_ms, record.level(), message)?;
+            out.write_str("\n")
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Writes the messages logged so far to the sink `id`, for sinks that were added after them.
+pub fn replay(id: SinkId) {
+    let Ok(mut display) = DISPLAY.try_borrow_mut() else {
+        return;
+    };
+    let Some(SinkInfo {
+        enabled: true,
+        level,
+        ..
+    }) = display.sinks.info(id)
+    else {
+        return;
+    };
+
+    let filter = filter();
+    if let Some(out) = display.sinks.sink_mut(id) {
+        let _ = DMESG.write_matching(out, |entry| {
+            entry.level <= level && entry.level <= filter.level_for(target(entry))
+        });
+    }
+}
+
+// Entries start with the target of their record, see `KernelLogger::log`
+fn target(entry: &Entry) -> &str {
+    entry
+        .text()
+        .split_once(": ")
+        .map_or("", |(target, _)| target)
+}
+
+/// Installs the kernel logger, the display can be set up after it and gets the messages so far
+/// through [`replay`].
+///
+/// `log=<filter>` sets the module levels the sinks show, see [`Filter`], `log.<sink>=<level>`
+/// limits a sink further, e.g. `log=debug log.vga=warn`. The dmesg records every level. The first invalid option is returned, an invalid
+/// filter leaves the default in place. The display is usually not up yet to report it.
+pub fn init(cmdline: Option<&'static str>) -> Result<(), FilterError> {
+    let _ = log::set_logger(&LOGGER);
+    let cmdline = cmdline.unwrap_or("");
+    *LOGGER.cmdline.borrow_mut() = cmdline;
+
+    let filter = cmdline::get(cmdline, "log").map(Filter::parse);
+    if let Some(Ok(filter)) = filter {
+        set_filter(filter);
+    }
+    // The `log` macros would otherwise drop records the dmesg wants before they reach the logger
+    log::set_max_level(LevelFilter::Trace);
+
+    // Sinks that came before the logger, such as the debug console, get their levels now
+    let registered: [Option<SinkInfo>; MAX_SINKS] = {
+        let display = DISPLAY.borrow();
+        let mut sinks = display.sinks.sinks();
+        core::array::from_fn(|_| sinks.next())
+    };
+    let mut result = match filter {
+        Some(Err(e)) => Err(e),
+        _ => Ok(()),
+    };
+    for info in registered.into_iter().flatten() {
+        result = result.and(apply_sink_level(info.id));
+    }
+
+    result
+}
+
+pub fn filter() -> Filter {
+    *LOGGER.filter.borrow()
+}
+
+pub fn set_filter(filter: Filter) {
+    *LOGGER.filter.borrow_mut() = filter;
+}
+
+/// Applies `log.<name>=<level>` to the sink `id`, if the command line has one for it.
+pub fn apply_sink_level(id: SinkId) -> Result<(), FilterError> {
+    let Some(info) = DISPLAY.borrow().sinks.info(id) else {
+        return Ok(());
+    };
+
+    if let Some(level) = sink_level_option(info.name) {
+        DISPLAY.borrow_mut().sinks.set_level(id, level?);
+    }
+
+    Ok(())
+}
+
+fn sink_level_option(name: &str) -> Option<Result<LevelFilter, FilterError>> {
+    let cmdline = *LOGGER.cmdline.borrow();
+    let level = cmdline::options(cmdline)
+        .filter(|(key, _)| key.strip_prefix("log.") == Some(name))
+        .filter_map(|(_, value)| value)
+        .last()?;
+
+    Some(parse_level(level))
+}
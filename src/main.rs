@@ -2,8 +2,6 @@
 #![no_std]
 // Disable rust entry point
 #![no_main]
-// Panic
-#![feature(panic_info_message)]
 // Test
 #![feature(custom_test_frameworks)]
 #![reexport_test_harness_main = "test_main"]
@@ -17,11 +15,19 @@ use core::ptr::addr_of;
 
 // External Crate
 use hashbrown::HashMap;
+use log::{debug, info, warn};
 
 // Libray
+use kratos::boot_info::{print_mmap_sections, BootInfo};
+use kratos::io::mouse::{self, MouseConfig};
+use kratos::io::port_manager::PortManager;
+use kratos::io::ps2::{Ps2Controller, Ps2Error};
+use kratos::io::{debugcon, keyboard, keymap};
 use kratos::libc::{get_esp, KERNEL_END, KERNEL_START};
-use kratos::multiboot::{print_mmap_sections, MultibootInfo};
-use kratos::{gdt, io};
+use kratos::memory_map::MemoryMap;
+use kratos::qemu::{self, ExitCode};
+use kratos::shell::{self, Shell};
+use kratos::{acpi, cmdline, gdt, io, logger, pit, symbols};
 use kratos::{interrupt, println};
 
 // Contains Test
@@ -38,40 +44,80 @@ global_asm!(include_str!("boot.s"), options(att_syntax));
 // Defines the behavior of panic
 #[panic_handler]
 fn panic(panic_info: &PanicInfo) -> ! {
-    if let Some(args) = panic_info.message() {
-        println!("{}", args);
-    } else {
-        println!("Panicked in else");
-    }
+    kratos::panic::report(panic_info);
 
-    io::exit(1);
+    #[cfg(test)]
+    qemu::exit_or_shutdown(ExitCode::Panic);
 
-    loop {}
+    #[cfg(not(test))]
+    kratos::panic::stop(ExitCode::Failure);
 }
 
 #[allow(clippy::empty_loop, clippy::missing_safety_doc)]
+#[cfg_attr(test, allow(unreachable_code))]
 #[no_mangle]
-pub unsafe extern "C" fn kernel_main(_magic: u32, info: *const MultibootInfo) -> ! {
-    ALLOC.init(&*info);
+pub unsafe extern "C" fn kernel_main(magic: u32, info: *const u8) -> ! {
+    #[cfg(feature = "debugcon")]
+    let _ = debugcon::enable();
+    let boot_info = BootInfo::new(magic, info).expect("Unsupported boot loader");
+    let debugcon = match boot_info.cmdline {
+        Some(cmdline) if cmdline::has(cmdline, "debugcon") => debugcon::enable(),
+        _ => Ok(()),
+    };
+
+    // Neither the logger nor the display need the heap, so they are up before anything else
+    // can fail
+    let log_options = logger::init(boot_info.cmdline);
+    kratos::panic::init(boot_info.cmdline);
+    let port_manager = &io::port_manager::PORT_MANAGER;
+    let debug_exit = qemu::init(port_manager);
+    io::init_display(
+        port_manager,
+        boot_info.framebuffer.as_ref(),
+        boot_info.cmdline,
+    );
+    info!("Display Initialized");
+    if let Err(e) = log_options {
+        warn!("Invalid log option: {}", e);
+    }
+    if let Err(e) = debugcon {
+        warn!("Debugcon sink unavailable: {}", e);
+    }
+    if let Err(e) = debug_exit {
+        warn!("Debug exit unavailable: {}", e);
+    }
+
+    let memory_map = MemoryMap::new(&boot_info);
+    ALLOC.init(&memory_map);
+    io::enable_scrollback(boot_info.cmdline);
 
-    let mut port_manager = io::port_manager::PortManager::new();
-    io::init_display(&mut port_manager);
-    println!("Display Initialized");
+    if let Some(sections) = &boot_info.elf_sections {
+        if let Err(e) = symbols::init(sections) {
+            warn!("Kernel symbols unavailable: {}", e);
+        }
+    }
+
+    if let Err(e) = acpi::init(boot_info.rsdp.as_ref(), port_manager) {
+        warn!("ACPI power off unavailable: {}", e);
+    }
 
     #[cfg(test)]
     {
         test_main();
-        io::exit(0);
+        qemu::exit_or_shutdown(ExitCode::Success);
     }
 
-    println!("Stack Pointer: {:#x}", get_esp());
-    println!(
+    debug!("Stack Pointer: {:#x}", get_esp());
+    debug!(
         "Kernel start: {:?} Kernel End: {:?}",
         addr_of!(KERNEL_START),
         addr_of!(KERNEL_END)
     );
 
-    print_mmap_sections(info);
+    if let Some(cmdline) = boot_info.cmdline {
+        info!("Command line: {}", cmdline);
+    }
+    print_mmap_sections(&boot_info, &memory_map);
 
     {
         println!("A vector: {:?}", vec![1, 2, 3, 4]);
@@ -80,23 +126,57 @@ pub unsafe extern "C" fn kernel_main(_magic: u32, info: *const MultibootInfo) ->
         println!("A map: {:?}", a_map);
     }
 
-    println!("Initial GDT:");
-    gdt::print_gdtr();
     gdt::init();
-    println!("Updated GDT");
-    gdt::print_gdtr();
 
-    interrupt::init(&mut port_manager);
+    if let Err(e) = pit::init(port_manager) {
+        warn!("Timer unavailable: {}", e);
+    }
+    interrupt::init(port_manager);
     kratos::interrupt!(8);
 
-    let rtc = io::rtc::Rtc::new(&mut port_manager).expect("Failed to create RTC");
-    let mut date = rtc.read();
-    println!("Current date: {:?}", date);
-    date.hours -= 1;
-    rtc.write(&date);
+    let ps2 = match init_keyboard(port_manager, boot_info.cmdline) {
+        Ok(ps2) => Some(ps2),
+        Err(e) => {
+            warn!("Keyboard unavailable: {}", e);
+            None
+        }
+    };
+    if let Some(ps2) = &ps2 {
+        match mouse::init(ps2, MouseConfig::default()) {
+            Ok(kind) => info!("Mouse initialized, {:?}", kind),
+            Err(e) => warn!("Mouse unavailable: {}", e),
+        }
+    }
+
+    let rtc = match io::rtc::Rtc::new(port_manager) {
+        Ok(rtc) => Some(rtc),
+        Err(e) => {
+            warn!("RTC unavailable: {}", e);
+            None
+        }
+    };
+
+    let mut shell = Shell::new();
+    shell::commands::register_builtins(&mut shell, &memory_map, rtc.as_ref(), port_manager);
+    shell.run(shell::Console::detect())
+}
+
+// The keymap is picked with `keymap=uk`
+fn init_keyboard(
+    port_manager: &'static PortManager,
+    cmdline: Option<&str>,
+) -> Result<Ps2Controller, Ps2Error> {
+    if let Some(name) = cmdline.and_then(|cmdline| cmdline::get(cmdline, "keymap")) {
+        match keymap::by_name(name) {
+            Some(keymap) => keyboard::set_keymap(keymap),
+            None => warn!("Unknown keymap {}, using {}", name, keyboard::keymap().name),
+        }
+    }
 
-    let date = rtc.read();
-    println!("Current date modified: {:?}", date);
+    let ps2 = Ps2Controller::new(port_manager)?;
+    ps2.init()?;
+    let set = keyboard::init(&ps2)?;
+    info!("Keyboard initialized, scancode {:?}", set);
 
-    loop {}
+    Ok(ps2)
 }
@@ -0,0 +1,216 @@
+use core::fmt;
+use core::ptr::addr_of;
+
+use crate::boot_info::{BootInfo, MemoryRegion};
+use crate::elf::section_headers;
+
+// Sanitizing can split every BIOS entry around the reserved ranges
+pub const MAX_SANITIZED_REGIONS: usize = 128;
+pub const MAX_RESERVED_RANGES: usize = 64;
+
+/// Kind of a physical memory region, ordered by precedence when BIOS entries overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemoryRegionKind {
+    Available,
+    AcpiReclaimable,
+    AcpiNvs,
+    Reserved,
+    Bad,
+}
+
+impl MemoryRegionKind {
+    pub const ALL: [MemoryRegionKind; 5] = [
+        MemoryRegionKind::Available,
+        MemoryRegionKind::AcpiReclaimable,
+        MemoryRegionKind::AcpiNvs,
+        MemoryRegionKind::Reserved,
+        MemoryRegionKind::Bad,
+    ];
+
+    /// Converts the `type` of a Multiboot memory map entry, unknown types are reserved.
+    pub fn from_multiboot(type_: u32) -> MemoryRegionKind {
+        match type_ {
+            1 => MemoryRegionKind::Available,
+            3 => MemoryRegionKind::AcpiReclaimable,
+            4 => MemoryRegionKind::AcpiNvs,
+            5 => MemoryRegionKind::Bad,
+            _ => MemoryRegionKind::Reserved,
+        }
+    }
+}
+
+impl fmt::Display for MemoryRegionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MemoryRegionKind::Available => "Available",
+            MemoryRegionKind::AcpiReclaimable => "ACPI reclaimable",
+            MemoryRegionKind::AcpiNvs => "ACPI NVS",
+            MemoryRegionKind::Reserved => "Reserved",
+            MemoryRegionKind::Bad => "Bad",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Sorted, non-overlapping physical memory map.
+pub struct MemoryMap {
+    regions: [MemoryRegion; MAX_SANITIZED_REGIONS],
+    len: usize,
+}
+
+impl MemoryMap {
+    /// Sanitizes the boot loader memory map and reserves the kernel, modules and boot information.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn new(boot_info: &BootInfo) -> MemoryMap {
+        let mut reserved = [(0, 0); MAX_RESERVED_RANGES];
+        let mut reserved_len = 0;
+        let mut reserve = |start: u64, end: u64| {
+            if start < end && reserved_len < MAX_RESERVED_RANGES {
+                reserved[reserved_len] = (start, end);
+                reserved_len += 1;
+            }
+        };
+
+        reserve(
+            addr_of!(crate::libc::KERNEL_START) as u64,
+            addr_of!(crate::libc::KERNEL_END) as u64,
+        );
+
+        for &(start, end) in boot_info.info_ranges() {
+            reserve(start, end);
+        }
+
+        for module in boot_info.modules() {
+            reserve(module.start as u64, module.end as u64);
+            reserve_str(&mut reserve, module.cmdline);
+        }
+
+        if let Some(cmdline) = boot_info.cmdline {
+            reserve_str(&mut reserve, cmdline);
+        }
+
+        if let Some(boot_loader_name) = boot_info.boot_loader_name {
+            reserve_str(&mut reserve, boot_loader_name);
+        }
+
+        // Section header table and the sections loaded outside of the kernel image (symbols)
+        if let Some(sections) = boot_info.elf_sections {
+            reserve(
+                sections.addr as u64,
+                sections.addr as u64 + sections.num as u64 * sections.entsize as u64,
+            );
+            // Sections the boot loader didn't place, e.g. `.comment` and `.debug_*`, stay at 0
+            for header in section_headers(&sections)
+                .iter()
+                .filter(|header| header.sh_addr != 0)
+            {
+                reserve(
+                    header.sh_addr as u64,
+                    header.sh_addr as u64 + header.sh_size as u64,
+                );
+            }
+        }
+
+        MemoryMap::sanitize(boot_info.memory_map(), &reserved[..reserved_len])
+    }
+
+    /// Resolves overlapping entries in favour of the most restrictive kind, merges neighbours of
+    /// the same kind and turns available memory inside `reserved` ranges into reserved memory.
+    pub fn sanitize(regions: &[MemoryRegion], reserved: &[(u64, u64)]) -> MemoryMap {
+        let mut boundaries = [0u64; 2 * (MAX_SANITIZED_REGIONS + MAX_RESERVED_RANGES)];
+        let mut boundaries_len = 0;
+        let ranges = regions
+            .iter()
+            .map(|region| (region.addr, region.addr.saturating_add(region.len)))
+            .chain(reserved.iter().copied())
+            .filter(|&(start, end)| start < end);
+        for (start, end) in ranges {
+            if boundaries_len + 2 > boundaries.len() {
+                break;
+            }
+            boundaries[boundaries_len] = start;
+            boundaries[boundaries_len + 1] = end;
+            boundaries_len += 2;
+        }
+
+        let boundaries = &mut boundaries[..boundaries_len];
+        boundaries.sort_unstable();
+
+        let mut memory_map = MemoryMap {
+            regions: [MemoryRegion {
+                addr: 0,
+                len: 0,
+                kind: MemoryRegionKind::Reserved,
+            }; MAX_SANITIZED_REGIONS],
+            len: 0,
+        };
+
+        for window in boundaries.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            if start == end {
+                continue;
+            }
+
+            // Holes not described by any entry are left out
+            let Some(mut kind) = regions
+                .iter()
+                .filter(|region| {
+                    region.addr <= start && start < region.addr.saturating_add(region.len)
+                })
+                .map(|region| region.kind)
+                .max()
+            else {
+                continue;
+            };
+
+            let is_reserved = reserved.iter().any(|&(reserved_start, reserved_end)| {
+                reserved_start <= start && start < reserved_end
+            });
+            if kind == MemoryRegionKind::Available && is_reserved {
+                kind = MemoryRegionKind::Reserved;
+            }
+
+            memory_map.push(start, end, kind);
+        }
+
+        memory_map
+    }
+
+    pub fn regions(&self) -> &[MemoryRegion] {
+        &self.regions[..self.len]
+    }
+
+    pub fn total(&self, kind: MemoryRegionKind) -> u64 {
+        self.regions()
+            .iter()
+            .filter(|region| region.kind == kind)
+            .map(|region| region.len)
+            .sum()
+    }
+
+    // Extends the previous region if it is adjacent and of the same kind
+    fn push(&mut self, start: u64, end: u64, kind: MemoryRegionKind) {
+        if let Some(previous) = self.regions[..self.len].last_mut() {
+            if previous.kind == kind && previous.addr + previous.len == start {
+                previous.len = end - previous.addr;
+                return;
+            }
+        }
+
+        if self.len < MAX_SANITIZED_REGIONS {
+            self.regions[self.len] = MemoryRegion {
+                addr: start,
+                len: end - start,
+                kind,
+            };
+            self.len += 1;
+        }
+    }
+}
+
+// Strings handed over by the boot loader live in its memory, including the NUL terminator
+fn reserve_str(reserve: &mut impl FnMut(u64, u64), string: &str) {
+    let start = string.as_ptr() as u64;
+    reserve(start, start + string.len() as u64 + 1);
+}
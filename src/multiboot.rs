@@ -1,4 +1,17 @@
-use crate::println;
+use crate::memory_map::MemoryRegionKind;
+use crate::util::bit_manipulation::get_bit;
+use crate::util::c_str;
+
+// Value left in `eax` by a Multiboot compliant boot loader
+pub const MULTIBOOT_BOOTLOADER_MAGIC: u32 = 0x2BADB002;
+
+// Bits of `MultibootInfo::flags` marking which fields are valid
+const CMDLINE_FLAG: u32 = 2;
+const MODS_FLAG: u32 = 3;
+const ELF_SECTIONS_FLAG: u32 = 5;
+const MMAP_FLAG: u32 = 6;
+const BOOT_LOADER_NAME_FLAG: u32 = 9;
+const FRAMEBUFFER_FLAG: u32 = 12;
 
 // Multiboot information
 #[repr(C, packed)]
@@ -20,8 +33,11 @@ pub struct MultibootInfo {
     mods_count: u32,
     mods_addr: u32,
 
-    // Store the syms data of Multiboot info
-    dummy: u128,
+    // ELF section header table
+    syms_num: u32,
+    syms_size: u32,
+    syms_addr: u32,
+    syms_shndx: u32,
 
     // memory Mapping Buffer
     mmap_length: u32,
@@ -36,11 +52,35 @@ pub struct MultibootInfo {
 
     // Boot Loader name
     boot_loader_name: *const u8,
+
+    // APM table
+    apm_table: u32,
+
+    // Video
+    vbe_control_info: u32,
+    vbe_mode_info: u32,
+    vbe_mode: u16,
+    vbe_interface_seg: u16,
+    vbe_interface_off: u16,
+    vbe_interface_len: u16,
+
+    // Framebuffer
+    framebuffer_addr: u64,
+    framebuffer_pitch: u32,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    framebuffer_bpp: u8,
+    framebuffer_type: u8,
+    framebuffer_color_info: [u8; 6],
 }
 
 impl MultibootInfo {
     #[allow(clippy::missing_safety_doc)]
     pub unsafe fn get_memory_map(&self) -> &[MultibootMmapEntry] {
+        if !self.has_flag(MMAP_FLAG) {
+            return &[];
+        }
+
         let number_of_memory_segments =
             self.mmap_length as usize / core::mem::size_of::<MultibootMmapEntry>();
         core::slice::from_raw_parts(
@@ -48,6 +88,92 @@ impl MultibootInfo {
             number_of_memory_segments,
         )
     }
+
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn get_modules(&self) -> &[MultibootModule] {
+        if !self.has_flag(MODS_FLAG) {
+            return &[];
+        }
+
+        core::slice::from_raw_parts(
+            self.mods_addr as *const MultibootModule,
+            self.mods_count as usize,
+        )
+    }
+
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn get_cmdline(&self) -> Option<&'static str> {
+        if !self.has_flag(CMDLINE_FLAG) {
+            return None;
+        }
+
+        Some(c_str::from_raw(self.cmdline as *const u8))
+    }
+
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn get_boot_loader_name(&self) -> Option<&'static str> {
+        if !self.has_flag(BOOT_LOADER_NAME_FLAG) {
+            return None;
+        }
+
+        Some(c_str::from_raw(self.boot_loader_name))
+    }
+
+    /// Returns the memory holding this structure, the memory map and the module list.
+    pub fn get_info_ranges(&self) -> [(u64, u64); 3] {
+        let info = self as *const MultibootInfo as u64;
+        let mut ranges = [
+            (info, info + core::mem::size_of::<MultibootInfo>() as u64),
+            (0, 0),
+            (0, 0),
+        ];
+
+        if self.has_flag(MMAP_FLAG) {
+            let mmap_addr = self.mmap_addr as u64;
+            ranges[1] = (mmap_addr, mmap_addr + self.mmap_length as u64);
+        }
+
+        if self.has_flag(MODS_FLAG) {
+            let mods_addr = self.mods_addr as u64;
+            let mods_len = self.mods_count as u64 * core::mem::size_of::<MultibootModule>() as u64;
+            ranges[2] = (mods_addr, mods_addr + mods_len);
+        }
+
+        ranges
+    }
+
+    pub fn get_elf_sections(&self) -> Option<MultibootElfSections> {
+        if !self.has_flag(ELF_SECTIONS_FLAG) {
+            return None;
+        }
+
+        Some(MultibootElfSections {
+            num: self.syms_num,
+            size: self.syms_size,
+            addr: self.syms_addr,
+            shndx: self.syms_shndx,
+        })
+    }
+
+    pub fn get_framebuffer(&self) -> Option<MultibootFramebuffer> {
+        if !self.has_flag(FRAMEBUFFER_FLAG) {
+            return None;
+        }
+
+        Some(MultibootFramebuffer {
+            addr: self.framebuffer_addr,
+            pitch: self.framebuffer_pitch,
+            width: self.framebuffer_width,
+            height: self.framebuffer_height,
+            bpp: self.framebuffer_bpp,
+            type_: self.framebuffer_type,
+            color_info: self.framebuffer_color_info,
+        })
+    }
+
+    fn has_flag(&self, flag: u32) -> bool {
+        get_bit(self.flags, flag) == 1
+    }
 }
 
 // Low field contains important data
@@ -60,28 +186,39 @@ pub struct MultibootMmapEntry {
     pub type_: u32,
 }
 
-#[allow(clippy::missing_safety_doc)]
-pub unsafe fn print_mmap_sections(info: *const MultibootInfo) {
-    let boot_loader_name = core::str::from_raw_parts((*info).boot_loader_name, 5);
-    println!("Boot Loader name: {}", boot_loader_name);
+impl MultibootMmapEntry {
+    pub fn kind(&self) -> MemoryRegionKind {
+        MemoryRegionKind::from_multiboot(self.type_)
+    }
+}
 
-    let mut total_memmory = 0;
-    println!("Available memory segments");
-    let mmap_length = (*info).mmap_length;
-    println!("mmap_length: {}", mmap_length);
+// Boot module loaded alongside the kernel
+#[repr(C, packed)]
+#[derive(Debug)]
+pub struct MultibootModule {
+    pub mod_start: u32,
+    pub mod_end: u32,
+    pub string: u32,
+    reserved: u32,
+}
 
-    for memory in (*info).get_memory_map() {
-        let size = memory.size;
-        let len = memory.len;
-        let addr = memory.addr;
-        let type_ = memory.type_;
-        total_memmory += len;
+// Location of the kernel ELF section header table
+#[derive(Debug)]
+pub struct MultibootElfSections {
+    pub num: u32,
+    pub size: u32,
+    pub addr: u32,
+    pub shndx: u32,
+}
 
-        println!("Size: {size}, len: {len}, addr: {addr:#0X}, type : {type_}");
-    }
-    println!(
-        "Total Memory: {}MB",
-        (total_memmory as f32) / 1024.0 / 1024.0
-    );
-    println!("End of memory segments.");
+// Framebuffer fields, color_info layout depends on type_
+#[derive(Debug)]
+pub struct MultibootFramebuffer {
+    pub addr: u64,
+    pub pitch: u32,
+    pub width: u32,
+    pub height: u32,
+    pub bpp: u8,
+    pub type_: u8,
+    pub color_info: [u8; 6],
 }
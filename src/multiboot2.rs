@@ -0,0 +1,239 @@
+use crate::util::c_str;
+
+// Value left in `eax` by a Multiboot2 compliant boot loader
+pub const MULTIBOOT2_BOOTLOADER_MAGIC: u32 = 0x36D76289;
+
+// Tags are padded so every tag starts on an 8 byte boundary
+const TAG_ALIGN: usize = 8;
+
+// Tag types
+const END_TAG: u32 = 0;
+const COMMAND_LINE_TAG: u32 = 1;
+const BOOT_LOADER_NAME_TAG: u32 = 2;
+const MODULE_TAG: u32 = 3;
+const MEMORY_MAP_TAG: u32 = 6;
+const FRAMEBUFFER_TAG: u32 = 8;
+const ELF_SECTIONS_TAG: u32 = 9;
+const ACPI_OLD_TAG: u32 = 14;
+const ACPI_NEW_TAG: u32 = 15;
+
+// Fixed part of the boot information, followed by the tags
+#[repr(C)]
+struct InfoHeader {
+    total_size: u32,
+    reserved: u32,
+}
+
+#[repr(C)]
+struct TagHeader {
+    type_: u32,
+    size: u32,
+}
+
+#[repr(C)]
+struct ModuleTag {
+    header: TagHeader,
+    mod_start: u32,
+    mod_end: u32,
+    // Followed by the NUL terminated module command line
+}
+
+#[repr(C)]
+struct MemoryMapTag {
+    header: TagHeader,
+    entry_size: u32,
+    entry_version: u32,
+    // Followed by the entries
+}
+
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryMapEntry {
+    pub addr: u64,
+    pub len: u64,
+    pub type_: u32,
+    reserved: u32,
+}
+
+#[repr(C, packed)]
+struct FramebufferTag {
+    header: TagHeader,
+    addr: u64,
+    pitch: u32,
+    width: u32,
+    height: u32,
+    bpp: u8,
+    type_: u8,
+    reserved: u16,
+    // Followed by the color info, layout depends on type_
+}
+
+#[repr(C)]
+struct ElfSectionsTag {
+    header: TagHeader,
+    num: u32,
+    entsize: u32,
+    shndx: u32,
+    // Followed by the section headers
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Module {
+    pub start: u32,
+    pub end: u32,
+    pub cmdline: &'static str,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryMap {
+    entry_size: usize,
+    entries: *const u8,
+    len: usize,
+}
+
+impl MemoryMap {
+    pub fn iter(&self) -> impl Iterator<Item = MemoryMapEntry> + '_ {
+        (0..self.len).map(|index| unsafe {
+            core::ptr::read_unaligned(
+                self.entries.add(index * self.entry_size) as *const MemoryMapEntry
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Framebuffer {
+    pub addr: u64,
+    pub pitch: u32,
+    pub width: u32,
+    pub height: u32,
+    pub bpp: u8,
+    pub type_: u8,
+    pub color_info: *const u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ElfSections {
+    pub num: u32,
+    pub entsize: u32,
+    pub shndx: u32,
+    pub addr: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Tag {
+    CommandLine(&'static str),
+    BootLoaderName(&'static str),
+    Module(Module),
+    MemoryMap(MemoryMap),
+    Framebuffer(Framebuffer),
+    ElfSections(ElfSections),
+    // Address of the copied RSDP
+    AcpiOld(u32),
+    AcpiNew(u32),
+    Unknown(u32),
+}
+
+pub struct TagIter {
+    current: *const u8,
+    end: *const u8,
+}
+
+/// Returns the total size of the boot information structure in bytes.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn total_size(info: *const u8) -> usize {
+    (*(info as *const InfoHeader)).total_size as usize
+}
+
+/// Iterates over the tags of the boot information, stopping at the end tag.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn tags(info: *const u8) -> TagIter {
+    TagIter {
+        current: info.add(core::mem::size_of::<InfoHeader>()),
+        end: info.add(total_size(info)),
+    }
+}
+
+impl Iterator for TagIter {
+    type Item = Tag;
+
+    fn next(&mut self) -> Option<Tag> {
+        if self.current.is_null()
+            || (self.current as usize) + core::mem::size_of::<TagHeader>() > self.end as usize
+        {
+            return None;
+        }
+
+        unsafe {
+            let header = &*(self.current as *const TagHeader);
+            let size = header.size as usize;
+            if header.type_ == END_TAG || size < core::mem::size_of::<TagHeader>() {
+                self.current = core::ptr::null();
+                return None;
+            }
+
+            let tag = parse_tag(self.current, header);
+
+            let next = (self.current as usize + size).next_multiple_of(TAG_ALIGN);
+            self.current = next as *const u8;
+
+            Some(tag)
+        }
+    }
+}
+
+unsafe fn parse_tag(ptr: *const u8, header: &TagHeader) -> Tag {
+    let payload = ptr.add(core::mem::size_of::<TagHeader>());
+
+    match header.type_ {
+        COMMAND_LINE_TAG => Tag::CommandLine(c_str::from_raw(payload)),
+        BOOT_LOADER_NAME_TAG => Tag::BootLoaderName(c_str::from_raw(payload)),
+        MODULE_TAG => {
+            let tag = &*(ptr as *const ModuleTag);
+            Tag::Module(Module {
+                start: tag.mod_start,
+                end: tag.mod_end,
+                cmdline: c_str::from_raw(ptr.add(core::mem::size_of::<ModuleTag>())),
+            })
+        }
+        MEMORY_MAP_TAG => {
+            let tag = &*(ptr as *const MemoryMapTag);
+            let entries_offset = core::mem::size_of::<MemoryMapTag>();
+            let entry_size = tag.entry_size as usize;
+            let len = (header.size as usize)
+                .saturating_sub(entries_offset)
+                .checked_div(entry_size)
+                .unwrap_or(0);
+
+            Tag::MemoryMap(MemoryMap {
+                entry_size,
+                entries: ptr.add(entries_offset),
+                len,
+            })
+        }
+        FRAMEBUFFER_TAG => {
+            let tag = core::ptr::read_unaligned(ptr as *const FramebufferTag);
+            Tag::Framebuffer(Framebuffer {
+                addr: tag.addr,
+                pitch: tag.pitch,
+                width: tag.width,
+                height: tag.height,
+                bpp: tag.bpp,
+                type_: tag.type_,
+                color_info: ptr.add(core::mem::size_of::<FramebufferTag>()),
+            })
+        }
+        ELF_SECTIONS_TAG => {
+            let tag = &*(ptr as *const ElfSectionsTag);
+            Tag::ElfSections(ElfSections {
+                num: tag.num,
+                entsize: tag.entsize,
+                shndx: tag.shndx,
+                addr: ptr.add(core::mem::size_of::<ElfSectionsTag>()) as u32,
+            })
+        }
+        ACPI_OLD_TAG => Tag::AcpiOld(payload as u32),
+        ACPI_NEW_TAG => Tag::AcpiNew(payload as u32),
+        unknown => Tag::Unknown(unknown),
+    }
+}
@@ -0,0 +1,86 @@
+use core::arch::asm;
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::backtrace::Backtrace;
+use crate::cmdline;
+use crate::io::vga::RawWriter;
+use crate::io::{debugcon, serial};
+use crate::qemu::{self, ExitCode};
+
+// Panics so far, a panic while reporting one only gets a short note
+static PANICS: AtomicUsize = AtomicUsize::new(0);
+static HALT: AtomicBool = AtomicBool::new(false);
+
+/// `panic=halt` keeps the machine stopped after a panic, e.g. for a debugger, instead of ending
+/// QEMU.
+pub fn init(cmdline: Option<&str>) {
+    let halt = cmdline.and_then(|cmdline| cmdline::get(cmdline, "panic")) == Some("halt");
+    HALT.store(halt, Ordering::Relaxed);
+}
+
+// Writes to every output that does not need a lock, the display may be held by the code that
+// panicked
+struct RawOutput {
+    vga: Option<RawWriter>,
+}
+
+impl Write for RawOutput {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        debugcon::write_raw(s);
+        serial::write_raw(s);
+        if let Some(vga) = &mut self.vga {
+            vga.write_str(s)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Disables interrupts and writes the location, message and backtrace of the panic to the raw
+/// outputs.
+///
+/// A panic while doing so is reported without message and backtrace, a third one halts right
+/// away.
+pub fn report(panic_info: &PanicInfo) {
+    unsafe { asm!("cli", options(nomem, nostack)) };
+
+    let nested = match PANICS.fetch_add(1, Ordering::SeqCst) {
+        0 => false,
+        1 => true,
+        _ => qemu::halt(),
+    };
+
+    let mut out = RawOutput {
+        vga: RawWriter::new(),
+    };
+    let _ = match nested {
+        false => write_report(&mut out, panic_info),
+        true => write_nested(&mut out, panic_info),
+    };
+}
+
+/// Ends QEMU with `code`, or halts if `panic=halt` was given or the debug exit device is absent.
+pub fn stop(code: ExitCode) -> ! {
+    if HALT.load(Ordering::Relaxed) {
+        qemu::halt();
+    }
+
+    qemu::exit(code)
+}
+
+// `PanicInfo` shows the location and the message
+fn write_report(out: &mut RawOutput, panic_info: &PanicInfo) -> fmt::Result {
+    writeln!(out, "\nKernel {}", panic_info)?;
+    writeln!(out, "{}", Backtrace::capture())
+}
+
+// The first report may have been cut short by whatever panicked again
+fn write_nested(out: &mut RawOutput, panic_info: &PanicInfo) -> fmt::Result {
+    write!(out, "\nPanic while panicking")?;
+    if let Some(location) = panic_info.location() {
+        write!(out, " at {}:{}", location.file(), location.line())?;
+    }
+    writeln!(out)
+}
@@ -0,0 +1,103 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::io::port_manager::{Port, PortManager};
+
+const MASTER_COMMAND: u16 = 0x20;
+const MASTER_DATA: u16 = 0x21;
+const SLAVE_COMMAND: u16 = 0xA0;
+const SLAVE_DATA: u16 = 0xA1;
+
+// The BIOS maps the master to vectors 0x08-0x0F, which collide with CPU exceptions
+pub const MASTER_OFFSET: u8 = 0x20;
+pub const SLAVE_OFFSET: u8 = 0x28;
+pub const IRQ_COUNT: u8 = 16;
+const CASCADE_IRQ: u8 = 2;
+
+const ICW1_INIT: u8 = 0x10;
+const ICW1_ICW4: u8 = 0x01;
+const ICW4_8086: u8 = 0x01;
+const OCW3_READ_ISR: u8 = 0x0B;
+const END_OF_INTERRUPT: u8 = 0x20;
+
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Remaps both 8259 PICs behind the CPU exceptions and masks every IRQ.
+pub fn init(port_manager: &'static PortManager) {
+    let master = port_manager
+        .request_range(MASTER_COMMAND, 2, "master pic")
+        .expect("Failed to get master PIC ports");
+    let slave = port_manager
+        .request_range(SLAVE_COMMAND, 2, "slave pic")
+        .expect("Failed to get slave PIC ports");
+    let (master_command, master_data): (Port, Port) = (master.port(0), master.port(1));
+    let (slave_command, slave_data): (Port, Port) = (slave.port(0), slave.port(1));
+
+    master_command.write(ICW1_INIT | ICW1_ICW4);
+    slave_command.write(ICW1_INIT | ICW1_ICW4);
+    master_data.write(MASTER_OFFSET);
+    slave_data.write(SLAVE_OFFSET);
+    master_data.write(1 << CASCADE_IRQ); // Slave is attached to IRQ2
+    slave_data.write(CASCADE_IRQ); // Cascade identity
+    master_data.write(ICW4_8086);
+    slave_data.write(ICW4_8086);
+
+    // Only the cascade stays unmasked so slave IRQs reach the master once enabled
+    master_data.write(!(1 << CASCADE_IRQ));
+    slave_data.write(0xFF);
+
+    master.leak();
+    slave.leak();
+
+    INITIALIZED.store(true, Ordering::Release);
+}
+
+pub fn is_initialized() -> bool {
+    INITIALIZED.load(Ordering::Acquire)
+}
+
+pub fn enable_irq(irq: u8) {
+    let (data, line) = data_port(irq);
+    data.write(data.read() & !(1 << line));
+}
+
+pub fn disable_irq(irq: u8) {
+    let (data, line) = data_port(irq);
+    data.write(data.read() | (1 << line));
+}
+
+/// Checks the in-service register for IRQ7 and IRQ15, a spurious one must not be acknowledged at
+/// the PIC that raised it.
+pub fn is_spurious(irq: u8) -> bool {
+    let (command, line) = match irq {
+        7 => (Port::<u8>::new(MASTER_COMMAND), 7),
+        15 => (Port::new(SLAVE_COMMAND), 7),
+        _ => return false,
+    };
+
+    command.write(OCW3_READ_ISR);
+    if command.read() & (1 << line) != 0 {
+        return false;
+    }
+
+    // A spurious IRQ15 still went through the master's cascade line
+    if irq == 15 {
+        Port::<u8>::new(MASTER_COMMAND).write(END_OF_INTERRUPT);
+    }
+
+    true
+}
+
+pub fn end_of_interrupt(irq: u8) {
+    if irq >= 8 {
+        Port::<u8>::new(SLAVE_COMMAND).write(END_OF_INTERRUPT);
+    }
+    Port::<u8>::new(MASTER_COMMAND).write(END_OF_INTERRUPT);
+}
+
+// The ports were reserved by `init` for the lifetime of the kernel
+fn data_port(irq: u8) -> (Port, u8) {
+    match irq {
+        0..=7 => (Port::new(MASTER_DATA), irq),
+        _ => (Port::new(SLAVE_DATA), irq - 8),
+    }
+}
@@ -0,0 +1,50 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::interrupt;
+use crate::io::port_manager::{Port, PortError, PortManager};
+
+const CHANNEL0_DATA: u16 = 0x40;
+const PORT_COUNT: u16 = 4;
+const COMMAND_OFFSET: u16 = 3;
+
+pub const IRQ: u8 = 0;
+// Input clock of the 8254
+const BASE_FREQUENCY: u32 = 1_193_182;
+pub const TICK_HZ: u32 = 1000;
+
+// Channel 0, low then high byte of the divisor, mode 3 (square wave), binary counting
+const CHANNEL0_SQUARE_WAVE: u8 = 0x36;
+
+// Wraps after about 49 days
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// Programs channel 0 of the 8254 to raise IRQ0 `TICK_HZ` times a second and counts the ticks.
+pub fn init(port_manager: &'static PortManager) -> Result<(), PortError> {
+    let ports = port_manager.request_range(CHANNEL0_DATA, PORT_COUNT, "pit")?;
+    let channel0: Port = ports.port(0);
+    let command: Port = ports.port(COMMAND_OFFSET);
+
+    let divisor = (BASE_FREQUENCY / TICK_HZ) as u16;
+    command.write(CHANNEL0_SQUARE_WAVE);
+    channel0.write(divisor as u8);
+    channel0.write((divisor >> 8) as u8);
+    ports.leak();
+
+    interrupt::register_irq_handler(IRQ, handle_irq);
+
+    Ok(())
+}
+
+fn handle_irq() {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Timer interrupts since `init`, zero before.
+pub fn ticks() -> u32 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+/// Milliseconds since `init`.
+pub fn uptime_ms() -> u32 {
+    ticks() * (1000 / TICK_HZ)
+}
@@ -0,0 +1,152 @@
+use core::arch::asm;
+use core::cell::RefCell;
+
+use crate::acpi;
+use crate::io::port_manager::{Port, PortError, PortManager, WriteOnlyPort};
+use crate::io::ps2;
+
+// Matches `-device isa-debug-exit,iobase=0xf4,iosize=0x04` in qemu_wrapper.sh
+pub const DEBUG_EXIT_PORT: u16 = 0xF4;
+const DEBUG_EXIT_SIZE: u16 = 4;
+
+// 8042 command pulsing output line 0, which is wired to the CPU reset
+const PS2_PULSE_RESET: u8 = 0xFE;
+
+// Power-off ports of QEMU's PIIX4 PM (>= 2.0), Bochs and older QEMU, and VirtualBox
+const POWER_OFF_PORTS: [(u16, u16); 3] = [(0x604, 0x2000), (0xB004, 0x2000), (0x4004, 0x3400)];
+
+static DEBUG_EXIT: DebugExitCell = DebugExitCell {
+    inner: RefCell::new(None),
+};
+
+struct DebugExitCell {
+    inner: RefCell<Option<DebugExit>>,
+}
+
+unsafe impl Sync for DebugExitCell {}
+
+/// Status QEMU exits with, the host sees `(code << 1) | 1`.
+///
+/// qemu_wrapper.sh shifts it back, so `Success` ends up as 0, `Failure` as 1 and `Panic` as 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success,
+    Failure,
+    // A test run that panicked, as opposed to one that completed with failed tests
+    Panic,
+    Custom(u8),
+}
+
+impl ExitCode {
+    /// Value written to the device.
+    pub fn code(self) -> u8 {
+        match self {
+            ExitCode::Success => 0,
+            ExitCode::Failure => 1,
+            ExitCode::Panic => 2,
+            ExitCode::Custom(code) => code,
+        }
+    }
+
+    /// Exit status of the QEMU process.
+    pub fn host_status(self) -> u32 {
+        (self.code() as u32) << 1 | 1
+    }
+}
+
+/// QEMU's isa-debug-exit device, writing to it ends the emulator.
+pub struct DebugExit {
+    port: WriteOnlyPort,
+}
+
+impl DebugExit {
+    pub fn new(port_manager: &'static PortManager) -> Result<DebugExit, PortError> {
+        let ports =
+            port_manager.request_range(DEBUG_EXIT_PORT, DEBUG_EXIT_SIZE, "isa-debug-exit")?;
+
+        Ok(DebugExit {
+            port: ports.write_only_port(0),
+        })
+    }
+
+    /// Ends QEMU with `code`, returns if the device is absent, e.g. on real hardware.
+    pub fn exit(&self, code: ExitCode) {
+        self.port.write(code.code());
+    }
+}
+
+/// Reserves the debug exit device for `exit`.
+pub fn init(port_manager: &'static PortManager) -> Result<(), PortError> {
+    *DEBUG_EXIT.inner.borrow_mut() = Some(DebugExit::new(port_manager)?);
+
+    Ok(())
+}
+
+/// Ends QEMU with `code`, halts if the debug exit device is absent.
+pub fn exit(code: ExitCode) -> ! {
+    try_exit(code);
+    halt()
+}
+
+/// Ends QEMU with `code`, powers off if the debug exit device is absent so test runs always
+/// terminate, the status is lost in that case.
+pub fn exit_or_shutdown(code: ExitCode) -> ! {
+    try_exit(code);
+    shutdown()
+}
+
+/// Powers off through ACPI, then through the emulator specific ports, halts if both fail.
+pub fn shutdown() -> ! {
+    acpi::power_off();
+
+    // Nothing else runs at this point, the ports are written without reserving them
+    for (port, value) in POWER_OFF_PORTS {
+        Port::<u16>::new(port).write(value);
+    }
+
+    halt()
+}
+
+/// Resets the machine through the 8042 reset line, then through a triple fault.
+pub fn reboot() -> ! {
+    unsafe { asm!("cli", options(nomem, nostack)) };
+
+    // Pulses the CPU reset line, once the controller has taken the previous byte. Without an 8042
+    // the status reads as 0xFF, so the poll is bounded and the triple fault takes over.
+    let status = Port::<u8>::new(ps2::STATUS_COMMAND_PORT);
+    let ready = (0..ps2::TIMEOUT_POLLS).any(|_| status.read() & ps2::STATUS_INPUT_FULL == 0);
+    if ready {
+        status.write(PS2_PULSE_RESET);
+    }
+
+    // Any exception without an IDT turns into a triple fault, which resets the CPU
+    let empty = [0u16; 3];
+    unsafe {
+        asm!(
+            "lidt ({idt})",
+            "int3",
+            idt = in(reg) &empty,
+            options(att_syntax),
+        );
+    }
+
+    halt()
+}
+
+/// Stops the CPU for good.
+pub fn halt() -> ! {
+    loop {
+        unsafe {
+            asm!("cli", "hlt", options(nomem, nostack));
+        }
+    }
+}
+
+fn try_exit(code: ExitCode) {
+    // The device may be borrowed by the code that panicked
+    if let Ok(debug_exit) = DEBUG_EXIT.inner.try_borrow() {
+        if let Some(debug_exit) = debug_exit.as_ref() {
+            debug_exit.exit(code);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use alloc::string::ToString;
+use core::fmt::Write;
+use core::str::FromStr;
+use log::LevelFilter;
+
+use crate::allocator::ALLOC;
+use crate::dmesg::DMESG;
+use crate::io::port_manager::PortManager;
+use crate::io::rtc::Rtc;
+use crate::memory_map::{MemoryMap, MemoryRegionKind};
+use crate::qemu::{self, ExitCode};
+use crate::register_command;
+use crate::shell::{CommandError, Shell};
+use crate::time::DateTime;
+use crate::{gdt, interrupt};
+
+/// Adds the built-in commands, `rtc` is left out of `date` if the clock is unavailable.
+pub fn register_builtins<'a>(
+    shell: &mut Shell<'a>,
+    memory_map: &'a MemoryMap,
+    rtc: Option<&'a Rtc>,
+    port_manager: &'static PortManager,
+) {
+    register_command!(
+        shell,
+        "mem",
+        "Shows the memory map and allocator statistics",
+        |out, _| {
+            for region in memory_map.regions() {
+                writeln!(
+                    out,
+                    "[{:#010X} - {:#010X}) {}",
+                    region.addr,
+                    region.addr + region.len,
+                    region.kind
+                )?;
+            }
+            for kind in MemoryRegionKind::ALL {
+                writeln!(out, "Total {}: {} KiB", kind, memory_map.total(kind) / 1024)?;
+            }
+
+            let stats = ALLOC.stats();
+            writeln!(
+                out,
+                "Heap free: {} KiB in {} segments, largest {} KiB",
+                stats.free_bytes / 1024,
+                stats.free_segments,
+                stats.largest_free_segment / 1024
+            )?;
+            Ok(())
+        }
+    );
+
+    register_command!(shell, "dmesg", "Shows the kernel log", |out, _| {
+        DMESG.write_entries(out, LevelFilter::Trace)?;
+        Ok(())
+    });
+
+    register_command!(shell, "gdt", "Shows the loaded GDT", |out, _| {
+        unsafe { gdt::write_gdtr(out)? };
+        Ok(())
+    });
+
+    register_command!(shell, "idt", "Shows the loaded IDT", |out, _| {
+        interrupt::write_idt(out)?;
+        Ok(())
+    });
+
+    register_command!(
+        shell,
+        "date",
+        "Shows the RTC time, `date set YYYY-MM-DD HH:MM:SS` sets it",
+        move |out, args| {
+            let rtc = rtc.ok_or_else(|| CommandError::Failed("RTC unavailable".to_string()))?;
+            match args {
+                [] => write_date_time(out, rtc),
+                ["set", date, time] => {
+                    rtc.write(&parse_date_time(date, time)?)
+                        .map_err(|e| CommandError::InvalidArgument(e.to_string()))?;
+                    write_date_time(out, rtc)
+                }
+                _ => Err(CommandError::Usage("date [set YYYY-MM-DD HH:MM:SS]")),
+            }
+        }
+    );
+
+    register_command!(
+        shell,
+        "ports",
+        "Lists the I/O port reservations",
+        move |out, _| {
+            for reservation in port_manager.reservations() {
+                writeln!(
+                    out,
+                    "{:#06x}-{:#06x} {}",
+                    reservation.start,
+                    reservation.start as u32 + reservation.len as u32 - 1,
+                    reservation.owner
+                )?;
+            }
+            Ok(())
+        }
+    );
+
+    register_command!(shell, "reboot", "Resets the machine", |_, _| qemu::reboot());
+
+    register_command!(
+        shell,
+        "exit",
+        "Ends QEMU with an exit code, `exit <code>`",
+        |_, args| {
+            let code = match args {
+                [] => ExitCode::Success,
+                [code] => ExitCode::Custom(
+                    code.parse()
+                        .map_err(|_| CommandError::InvalidArgument(code.to_string()))?,
+                ),
+                _ => return Err(CommandError::Usage("exit [code]")),
+            };
+
+            qemu::exit(code)
+        }
+    );
+}
+
+fn write_date_time(out: &mut dyn Write, rtc: &Rtc) -> Result<(), CommandError> {
+    let date_time = rtc
+        .read()
+        .map_err(|e| CommandError::Failed(alloc::format!("RTC holds no valid time: {}", e)))?;
+    writeln!(out, "{} {:?}", date_time, date_time.weekday())?;
+    Ok(())
+}
+
+// Takes "2024-07-24" and "12:34:56"
+fn parse_date_time(date: &str, time: &str) -> Result<DateTime, CommandError> {
+    let invalid = || CommandError::InvalidArgument(alloc::format!("{} {}", date, time));
+    let [year, month, day]: [u16; 3] = fields(date, '-').ok_or_else(invalid)?;
+    let [hours, minutes, seconds] = fields(time, ':').ok_or_else(invalid)?;
+    let [month, day] = [month, day].map(u8::try_from);
+
+    DateTime::new(
+        year,
+        month.map_err(|_| invalid())?,
+        day.map_err(|_| invalid())?,
+        hours,
+        minutes,
+        seconds,
+    )
+    .map_err(|e| CommandError::InvalidArgument(e.to_string()))
+}
+
+fn fields<T: FromStr>(text: &str, separator: char) -> Option<[T; 3]> {
+    let mut fields = text.split(separator).map(|field| field.parse().ok());
+    let parsed = [fields.next()??, fields.next()??, fields.next()??];
+    fields.next().is_none().then_some(parsed)
+}
@@ -0,0 +1,219 @@
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+pub const HISTORY_SIZE: usize = 32;
+// Columns of the VGA text screen and of a serial terminal by default
+pub const LINE_WIDTH: usize = 80;
+
+/// Editing key, decoded from keyboard events or the escape sequences of a serial terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKey {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Tab,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    // Ctrl+C, drops the line
+    Interrupt,
+}
+
+/// Single line editor with history and completion, drawing through VT100 sequences.
+///
+/// The prompt and the line stay within one row of `LINE_WIDTH` columns, redrawing a line that
+/// wrapped would start on the wrong row.
+pub struct LineEditor {
+    prompt: &'static str,
+    line: Vec<char>,
+    cursor: usize,
+    history: VecDeque<String>,
+    // Entry shown while browsing the history, `history.len()` is the line being typed
+    history_index: usize,
+    // The line being typed, kept while browsing the history
+    draft: Vec<char>,
+}
+
+impl LineEditor {
+    pub fn new(prompt: &'static str) -> LineEditor {
+        LineEditor {
+            prompt,
+            line: Vec::new(),
+            cursor: 0,
+            history: VecDeque::with_capacity(HISTORY_SIZE),
+            history_index: 0,
+            draft: Vec::new(),
+        }
+    }
+
+    pub fn prompt(&self, out: &mut dyn Write) -> fmt::Result {
+        out.write_str(self.prompt)
+    }
+
+    /// Applies `key`, returns the line once Enter is pressed.
+    ///
+    /// `complete` lists the candidates for the word at the start of the line.
+    pub fn handle(
+        &mut self,
+        key: EditKey,
+        out: &mut dyn Write,
+        complete: &dyn Fn(&str) -> Vec<&'static str>,
+    ) -> Result<Option<String>, fmt::Error> {
+        match key {
+            EditKey::Char(_) if self.line.len() >= self.max_len() => return Ok(None),
+            EditKey::Char(character) => {
+                self.line.insert(self.cursor, character);
+                self.cursor += 1;
+            }
+            EditKey::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            }
+            EditKey::Delete if self.cursor < self.line.len() => {
+                self.line.remove(self.cursor);
+            }
+            EditKey::Left => self.cursor = self.cursor.saturating_sub(1),
+            EditKey::Right => self.cursor = (self.cursor + 1).min(self.line.len()),
+            EditKey::Home => self.cursor = 0,
+            EditKey::End => self.cursor = self.line.len(),
+            EditKey::Up => self.browse_history(-1),
+            EditKey::Down => self.browse_history(1),
+            EditKey::Tab => self.complete(out, complete)?,
+            EditKey::Interrupt => {
+                out.write_str("^C\n")?;
+                self.reset();
+                self.prompt(out)?;
+                return Ok(None);
+            }
+            EditKey::Enter => {
+                out.write_str("\n")?;
+                let line: String = self.line.iter().collect();
+                self.add_history(&line);
+                self.reset();
+                return Ok(Some(line));
+            }
+            _ => return Ok(None),
+        }
+
+        self.redraw(out)?;
+
+        Ok(None)
+    }
+
+    /// Characters a line can have, the last column is kept free so the terminal does not wrap.
+    pub fn max_len(&self) -> usize {
+        LINE_WIDTH.saturating_sub(self.prompt.chars().count() + 1)
+    }
+
+    /// Entries of the history, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        self.history.iter().map(String::as_str)
+    }
+
+    fn reset(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+        self.draft.clear();
+        self.history_index = self.history.len();
+    }
+
+    fn add_history(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.history.back().is_some_and(|last| last == line) {
+            return;
+        }
+
+        if self.history.len() == HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        self.history.push_back(String::from(line));
+    }
+
+    fn browse_history(&mut self, step: isize) {
+        let Some(index) = self.history_index.checked_add_signed(step) else {
+            return;
+        };
+        if index > self.history.len() {
+            return;
+        }
+
+        if self.history_index == self.history.len() {
+            self.draft = core::mem::take(&mut self.line);
+        }
+        self.history_index = index;
+        self.line = match self.history.get(index) {
+            Some(entry) => entry.chars().collect(),
+            None => core::mem::take(&mut self.draft),
+        };
+        self.cursor = self.line.len();
+    }
+
+    // Completes the command name, a single candidate is taken, several are listed and their
+    // common prefix is taken
+    fn complete(
+        &mut self,
+        out: &mut dyn Write,
+        complete: &dyn Fn(&str) -> Vec<&'static str>,
+    ) -> fmt::Result {
+        let before_cursor: String = self.line[..self.cursor].iter().collect();
+        if before_cursor.contains(' ') {
+            return Ok(());
+        }
+
+        let candidates = complete(&before_cursor);
+        let completion = match candidates.as_slice() {
+            [] => return Ok(()),
+            [candidate] => alloc::format!("{} ", candidate),
+            [first, rest @ ..] => {
+                out.write_str("\n")?;
+                for candidate in &candidates {
+                    write!(out, "{}  ", candidate)?;
+                }
+                out.write_str("\n")?;
+
+                let common = rest.iter().fold(first.len(), |common, candidate| {
+                    first
+                        .bytes()
+                        .zip(candidate.bytes())
+                        .take(common)
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                });
+                String::from(&first[..common])
+            }
+        };
+
+        if self.line.len() - self.cursor + completion.chars().count() > self.max_len() {
+            return Ok(());
+        }
+
+        let rest: Vec<char> = self.line.split_off(self.cursor);
+        self.line = completion.chars().chain(rest).collect();
+        self.cursor = completion.chars().count();
+
+        Ok(())
+    }
+
+    // Rewrites the whole line, then moves the cursor back into place
+    fn redraw(&self, out: &mut dyn Write) -> fmt::Result {
+        out.write_str("\r")?;
+        out.write_str(self.prompt)?;
+        for &character in &self.line {
+            out.write_char(character)?;
+        }
+        out.write_str("\x1b[K")?;
+
+        let back = self.line.len() - self.cursor;
+        if back > 0 {
+            write!(out, "\x1b[{}D", back)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+pub mod commands; // Contains the built-in commands
+pub mod line_editor; // Contains line editing, history and completion
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use thiserror_no_std::Error;
+
+use crate::interrupt;
+use crate::io::ansi::{Action, Parser};
+use crate::io::keyboard::{self, KeyCode};
+use crate::io::serial::Serial;
+use crate::io::{DisplayWriter, DISPLAY};
+use line_editor::{EditKey, LineEditor};
+
+const PROMPT: &str = "kratos> ";
+
+/// Adds a command to a `Shell`.
+///
+/// ```ignore
+/// register_command!(shell, "echo", "Prints its arguments", |out, args| {
+///     writeln!(out, "{}", args.join(" "))?;
+///     Ok(())
+/// });
+/// ```
+#[macro_export]
+macro_rules! register_command {
+    ($shell:expr, $name:literal, $help:literal, $run:expr) => {
+        $shell.register($crate::shell::Command::new($name, $help, $run))
+    };
+}
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("Usage: {0}")]
+    Usage(&'static str),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("{0}")]
+    Failed(String),
+    #[error("Failed to write the output")]
+    Output(#[from] fmt::Error),
+}
+
+type Run<'a> = Box<dyn FnMut(&mut dyn Write, &[&str]) -> Result<(), CommandError> + 'a>;
+
+pub struct Command<'a> {
+    pub name: &'static str,
+    pub help: &'static str,
+    run: Run<'a>,
+}
+
+impl<'a> Command<'a> {
+    pub fn new(
+        name: &'static str,
+        help: &'static str,
+        run: impl FnMut(&mut dyn Write, &[&str]) -> Result<(), CommandError> + 'a,
+    ) -> Command<'a> {
+        Command {
+            name,
+            help,
+            run: Box::new(run),
+        }
+    }
+}
+
+/// Where the shell talks to the user.
+///
+/// Keyboard input is always accepted, serial input comes from the debug console if there is one,
+/// otherwise from the serial console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Console {
+    // Output goes to every display sink, input from the serial console
+    Display,
+    // Output and input stay on the debug console
+    DebugConsole,
+}
+
+impl Console {
+    pub fn detect() -> Console {
+        match DISPLAY.borrow().debug_console {
+            Some(_) => Console::DebugConsole,
+            None => Console::Display,
+        }
+    }
+
+    fn read_serial(&self) -> Option<u8> {
+        let display = DISPLAY.borrow();
+        let serial = match self {
+            Console::Display => display.sinks.get::<Serial>(),
+            Console::DebugConsole => display.debug_console.as_ref(),
+        }?;
+
+        // Line errors only lose the damaged byte
+        serial.try_read().ok().flatten()
+    }
+}
+
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self {
+            Console::Display => DisplayWriter.write_str(s)?,
+            Console::DebugConsole => {
+                if let Some(debug_console) = &DISPLAY.borrow().debug_console {
+                    debug_console.write(s);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Shell<'a> {
+    commands: Vec<Command<'a>>,
+    editor: LineEditor,
+    // Decodes the escape sequences of serial terminals
+    parser: Parser,
+    after_carriage_return: bool,
+}
+
+impl Default for Shell<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Shell<'a> {
+    pub fn new() -> Shell<'a> {
+        Shell {
+            commands: Vec::new(),
+            editor: LineEditor::new(PROMPT),
+            parser: Parser::new(),
+            after_carriage_return: false,
+        }
+    }
+
+    /// Adds `command`, replacing a command with the same name.
+    pub fn register(&mut self, command: Command<'a>) {
+        self.commands
+            .retain(|registered| registered.name != command.name);
+        self.commands.push(command);
+        self.commands.sort_by_key(|command| command.name);
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        self.commands
+            .iter()
+            .map(|command| (command.name, command.help))
+    }
+
+    /// Names starting with `prefix`, `help` included, sorted.
+    pub fn completions(&self, prefix: &str) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = core::iter::once("help")
+            .chain(self.commands.iter().map(|command| command.name))
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+
+        names
+    }
+
+    /// Reads and runs commands forever.
+    pub fn run(&mut self, mut console: Console) -> ! {
+        let _ = self.editor.prompt(&mut console);
+
+        loop {
+            let key = self.read_key(&console);
+            let names = self.completions("");
+            let complete = |prefix: &str| {
+                names
+                    .iter()
+                    .copied()
+                    .filter(|name| name.starts_with(prefix))
+                    .collect()
+            };
+
+            if let Ok(Some(line)) = self.editor.handle(key, &mut console, &complete) {
+                self.execute(&line, &mut console);
+                let _ = self.editor.prompt(&mut console);
+            }
+        }
+    }
+
+    /// Runs a command line, errors are reported to `out`.
+    pub fn execute(&mut self, line: &str, out: &mut dyn Write) {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return;
+        };
+        let args: Vec<&str> = words.collect();
+
+        if name == "help" {
+            let _ = self.help(out);
+            return;
+        }
+
+        let result = match self
+            .commands
+            .iter_mut()
+            .find(|command| command.name == name)
+        {
+            Some(command) => (command.run)(out, &args),
+            None => Err(CommandError::Failed(alloc::format!(
+                "Unknown command {}, try help",
+                name
+            ))),
+        };
+
+        if let Err(e) = result {
+            let _ = writeln!(out, "{}: {}", name, e);
+        }
+    }
+
+    fn help(&self, out: &mut dyn Write) -> fmt::Result {
+        writeln!(out, "{:<8} Lists the commands", "help")?;
+        for (name, help) in self.commands() {
+            writeln!(out, "{:<8} {}", name, help)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_key(&mut self, console: &Console) -> EditKey {
+        // Events and bytes that make no key, e.g. releases and escape sequence prefixes, are
+        // drained before sleeping, the rest of them may already be queued
+        interrupt::wait_for(|| {
+            while let Some(event) = keyboard::try_read_event() {
+                if let Some(key) = keyboard_key(event) {
+                    return Some(key);
+                }
+            }
+            while let Some(byte) = console.read_serial() {
+                if let Some(key) = self.serial_key(byte) {
+                    return Some(key);
+                }
+            }
+
+            None
+        })
+    }
+
+    fn serial_key(&mut self, byte: u8) -> Option<EditKey> {
+        let after_carriage_return =
+            core::mem::replace(&mut self.after_carriage_return, byte == b'\r');
+
+        match self.parser.advance(byte)? {
+            Action::Print(b'\n') if after_carriage_return => None,
+            Action::Print(byte) => control_key(byte as char),
+            Action::Csi(csi) => match (csi.final_byte, csi.param(0, 1)) {
+                (b'A', _) => Some(EditKey::Up),
+                (b'B', _) => Some(EditKey::Down),
+                (b'C', _) => Some(EditKey::Right),
+                (b'D', _) => Some(EditKey::Left),
+                (b'H', _) | (b'~', 1 | 7) => Some(EditKey::Home),
+                (b'F', _) | (b'~', 4 | 8) => Some(EditKey::End),
+                (b'~', 3) => Some(EditKey::Delete),
+                _ => None,
+            },
+        }
+    }
+}
+
+fn keyboard_key(event: keyboard::KeyEvent) -> Option<EditKey> {
+    if !event.pressed {
+        return None;
+    }
+
+    match event.key {
+        KeyCode::ArrowUp => Some(EditKey::Up),
+        KeyCode::ArrowDown => Some(EditKey::Down),
+        KeyCode::ArrowLeft => Some(EditKey::Left),
+        KeyCode::ArrowRight => Some(EditKey::Right),
+        KeyCode::Home => Some(EditKey::Home),
+        KeyCode::End => Some(EditKey::End),
+        KeyCode::Delete => Some(EditKey::Delete),
+        key => control_key(keyboard::keymap().translate(key, &event.modifiers)?),
+    }
+}
+
+fn control_key(character: char) -> Option<EditKey> {
+    match character {
+        '\r' | '\n' => Some(EditKey::Enter),
+        '\x08' | '\x7f' => Some(EditKey::Backspace),
+        '\t' => Some(EditKey::Tab),
+        '\x03' => Some(EditKey::Interrupt),
+        ' '..='~' => Some(EditKey::Char(character)),
+        // Everything above ASCII, the VGA shows what code page 437 has of it
+        character if character > '\x7f' => Some(EditKey::Char(character)),
+        _ => None,
+    }
+}
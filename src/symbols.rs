@@ -0,0 +1,194 @@
+use core::cell::RefCell;
+use core::fmt;
+use thiserror_no_std::Error;
+
+use crate::boot_info::ElfSections;
+use crate::elf::{section_headers, Symbol, SHT_STRTAB, SHT_SYMTAB, STT_FUNC};
+
+static SYMBOL_TABLE: SymbolTableCell = SymbolTableCell {
+    inner: RefCell::new(None),
+};
+
+struct SymbolTableCell {
+    inner: RefCell<Option<SymbolTable>>,
+}
+
+unsafe impl Sync for SymbolTableCell {}
+
+struct SymbolTable {
+    symbols: &'static [Symbol],
+    strings: &'static [u8],
+}
+
+impl SymbolTable {
+    fn name(&self, symbol: &Symbol) -> &'static str {
+        let start = symbol.st_name as usize;
+        let Some(tail) = self.strings.get(start..) else {
+            return "";
+        };
+        let len = tail.iter().position(|&c| c == 0).unwrap_or(tail.len());
+
+        core::str::from_utf8(&tail[..len]).unwrap_or("")
+    }
+
+    // Closest function at or before addr, symbols without a size cover everything up to the next one
+    fn lookup(&self, addr: u32) -> Option<(&'static str, u32)> {
+        let symbol = self
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.symbol_type() == STT_FUNC && symbol.st_value <= addr)
+            .filter(|symbol| symbol.st_size == 0 || addr - symbol.st_value < symbol.st_size)
+            .max_by_key(|symbol| symbol.st_value)?;
+
+        Some((self.name(symbol), addr - symbol.st_value))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SymbolsError {
+    #[error("No symbol table section")]
+    NoSymbolTable,
+    #[error("No string table linked to the symbol table")]
+    NoStringTable,
+}
+
+/// Locates `.symtab` and `.strtab` through the section headers passed by the boot loader.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn init(sections: &ElfSections) -> Result<(), SymbolsError> {
+    use SymbolsError::*;
+
+    let headers = section_headers(sections);
+    let symtab = headers
+        .iter()
+        .find(|header| header.sh_type == SHT_SYMTAB && header.sh_addr != 0)
+        .ok_or(NoSymbolTable)?;
+    let strtab = headers
+        .get(symtab.sh_link as usize)
+        .filter(|header| header.sh_type == SHT_STRTAB && header.sh_addr != 0)
+        .ok_or(NoStringTable)?;
+
+    let symbols = core::slice::from_raw_parts(
+        symtab.sh_addr as *const Symbol,
+        symtab.sh_size as usize / core::mem::size_of::<Symbol>(),
+    );
+    let strings = core::slice::from_raw_parts(strtab.sh_addr as *const u8, strtab.sh_size as usize);
+
+    *SYMBOL_TABLE.inner.borrow_mut() = Some(SymbolTable { symbols, strings });
+
+    Ok(())
+}
+
+/// Returns the mangled name of the function containing `addr` and the offset into it.
+pub fn symbolize(addr: u32) -> Option<(&'static str, u32)> {
+    let table = SYMBOL_TABLE.inner.try_borrow().ok()?;
+    table.as_ref()?.lookup(addr)
+}
+
+/// Formats an address as `0x201a3c <kratos::allocator::insert_segment_into_list+0x3c>`.
+pub struct Symbolized(pub u32);
+
+impl fmt::Display for Symbolized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)?;
+        if let Some((name, offset)) = symbolize(self.0) {
+            write!(f, " <{}+{:#x}>", Demangle(name), offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Demangles legacy Rust symbols, e.g. `_ZN6kratos4main17h0123456789abcdefE` to `kratos::main`.
+///
+/// Names that are not mangled are written as is.
+pub struct Demangle<'a>(pub &'a str);
+
+impl fmt::Display for Demangle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(mut rest) = self
+            .0
+            .strip_prefix("_ZN")
+            .and_then(|rest| rest.strip_suffix('E'))
+        else {
+            return f.write_str(self.0);
+        };
+
+        let mut first = true;
+        while !rest.is_empty() {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let len = match rest[..digits].parse::<usize>() {
+                Ok(len) if digits + len <= rest.len() => len,
+                _ => return f.write_str(self.0),
+            };
+            let component = &rest[digits..digits + len];
+            rest = &rest[digits + len..];
+
+            // Drop the trailing hash component
+            if rest.is_empty() && is_hash(component) {
+                break;
+            }
+
+            if !first {
+                f.write_str("::")?;
+            }
+            first = false;
+
+            write_component(f, component)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_hash(component: &str) -> bool {
+    component.len() == 17
+        && component.starts_with('h')
+        && component[1..].bytes().all(|c| c.is_ascii_hexdigit())
+}
+
+fn write_component(f: &mut fmt::Formatter<'_>, component: &str) -> fmt::Result {
+    let mut rest = if component.starts_with("_$") {
+        &component[1..]
+    } else {
+        component
+    };
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(tail) = rest.strip_prefix("..") {
+            f.write_str("::")?;
+            rest = tail;
+            continue;
+        }
+
+        if c == '$' {
+            if let Some(end) = rest[1..].find('$') {
+                let escape = &rest[1..end + 1];
+                let decoded = match escape {
+                    "SP" => Some('@'),
+                    "BP" => Some('*'),
+                    "RF" => Some('&'),
+                    "LT" => Some('<'),
+                    "GT" => Some('>'),
+                    "LP" => Some('('),
+                    "RP" => Some(')'),
+                    "C" => Some(','),
+                    _ => escape
+                        .strip_prefix('u')
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .and_then(char::from_u32),
+                };
+
+                if let Some(decoded) = decoded {
+                    write!(f, "{}", decoded)?;
+                    rest = &rest[end + 2..];
+                    continue;
+                }
+            }
+        }
+
+        write!(f, "{}", c)?;
+        rest = &rest[c.len_utf8()..];
+    }
+
+    Ok(())
+}
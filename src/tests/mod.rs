@@ -2,9 +2,27 @@ use alloc::string::String;
 use kratos::{print, println};
 
 // Test
+mod test_acpi;
 mod test_allocator;
+mod test_backtrace;
 mod test_bit_manipulation;
+mod test_boot_info;
+mod test_cmdline;
+mod test_date_time;
+mod test_dmesg;
+mod test_framebuffer;
 mod test_gdt;
+mod test_keyboard;
+mod test_logger;
+mod test_memory_map;
+mod test_mouse;
+mod test_port_manager;
+mod test_ring_buffer;
+mod test_serial_config;
+mod test_shell;
+mod test_sink;
+mod test_symbols;
+mod test_vga;
 
 pub struct TestCase {
     pub name: &'static str,
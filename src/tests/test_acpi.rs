@@ -0,0 +1,39 @@
+use kratos::acpi::{checksum, parse_soft_off};
+use kratos::qemu::ExitCode;
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+create_test!(test_acpi_soft_off, {
+    // Name (_S5, Package (0x04) { Zero, Zero, Zero, Zero })
+    let aml = [
+        0x10, 0x08, 0x5F, 0x53, 0x35, 0x5F, 0x12, 0x06, 0x04, 0x00, 0x00, 0x00, 0x00,
+    ];
+    test_eq!(parse_soft_off(&aml), Some((0, 0)));
+
+    // Name (\_S5, Package (0x02) { 0x05, One }) with a two byte PkgLength
+    let aml = [
+        0x08, b'\\', b'_', b'S', b'5', b'_', 0x12, 0x40, 0x00, 0x02, 0x0A, 0x05, 0x01,
+    ];
+    test_eq!(parse_soft_off(&aml), Some((5, 1)));
+
+    // A method referencing _S5 is not its definition
+    let aml = [0x14, b'_', b'S', b'5', b'_', 0x12, 0x06, 0x04, 0x00, 0x00];
+    test_true!(parse_soft_off(&aml).is_none());
+    test_true!(parse_soft_off(b"no sleep states").is_none());
+    Ok(())
+});
+
+create_test!(test_acpi_checksum, {
+    test_eq!(checksum(&[0x10, 0xF0]), 0);
+    test_eq!(checksum(&[0xFF, 0x02, 0x03]), 4);
+    Ok(())
+});
+
+create_test!(test_qemu_exit_code, {
+    test_eq!(ExitCode::Success.host_status(), 1);
+    test_eq!(ExitCode::Failure.host_status(), 3);
+    test_eq!(ExitCode::Custom(0x10).code(), 0x10);
+    test_eq!(ExitCode::Custom(0x10).host_status(), 0x21);
+    Ok(())
+});
@@ -0,0 +1,15 @@
+use kratos::backtrace::{stack_range, Backtrace};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_true};
+
+create_test!(test_backtrace_capture, {
+    let backtrace = Backtrace::capture();
+    let (bottom, top) = stack_range();
+
+    test_true!(bottom < top);
+    // test function <- test runner <- kernel_main
+    test_true!(backtrace.frames().len() >= 3);
+    test_true!(backtrace.frames().iter().all(|&addr| addr != 0));
+    Ok(())
+});
@@ -0,0 +1,110 @@
+use alloc::vec::Vec;
+use kratos::boot_info::{BootInfo, BootProtocol, FramebufferKind, Rsdp};
+use kratos::multiboot2::MULTIBOOT2_BOOTLOADER_MAGIC;
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+pub(super) fn push_tag(info: &mut Vec<u8>, type_: u32, payload: &[u8]) {
+    info.extend_from_slice(&type_.to_le_bytes());
+    info.extend_from_slice(&(8 + payload.len() as u32).to_le_bytes());
+    info.extend_from_slice(payload);
+    while info.len() % 8 != 0 {
+        info.push(0);
+    }
+}
+
+// Builds a multiboot2 information structure in an 8 byte aligned, leaked buffer
+fn build_multiboot2_info() -> *const u8 {
+    let mut info = Vec::new();
+    info.extend_from_slice(&[0; 8]);
+
+    push_tag(&mut info, 1, b"console=ttyS0\0");
+    push_tag(&mut info, 2, b"GRUB 2.06\0");
+
+    let mut module = Vec::new();
+    module.extend_from_slice(&0x300000u32.to_le_bytes());
+    module.extend_from_slice(&0x301000u32.to_le_bytes());
+    module.extend_from_slice(b"initrd\0");
+    push_tag(&mut info, 3, &module);
+
+    let mut mmap = Vec::new();
+    mmap.extend_from_slice(&24u32.to_le_bytes());
+    mmap.extend_from_slice(&0u32.to_le_bytes());
+    for (addr, len, type_) in [(0u64, 0x9FC00u64, 1u32), (0x100000, 0x7EE0000, 1)] {
+        mmap.extend_from_slice(&addr.to_le_bytes());
+        mmap.extend_from_slice(&len.to_le_bytes());
+        mmap.extend_from_slice(&type_.to_le_bytes());
+        mmap.extend_from_slice(&0u32.to_le_bytes());
+    }
+    push_tag(&mut info, 6, &mmap);
+
+    let mut framebuffer = Vec::new();
+    framebuffer.extend_from_slice(&0xFD000000u64.to_le_bytes());
+    framebuffer.extend_from_slice(&4096u32.to_le_bytes());
+    framebuffer.extend_from_slice(&1024u32.to_le_bytes());
+    framebuffer.extend_from_slice(&768u32.to_le_bytes());
+    framebuffer.extend_from_slice(&[32, 1, 0, 0, 16, 8, 8, 8, 0, 8]);
+    push_tag(&mut info, 8, &framebuffer);
+
+    push_tag(&mut info, 14, &[0; 20]);
+
+    finish_multiboot2_info(info)
+}
+
+// Adds the end tag and the total size to tags pushed after 8 bytes of room for the header
+pub(super) fn finish_multiboot2_info(mut info: Vec<u8>) -> *const u8 {
+    push_tag(&mut info, 0, &[]);
+
+    let total_size = info.len() as u32;
+    info[0..4].copy_from_slice(&total_size.to_le_bytes());
+
+    let mut aligned = alloc::vec![0u64; info.len() / 8];
+    for (index, chunk) in info.chunks(8).enumerate() {
+        aligned[index] = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+
+    aligned.leak().as_ptr() as *const u8
+}
+
+create_test!(test_multiboot2_boot_info, {
+    let info = build_multiboot2_info();
+    let boot_info = unsafe { BootInfo::new(MULTIBOOT2_BOOTLOADER_MAGIC, info) }
+        .map_err(|e| alloc::format!("{}", e))?;
+
+    test_eq!(boot_info.protocol, BootProtocol::Multiboot2);
+    test_eq!(boot_info.cmdline, Some("console=ttyS0"));
+    test_eq!(boot_info.boot_loader_name, Some("GRUB 2.06"));
+
+    test_eq!(boot_info.modules().len(), 1);
+    test_eq!(boot_info.modules()[0].start, 0x300000);
+    test_eq!(boot_info.modules()[0].cmdline, "initrd");
+
+    test_eq!(boot_info.memory_map().len(), 2);
+    test_eq!(boot_info.memory_map()[1].addr, 0x100000);
+    test_eq!(boot_info.memory_map()[1].len, 0x7EE0000);
+
+    let framebuffer = boot_info.framebuffer.ok_or("Missing framebuffer")?;
+    test_eq!(framebuffer.width, 1024);
+    test_eq!(framebuffer.bpp, 32);
+    test_eq!(
+        framebuffer.kind,
+        FramebufferKind::Rgb {
+            red_position: 16,
+            red_size: 8,
+            green_position: 8,
+            green_size: 8,
+            blue_position: 0,
+            blue_size: 8,
+        }
+    );
+
+    test_true!(matches!(boot_info.rsdp, Some(Rsdp::V1(_))));
+    Ok(())
+});
+
+create_test!(test_unknown_boot_magic, {
+    let info = build_multiboot2_info();
+    test_true!(unsafe { BootInfo::new(0xDEADBEEF, info) }.is_err());
+    Ok(())
+});
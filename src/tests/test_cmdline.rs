@@ -0,0 +1,16 @@
+use kratos::cmdline;
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+create_test!(test_cmdline_options, {
+    let line = "/boot/myos.bin console=com2,9600 quiet console=com1  log=debug";
+
+    test_eq!(cmdline::get(line, "console"), Some("com1"));
+    test_eq!(cmdline::get(line, "log"), Some("debug"));
+    test_true!(cmdline::get(line, "quiet").is_none());
+    test_true!(cmdline::has(line, "quiet"));
+    test_true!(!cmdline::has(line, "debug"));
+    test_eq!(cmdline::options(line).count(), 5);
+    Ok(())
+});
@@ -0,0 +1,132 @@
+use core::time::Duration;
+use kratos::time::{DateTime, DateTimeError, Weekday};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+fn date_time(
+    year: u16,
+    month: u8,
+    day: u8,
+    hours: u8,
+    minutes: u8,
+    seconds: u8,
+) -> Result<DateTime, alloc::string::String> {
+    DateTime::new(year, month, day, hours, minutes, seconds).map_err(|e| alloc::format!("{}", e))
+}
+
+create_test!(test_date_time_validation, {
+    test_true!(DateTime::new(2024, 2, 29, 0, 0, 0).is_ok());
+    test_true!(DateTime::new(2000, 2, 29, 0, 0, 0).is_ok());
+    test_eq!(
+        DateTime::new(2023, 2, 29, 0, 0, 0).err(),
+        Some(DateTimeError::InvalidDay {
+            year: 2023,
+            month: 2,
+            day: 29
+        })
+    );
+    // Centuries are only leap years every 400 years
+    test_true!(DateTime::new(1900, 2, 29, 0, 0, 0).is_err());
+    test_true!(DateTime::new(2024, 4, 31, 0, 0, 0).is_err());
+    test_true!(DateTime::new(2024, 1, 0, 0, 0, 0).is_err());
+    test_eq!(
+        DateTime::new(2024, 13, 1, 0, 0, 0).err(),
+        Some(DateTimeError::InvalidMonth(13))
+    );
+    test_eq!(
+        DateTime::new(0, 1, 1, 0, 0, 0).err(),
+        Some(DateTimeError::InvalidYear(0))
+    );
+    test_eq!(
+        DateTime::new(2024, 1, 1, 24, 0, 0).err(),
+        Some(DateTimeError::InvalidTime(24, 0, 0))
+    );
+    test_true!(DateTime::new(2024, 1, 1, 23, 60, 0).is_err());
+    test_true!(DateTime::new(2024, 1, 1, 23, 59, 60).is_err());
+    Ok(())
+});
+
+create_test!(test_date_time_unix_timestamp, {
+    let epoch = date_time(1970, 1, 1, 0, 0, 0)?;
+    test_eq!(epoch.unix_timestamp(), 0);
+    test_eq!(DateTime::from_unix_timestamp(0).ok(), Some(epoch));
+
+    let date = date_time(2024, 7, 24, 12, 34, 56)?;
+    test_eq!(date.unix_timestamp(), 1_721_824_496);
+    test_eq!(
+        DateTime::from_unix_timestamp(1_721_824_496).ok(),
+        Some(date)
+    );
+
+    let leap_day = date_time(2000, 2, 29, 0, 0, 0)?;
+    test_eq!(leap_day.unix_timestamp(), 951_782_400);
+    test_eq!(
+        DateTime::from_unix_timestamp(951_782_400).ok(),
+        Some(leap_day)
+    );
+
+    // One second before the epoch
+    test_eq!(
+        DateTime::from_unix_timestamp(-1).ok(),
+        Some(date_time(1969, 12, 31, 23, 59, 59)?)
+    );
+    test_eq!(DateTime::MIN.unix_timestamp(), -62_135_596_800);
+    test_eq!(DateTime::MAX.unix_timestamp(), 253_402_300_799);
+    test_eq!(
+        DateTime::from_unix_timestamp(253_402_300_800).err(),
+        Some(DateTimeError::TimestampOutOfRange(253_402_300_800))
+    );
+    Ok(())
+});
+
+create_test!(test_date_time_arithmetic, {
+    let midnight = date_time(2024, 3, 1, 0, 0, 0)?;
+
+    // Going back an hour from midnight crosses into the leap day
+    test_eq!(
+        midnight.checked_sub(Duration::from_secs(3600)),
+        Some(date_time(2024, 2, 29, 23, 0, 0)?)
+    );
+    test_eq!(
+        date_time(2024, 12, 31, 23, 59, 59)?.checked_add(Duration::from_secs(1)),
+        Some(date_time(2025, 1, 1, 0, 0, 0)?)
+    );
+    test_eq!(
+        midnight.checked_add(Duration::from_secs(365 * 24 * 3600)),
+        Some(date_time(2025, 3, 1, 0, 0, 0)?)
+    );
+    // Fractions of a second are dropped
+    test_eq!(
+        midnight.checked_add(Duration::from_millis(999)),
+        Some(midnight)
+    );
+
+    test_true!(DateTime::MAX.checked_add(Duration::from_secs(1)).is_none());
+    test_true!(DateTime::MIN.checked_sub(Duration::from_secs(1)).is_none());
+    test_true!(midnight.checked_add(Duration::MAX).is_none());
+    test_true!(DateTime::MIN < midnight && midnight < DateTime::MAX);
+    Ok(())
+});
+
+create_test!(test_date_time_weekday, {
+    test_eq!(date_time(1970, 1, 1, 0, 0, 0)?.weekday(), Weekday::Thursday);
+    test_eq!(date_time(2000, 1, 1, 0, 0, 0)?.weekday(), Weekday::Saturday);
+    test_eq!(date_time(1900, 1, 1, 0, 0, 0)?.weekday(), Weekday::Monday);
+    test_eq!(
+        date_time(2024, 7, 28, 23, 59, 59)?.weekday(),
+        Weekday::Sunday
+    );
+    test_eq!(Weekday::Sunday.days_from_sunday(), 0);
+    test_eq!(Weekday::Saturday.days_from_sunday(), 6);
+    Ok(())
+});
+
+create_test!(test_date_time_display, {
+    test_eq!(
+        alloc::format!("{}", date_time(2024, 7, 4, 9, 5, 3)?),
+        "2024-07-04T09:05:03"
+    );
+    test_eq!(alloc::format!("{}", DateTime::MIN), "0001-01-01T00:00:00");
+    Ok(())
+});
@@ -0,0 +1,70 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use kratos::dmesg::{LogBuffer, MESSAGE_LEN};
+use log::{Level, LevelFilter};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+create_test!(test_dmesg_sequence, {
+    let buffer: LogBuffer<4> = LogBuffer::new();
+    test_eq!(
+        buffer.push(Level::Info, 1500, format_args!("first")),
+        Some(0)
+    );
+    test_eq!(
+        buffer.push(Level::Warn, 2001, format_args!("{}", 2)),
+        Some(1)
+    );
+
+    let entries: Vec<_> = buffer.entries().collect();
+    test_eq!(entries.len(), 2);
+    test_eq!(entries[1].sequence, 1);
+    test_eq!(entries[1].level, Level::Warn);
+    test_eq!(entries[1].text(), "2");
+    test_eq!(alloc::format!("{}", entries[0]), "[    1.500] INFO  first");
+    test_true!(buffer.get(2).is_none());
+    Ok(())
+});
+
+create_test!(test_dmesg_overflow, {
+    let buffer: LogBuffer<4> = LogBuffer::new();
+    for i in 0..6 {
+        buffer.push(Level::Info, 0, format_args!("{}", i));
+    }
+
+    test_eq!(buffer.first_sequence(), 2);
+    test_eq!(buffer.next_sequence(), 6);
+    test_eq!(buffer.overwritten(), 2);
+    test_true!(buffer.get(1).is_none());
+    let texts: Vec<String> = buffer
+        .entries()
+        .map(|entry| String::from(entry.text()))
+        .collect();
+    test_eq!(texts, ["2", "3", "4", "5"]);
+    // Readers that fell behind continue at the oldest kept message
+    test_eq!(
+        buffer.entries_from(1).next().map(|entry| entry.sequence),
+        Some(2)
+    );
+
+    let mut out = String::new();
+    buffer
+        .write_entries(&mut out, LevelFilter::Info)
+        .map_err(|_| String::from("Write failed"))?;
+    test_true!(out.starts_with("[2 earlier messages lost]\n"));
+    Ok(())
+});
+
+create_test!(test_dmesg_truncation, {
+    let buffer: LogBuffer<2> = LogBuffer::new();
+    // Multi-byte characters are not split
+    let long: String = core::iter::repeat('é').take(MESSAGE_LEN).collect();
+    buffer.push(Level::Error, 0, format_args!("x{}", long));
+
+    let entry = buffer.get(0).ok_or(String::from("Entry missing"))?;
+    test_true!(entry.is_truncated());
+    test_eq!(entry.text().len(), MESSAGE_LEN - 1);
+    test_true!(alloc::format!("{}", entry).ends_with("é..."));
+    Ok(())
+});
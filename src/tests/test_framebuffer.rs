@@ -0,0 +1,52 @@
+use alloc::vec;
+use core::fmt::Write;
+use kratos::boot_info::{FramebufferInfo, FramebufferKind};
+use kratos::io::framebuffer::{FramebufferConsole, PsfFont};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_ne, test_true};
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 32;
+
+create_test!(test_builtin_font, {
+    let font = PsfFont::builtin().ok_or("Failed to parse the builtin font")?;
+    test_eq!(font.width(), 8);
+    test_eq!(font.height(), 16);
+    Ok(())
+});
+
+create_test!(test_framebuffer_console, {
+    // Two columns and two rows of text in a 32 bpp XRGB buffer
+    let pixels = vec![0u32; WIDTH * HEIGHT].leak();
+    let info = FramebufferInfo {
+        addr: pixels.as_mut_ptr() as u64,
+        pitch: (WIDTH * 4) as u32,
+        width: WIDTH as u32,
+        height: HEIGHT as u32,
+        bpp: 32,
+        kind: FramebufferKind::Rgb {
+            red_position: 16,
+            red_size: 8,
+            green_position: 8,
+            green_size: 8,
+            blue_position: 0,
+            blue_size: 8,
+        },
+    };
+
+    let mut console = FramebufferConsole::new(&info).map_err(|e| alloc::format!("{}", e))?;
+    console.init().map_err(|_| "Failed to clear")?;
+    test_eq!(console.columns(), 2);
+    test_eq!(console.rows(), 2);
+
+    write!(console, "#").map_err(|_| "Failed to write")?;
+    let light_grey = 0x00AAAAAA;
+    test_true!(pixels[..WIDTH * 16].contains(&light_grey));
+    test_true!(!pixels[WIDTH * 16..].contains(&light_grey));
+
+    // The first row scrolls out once the third line starts
+    write!(console, "\n\n").map_err(|_| "Failed to write")?;
+    test_ne!(pixels[..WIDTH * 16].contains(&light_grey), true);
+    Ok(())
+});
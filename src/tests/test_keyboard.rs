@@ -0,0 +1,142 @@
+use kratos::io::keyboard::{Decoder, KeyCode, LedUpdate, ModifierState, Modifiers, ScancodeSet};
+use kratos::io::keymap;
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+fn decode(set: ScancodeSet, bytes: &[u8]) -> alloc::vec::Vec<(KeyCode, bool)> {
+    let mut decoder = Decoder::new(set);
+    bytes
+        .iter()
+        .filter_map(|&byte| decoder.feed(byte))
+        .collect()
+}
+
+create_test!(test_scancode_set1, {
+    test_eq!(
+        decode(ScancodeSet::Set1, &[0x1E, 0x9E, 0xE0, 0x48, 0xE0, 0xC8]),
+        [
+            (KeyCode::A, true),
+            (KeyCode::A, false),
+            (KeyCode::ArrowUp, true),
+            (KeyCode::ArrowUp, false)
+        ]
+    );
+    // Fake shifts around Print Screen are dropped, Pause only reports the press
+    test_eq!(
+        decode(
+            ScancodeSet::Set1,
+            &[0xE0, 0x2A, 0xE0, 0x37, 0xE1, 0x1D, 0x45, 0xE1, 0x9D, 0xC5]
+        ),
+        [(KeyCode::PrintScreen, true), (KeyCode::Pause, true)]
+    );
+    Ok(())
+});
+
+create_test!(test_scancode_set2, {
+    test_eq!(
+        decode(
+            ScancodeSet::Set2,
+            &[0x1C, 0xF0, 0x1C, 0xE0, 0x75, 0xE0, 0xF0, 0x75]
+        ),
+        [
+            (KeyCode::A, true),
+            (KeyCode::A, false),
+            (KeyCode::ArrowUp, true),
+            (KeyCode::ArrowUp, false)
+        ]
+    );
+    // ACKs and the self test result are not scancodes
+    test_eq!(
+        decode(
+            ScancodeSet::Set2,
+            &[0xFA, 0xAA, 0xE1, 0x14, 0x77, 0xE1, 0xF0, 0x14, 0xF0, 0x77]
+        ),
+        [(KeyCode::Pause, true)]
+    );
+    Ok(())
+});
+
+create_test!(test_modifier_state, {
+    let mut state = ModifierState::new();
+
+    test_true!(!state.update(KeyCode::LeftShift, true));
+    test_true!(!state.update(KeyCode::RightShift, true));
+    state.update(KeyCode::LeftShift, false);
+    test_true!(state.modifiers().shift);
+    state.update(KeyCode::RightShift, false);
+    test_true!(!state.modifiers().shift);
+
+    // Lock keys toggle on press and ask for an LED update
+    test_true!(state.update(KeyCode::CapsLock, true));
+    test_true!(!state.update(KeyCode::CapsLock, false));
+    test_true!(state.update(KeyCode::NumLock, true));
+    test_eq!(state.leds(), 0b110);
+    test_true!(state.update(KeyCode::CapsLock, true));
+    test_eq!(state.leds(), 0b010);
+    Ok(())
+});
+
+create_test!(test_keymaps, {
+    let none = Modifiers::default();
+    let shift = Modifiers {
+        shift: true,
+        ..Modifiers::default()
+    };
+    let caps_lock = Modifiers {
+        caps_lock: true,
+        ..Modifiers::default()
+    };
+    let ctrl = Modifiers {
+        ctrl: true,
+        ..Modifiers::default()
+    };
+    let alt_gr = Modifiers {
+        alt_gr: true,
+        ..Modifiers::default()
+    };
+
+    test_eq!(keymap::US.translate(KeyCode::A, &none), Some('a'));
+    test_eq!(keymap::US.translate(KeyCode::A, &shift), Some('A'));
+    test_eq!(keymap::US.translate(KeyCode::A, &caps_lock), Some('A'));
+    test_eq!(keymap::US.translate(KeyCode::Digit1, &caps_lock), Some('1'));
+    test_eq!(keymap::US.translate(KeyCode::C, &ctrl), Some('\x03'));
+    test_eq!(keymap::US.translate(KeyCode::Digit2, &shift), Some('@'));
+    test_eq!(keymap::UK.translate(KeyCode::Digit2, &shift), Some('"'));
+    test_eq!(keymap::UK.translate(KeyCode::Backslash, &none), Some('#'));
+    test_eq!(keymap::UK.translate(KeyCode::Digit4, &alt_gr), Some('€'));
+    test_true!(keymap::US.translate(KeyCode::F1, &none).is_none());
+    test_true!(keymap::US.translate(KeyCode::Keypad7, &none).is_none());
+
+    test_eq!(keymap::by_name("UK").map(|keymap| keymap.name), Some("uk"));
+    test_true!(keymap::by_name("dvorak").is_none());
+    Ok(())
+});
+
+create_test!(test_led_update, {
+    let mut leds = LedUpdate::new();
+
+    // The LED byte only goes out once the command is acknowledged
+    test_eq!(leds.request(0b100, 0), Some(0xED));
+    test_true!(leds.response(0x1E, 1).is_none());
+    test_eq!(leds.response(0xFE, 1), Some(0xED));
+    test_eq!(leds.response(0xFA, 2), Some(0b100));
+
+    // Toggles while busy are queued, only the latest one is sent
+    test_true!(leds.request(0b110, 3).is_none());
+    test_true!(leds.request(0b010, 4).is_none());
+    test_eq!(leds.response(0xFA, 5), Some(0xED));
+    test_eq!(leds.response(0xFA, 6), Some(0b010));
+    test_true!(leds.response(0xFA, 7).is_none());
+    test_true!(!leds.in_flight());
+
+    // A lost ACK does not block later updates
+    test_eq!(leds.request(0b001, 10), Some(0xED));
+    test_true!(leds.request(0b000, 20).is_none());
+    test_eq!(leds.request(0b000, 10 + LedUpdate::TIMEOUT_MS), Some(0xED));
+    test_true!(leds.in_flight());
+    leds.abort();
+    test_true!(!leds.in_flight());
+    test_true!(leds.response(0xFA, 200).is_none());
+    Ok(())
+});
@@ -0,0 +1,54 @@
+use kratos::dmesg::DMESG;
+use kratos::logger::{Filter, FilterError, DEFAULT_LEVEL};
+use log::{Level, LevelFilter};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+create_test!(test_logger_filter, {
+    let filter = Filter::parse("warn,kratos::io=debug,kratos::io::ps2=trace,kratos::pit=off")
+        .map_err(|e| alloc::format!("{}", e))?;
+
+    test_eq!(filter.level_for("kratos::allocator"), LevelFilter::Warn);
+    test_eq!(filter.level_for("kratos::io"), LevelFilter::Debug);
+    test_eq!(filter.level_for("kratos::io::serial"), LevelFilter::Debug);
+    // The longest module path decides
+    test_eq!(filter.level_for("kratos::io::ps2"), LevelFilter::Trace);
+    test_eq!(filter.level_for("kratos::pit"), LevelFilter::Off);
+    // Only whole path segments match
+    test_eq!(filter.level_for("kratos::iommu"), LevelFilter::Warn);
+    test_eq!(filter.max_level(), LevelFilter::Trace);
+    Ok(())
+});
+
+create_test!(test_logger_filter_errors, {
+    test_eq!(
+        Filter::parse(""),
+        Ok::<_, FilterError>(Filter::new(DEFAULT_LEVEL))
+    );
+    test_eq!(
+        Filter::parse("info,kratos=loud").err(),
+        Some(FilterError::InvalidLevel("loud"))
+    );
+
+    let too_many = "a=info,b=info,c=info,d=info,e=info,f=info,g=info,h=info,\
+                    i=info,j=info,k=info,l=info,m=info,n=info,o=info,p=info,q=info";
+    test_eq!(
+        Filter::parse(too_many).err(),
+        Some(FilterError::TooManyDirectives)
+    );
+    Ok(())
+});
+
+create_test!(test_logger_dmesg_keeps_every_level, {
+    // Below the default filter, the sinks don't show it but the dmesg records it
+    let sequence = DMESG.next_sequence();
+    log::trace!("Kept by the dmesg");
+
+    let entry = DMESG
+        .get(sequence)
+        .ok_or("Trace record missing from the dmesg")?;
+    test_eq!(entry.level, Level::Trace);
+    test_true!(entry.text().ends_with(": Kept by the dmesg"));
+    Ok(())
+});
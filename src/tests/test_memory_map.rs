@@ -0,0 +1,123 @@
+use alloc::vec::Vec;
+use kratos::boot_info::{BootInfo, MemoryRegion};
+use kratos::memory_map::{MemoryMap, MemoryRegionKind};
+use kratos::multiboot2::MULTIBOOT2_BOOTLOADER_MAGIC;
+
+use crate::tests::test_boot_info::{finish_multiboot2_info, push_tag};
+use crate::tests::TestCase;
+use crate::{create_test, test_eq};
+
+fn region(addr: u64, len: u64, kind: MemoryRegionKind) -> MemoryRegion {
+    MemoryRegion { addr, len, kind }
+}
+
+create_test!(test_memory_map_sanitize, {
+    use MemoryRegionKind::*;
+
+    // Unsorted, an ACPI table overlapping available memory and two touching available entries
+    let regions = [
+        region(0x100000, 0x100000, Available),
+        region(0x0, 0x9FC00, Available),
+        region(0x180000, 0x1000, AcpiNvs),
+        region(0x200000, 0x100000, Available),
+        region(0x9F000, 0x1000, Reserved),
+    ];
+    // Kernel image
+    let reserved = [(0x200000, 0x280000)];
+
+    let memory_map = MemoryMap::sanitize(&regions, &reserved);
+    let expected = [
+        region(0x0, 0x9F000, Available),
+        region(0x9F000, 0x1000, Reserved),
+        region(0x100000, 0x80000, Available),
+        region(0x180000, 0x1000, AcpiNvs),
+        region(0x181000, 0x7F000, Available),
+        region(0x200000, 0x80000, Reserved),
+        region(0x280000, 0x80000, Available),
+    ];
+
+    test_eq!(memory_map.regions(), &expected[..]);
+    test_eq!(
+        memory_map.total(Available),
+        0x9F000 + 0x80000 + 0x7F000 + 0x80000
+    );
+    test_eq!(memory_map.total(Reserved), 0x81000);
+    test_eq!(memory_map.total(Bad), 0);
+    Ok(())
+});
+
+// Available memory below 640 KiB and from 1 MiB to 128 MiB
+fn mmap_tag() -> Vec<u8> {
+    let mut mmap = Vec::new();
+    mmap.extend_from_slice(&24u32.to_le_bytes());
+    mmap.extend_from_slice(&0u32.to_le_bytes());
+    for (addr, len) in [(0u64, 0x9FC00u64), (0x100000, 0x7F00000)] {
+        mmap.extend_from_slice(&addr.to_le_bytes());
+        mmap.extend_from_slice(&len.to_le_bytes());
+        mmap.extend_from_slice(&1u32.to_le_bytes());
+        mmap.extend_from_slice(&0u32.to_le_bytes());
+    }
+    mmap
+}
+
+create_test!(test_memory_map_elf_sections, {
+    use MemoryRegionKind::*;
+
+    let mut info = Vec::new();
+    info.extend_from_slice(&[0; 8]);
+
+    push_tag(&mut info, 6, &mmap_tag());
+
+    // The null section, `.debug_info` which isn't loaded and `.symtab` loaded by the boot loader
+    let mut sections = Vec::new();
+    for value in [3u32, 40, 0] {
+        sections.extend_from_slice(&value.to_le_bytes());
+    }
+    for (addr, size) in [(0u32, 0u32), (0, 0x400000), (0x7000000, 0x1000)] {
+        for value in [0, 1, 0, addr, 0x1000, size, 0, 0, 1, 0] {
+            sections.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    push_tag(&mut info, 9, &sections);
+
+    let info = finish_multiboot2_info(info);
+    let boot_info = unsafe { BootInfo::new(MULTIBOOT2_BOOTLOADER_MAGIC, info) }
+        .map_err(|e| alloc::format!("{}", e))?;
+    let memory_map = unsafe { MemoryMap::new(&boot_info) };
+
+    let kind_at = |addr: u64| {
+        memory_map
+            .regions()
+            .iter()
+            .find(|region| region.addr <= addr && addr < region.addr + region.len)
+            .map(|region| region.kind)
+    };
+    test_eq!(kind_at(0x1000), Some(Available));
+    test_eq!(kind_at(0x7000000), Some(Reserved));
+    test_eq!(kind_at(0x7001000), Some(Available));
+    Ok(())
+});
+
+create_test!(test_memory_map_malformed_elf_sections, {
+    let mut info = Vec::new();
+    info.extend_from_slice(&[0; 8]);
+    push_tag(&mut info, 6, &mmap_tag());
+
+    // 0x1000000 entries of 0x100 bytes, the table size doesn't fit in 32 bits
+    let mut sections = Vec::new();
+    for value in [0x1000000u32, 0x100, 0] {
+        sections.extend_from_slice(&value.to_le_bytes());
+    }
+    push_tag(&mut info, 9, &sections);
+
+    let info = finish_multiboot2_info(info);
+    let boot_info = unsafe { BootInfo::new(MULTIBOOT2_BOOTLOADER_MAGIC, info) }
+        .map_err(|e| alloc::format!("{}", e))?;
+    let memory_map = unsafe { MemoryMap::new(&boot_info) };
+
+    test_eq!(
+        memory_map.regions().first().map(|region| region.kind),
+        Some(MemoryRegionKind::Available)
+    );
+    Ok(())
+});
@@ -0,0 +1,60 @@
+use kratos::io::mouse::{Buttons, MouseEvent, MouseKind, PacketDecoder, VgaPointer};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+fn decode(kind: MouseKind, bytes: &[u8]) -> alloc::vec::Vec<MouseEvent> {
+    let mut decoder = PacketDecoder::new(kind);
+    bytes
+        .iter()
+        .filter_map(|&byte| decoder.feed(byte))
+        .collect()
+}
+
+create_test!(test_mouse_packets, {
+    // Left button held, moving right by 5 and down by 3
+    test_eq!(
+        decode(MouseKind::Standard, &[0x29, 0x05, 0xFD]),
+        [MouseEvent {
+            dx: 5,
+            dy: -3,
+            wheel: 0,
+            buttons: Buttons {
+                left: true,
+                ..Buttons::default()
+            },
+        }]
+    );
+
+    // The fourth byte is the wheel, scrolled up by one
+    let events = decode(MouseKind::IntelliMouse, &[0x08, 0x00, 0x00, 0x0F]);
+    test_eq!(events.len(), 1);
+    test_eq!(events[0].wheel, -1);
+    Ok(())
+});
+
+create_test!(test_mouse_resync, {
+    // A lost first byte leaves two stray bytes without the always one bit before the next packet
+    let events = decode(MouseKind::Standard, &[0x01, 0x02, 0x0A, 0x01, 0x01]);
+    test_eq!(events.len(), 1);
+    test_true!(events[0].buttons.right);
+
+    // Packets with overflowed counters are dropped
+    test_true!(decode(MouseKind::Standard, &[0xC8, 0xFF, 0xFF]).is_empty());
+    Ok(())
+});
+
+create_test!(test_vga_pointer, {
+    let mut pointer = VgaPointer::new();
+    test_eq!(pointer.position(), (12, 40));
+
+    let event = |dx, dy| MouseEvent {
+        dx,
+        dy,
+        wheel: 0,
+        buttons: Buttons::default(),
+    };
+    test_eq!(pointer.apply(&event(16, 16)), (11, 42));
+    test_eq!(pointer.apply(&event(-1000, -1000)), (24, 0));
+    Ok(())
+});
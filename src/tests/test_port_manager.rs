@@ -0,0 +1,81 @@
+use kratos::io::port_manager::{PortError, PortManager};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+// The bitmap is too large for the kernel stack, every test gets its own manager
+static RANGE_MANAGER: PortManager = PortManager::new();
+static LIMITS_MANAGER: PortManager = PortManager::new();
+static HANDLE_MANAGER: PortManager = PortManager::new();
+
+create_test!(test_port_range_reservation, {
+    let port_manager = &RANGE_MANAGER;
+
+    let range = port_manager
+        .request_range(0xCF8, 8, "pci")
+        .map_err(|_| "Failed to get range")?;
+    test_eq!(range.start(), 0xCF8);
+    test_eq!(range.len(), 8);
+    test_eq!(range.port::<u32>(4).get_addr(), 0xCFC);
+    test_eq!(port_manager.owner(0xCFF), Some("pci"));
+    test_true!(port_manager.owner(0xD00).is_none());
+
+    // Overlapping requests report the port and who holds it
+    match port_manager.request_range(0xCF0, 9, "other") {
+        Err(PortError::Conflict { port, owner }) => {
+            test_eq!(port, 0xCF8);
+            test_eq!(owner, "pci");
+        }
+        _ => return Err("Overlapping range was granted".into()),
+    }
+
+    // Dropping the range releases its ports
+    drop(range);
+    test_true!(port_manager.owner(0xCF8).is_none());
+    test_true!(port_manager.request_range(0xCF8, 8, "other").is_ok());
+    test_eq!(port_manager.reservations().count(), 0);
+    Ok(())
+});
+
+create_test!(test_port_range_limits, {
+    let port_manager = &LIMITS_MANAGER;
+
+    test_true!(matches!(
+        port_manager.request_range(0x80, 0, "empty"),
+        Err(PortError::Empty)
+    ));
+    test_true!(matches!(
+        port_manager.request_range(0xFFFE, 4, "wrapping"),
+        Err(PortError::OutOfRange { .. })
+    ));
+
+    let range = port_manager
+        .request_range(0xFFFF, 1, "last")
+        .map_err(|_| "Failed to get last port")?;
+    test_eq!(range.write_only_port::<u8>(0).get_addr(), 0xFFFF);
+    test_eq!(port_manager.reservations().count(), 1);
+    Ok(())
+});
+
+create_test!(test_port_range_handles, {
+    let port_manager = &HANDLE_MANAGER;
+
+    let range = port_manager
+        .request_range(0x3F8, 8, "serial")
+        .map_err(|_| "Failed to get range")?;
+    let data = range.port::<u8>(0);
+    let line_status = range.read_only_port::<u8>(5);
+
+    // The ports taken from the range keep it reserved
+    drop(range);
+    test_eq!(port_manager.owner(0x3FF), Some("serial"));
+    test_true!(port_manager.request_range(0x3F8, 1, "other").is_err());
+
+    drop(data);
+    test_eq!(port_manager.owner(0x3F8), Some("serial"));
+
+    drop(line_status);
+    test_true!(port_manager.owner(0x3F8).is_none());
+    test_eq!(port_manager.reservations().count(), 0);
+    Ok(())
+});
@@ -0,0 +1,41 @@
+use kratos::util::ring_buffer::RingBuffer;
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+create_test!(test_ring_buffer_order, {
+    let buffer: RingBuffer<u8, 4> = RingBuffer::new();
+    test_true!(buffer.is_empty());
+
+    for value in 1..=3 {
+        test_true!(buffer.push(value));
+    }
+    test_eq!(buffer.len(), 3);
+    test_eq!(buffer.pop(), Some(1));
+    test_eq!(buffer.pop(), Some(2));
+
+    // Wraps around the end of the storage
+    for value in 4..=6 {
+        test_true!(buffer.push(value));
+    }
+    test_eq!(buffer.pop(), Some(3));
+    test_eq!(buffer.pop(), Some(4));
+    test_eq!(buffer.pop(), Some(5));
+    test_eq!(buffer.pop(), Some(6));
+    test_true!(buffer.pop().is_none());
+    Ok(())
+});
+
+create_test!(test_ring_buffer_overflow, {
+    let buffer: RingBuffer<u8, 2> = RingBuffer::new();
+
+    test_true!(buffer.push(1));
+    test_true!(buffer.push(2));
+    test_true!(!buffer.push(3));
+    test_eq!(buffer.dropped(), 1);
+    test_eq!(buffer.pop(), Some(1));
+
+    buffer.clear();
+    test_true!(buffer.is_empty());
+    Ok(())
+});
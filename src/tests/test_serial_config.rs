@@ -0,0 +1,73 @@
+use kratos::io::serial_config::{
+    parse_console, ComPort, DataBits, FifoThreshold, Parity, SerialConfig, SerialConfigError,
+    StopBits,
+};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+create_test!(test_serial_config_parse, {
+    let config = SerialConfig::parse("115200,7o2,fifo8").map_err(|_| "Failed to parse config")?;
+    test_eq!(config.baud_rate, 115200);
+    test_eq!(config.data_bits, DataBits::Seven);
+    test_eq!(config.parity, Parity::Odd);
+    test_eq!(config.stop_bits, StopBits::Two);
+    test_eq!(config.fifo_threshold, FifoThreshold::Bytes8);
+    test_eq!(config.divisor().ok(), Some(1));
+    test_eq!(config.line_control(), 0b0000_1110);
+    test_eq!(config.fifo_control(), 0b1000_0000);
+
+    // Omitted parts keep their defaults
+    let config = SerialConfig::parse("").map_err(|_| "Failed to parse config")?;
+    test_eq!(config, SerialConfig::default());
+    test_eq!(config.divisor().ok(), Some(3));
+    test_eq!(config.line_control(), 0x03);
+    Ok(())
+});
+
+create_test!(test_serial_config_errors, {
+    test_eq!(
+        SerialConfig::parse("1000").err(),
+        Some(SerialConfigError::InvalidBaudRate(1000))
+    );
+    test_eq!(
+        SerialConfig::parse("abc").err(),
+        Some(SerialConfigError::MalformedBaudRate("abc"))
+    );
+    test_eq!(
+        alloc::format!("{}", SerialConfigError::MalformedBaudRate("abc")),
+        "Baud rate \"abc\" is not a number"
+    );
+    test_eq!(
+        SerialConfig::parse("9600,9n1").err(),
+        Some(SerialConfigError::InvalidFormat)
+    );
+    test_eq!(
+        SerialConfig::parse("9600,8n1,fifo2").err(),
+        Some(SerialConfigError::InvalidFifoThreshold)
+    );
+    test_eq!(
+        SerialConfig::parse("9600,8n1,fifo1,x").err(),
+        Some(SerialConfigError::InvalidFormat)
+    );
+    Ok(())
+});
+
+create_test!(test_serial_console_option, {
+    let (port, config) = parse_console("COM2,57600,8e1").map_err(|_| "Failed to parse")?;
+    test_eq!(port, ComPort::Com2);
+    test_eq!(port.base(), 0x2F8);
+    test_eq!(port.irq(), 3);
+    test_eq!(config.baud_rate, 57600);
+    test_eq!(config.parity, Parity::Even);
+
+    let (port, config) = parse_console("com4").map_err(|_| "Failed to parse")?;
+    test_eq!(port, ComPort::Com4);
+    test_eq!(config, SerialConfig::default());
+
+    test_true!(parse_console("com5").is_err());
+    test_true!(parse_console("ttyS0").is_err());
+    // Four bytes, but not four characters
+    test_true!(parse_console("a€").is_err());
+    Ok(())
+});
@@ -0,0 +1,139 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use kratos::register_command;
+use kratos::shell::line_editor::{EditKey, LineEditor, LINE_WIDTH};
+use kratos::shell::{CommandError, Shell};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+const NAMES: [&str; 3] = ["date", "gdt", "gnu"];
+
+fn complete(prefix: &str) -> Vec<&'static str> {
+    NAMES
+        .iter()
+        .copied()
+        .filter(|name| name.starts_with(prefix))
+        .collect()
+}
+
+// Feeds the keys, returns the lines entered
+fn type_keys(editor: &mut LineEditor, keys: &[EditKey]) -> Vec<String> {
+    let mut out = String::new();
+    keys.iter()
+        .filter_map(|&key| editor.handle(key, &mut out, &complete).ok().flatten())
+        .collect()
+}
+
+fn type_text(text: &str) -> Vec<EditKey> {
+    text.chars().map(EditKey::Char).collect()
+}
+
+create_test!(test_shell_line_editing, {
+    let mut editor = LineEditor::new("> ");
+    let mut keys = type_text("dte");
+    keys.extend([EditKey::Left, EditKey::Left]);
+    keys.extend(type_text("a"));
+    keys.extend([
+        EditKey::End,
+        EditKey::Backspace,
+        EditKey::Home,
+        EditKey::Delete,
+    ]);
+    keys.push(EditKey::Enter);
+
+    test_eq!(type_keys(&mut editor, &keys), ["at"]);
+    Ok(())
+});
+
+create_test!(test_shell_history, {
+    let mut editor = LineEditor::new("> ");
+    let mut keys = type_text("gdt");
+    keys.push(EditKey::Enter);
+    keys.extend(type_text("date"));
+    keys.push(EditKey::Enter);
+    // Repeated lines are kept once
+    keys.extend(type_text("date"));
+    keys.push(EditKey::Enter);
+    test_eq!(type_keys(&mut editor, &keys).len(), 3);
+    test_eq!(editor.history().collect::<Vec<_>>(), ["gdt", "date"]);
+
+    // Browsing keeps the line being typed
+    let mut keys = type_text("x");
+    keys.extend([EditKey::Up, EditKey::Up, EditKey::Up, EditKey::Enter]);
+    test_eq!(type_keys(&mut editor, &keys), ["gdt"]);
+    let keys = [EditKey::Up, EditKey::Down, EditKey::Down, EditKey::Enter];
+    test_eq!(type_keys(&mut editor, &keys), [""]);
+    Ok(())
+});
+
+create_test!(test_shell_completion, {
+    let mut editor = LineEditor::new("> ");
+    // A single candidate is completed with a space after it
+    let mut keys = type_text("d");
+    keys.extend([EditKey::Tab, EditKey::Enter]);
+    test_eq!(type_keys(&mut editor, &keys), ["date "]);
+
+    // Several candidates are completed to their common prefix
+    let mut keys = type_text("g");
+    keys.extend([EditKey::Tab, EditKey::Enter]);
+    test_eq!(type_keys(&mut editor, &keys), ["g"]);
+    let mut keys = type_text("gd");
+    keys.extend([EditKey::Tab, EditKey::Enter]);
+    test_eq!(type_keys(&mut editor, &keys), ["gdt "]);
+    Ok(())
+});
+
+create_test!(test_shell_line_width, {
+    let mut editor = LineEditor::new("> ");
+    test_eq!(editor.max_len(), LINE_WIDTH - 3);
+
+    // Characters past the end of the row are dropped, so the line never wraps
+    let mut keys = type_text(&"x".repeat(LINE_WIDTH));
+    keys.push(EditKey::Enter);
+    test_eq!(type_keys(&mut editor, &keys), ["x".repeat(LINE_WIDTH - 3)]);
+
+    // Completions that would not fit are left out
+    let mut keys = type_text(&"x".repeat(LINE_WIDTH - 5));
+    keys.extend([
+        EditKey::Home,
+        EditKey::Char('d'),
+        EditKey::Tab,
+        EditKey::Enter,
+    ]);
+    let expected = alloc::format!("d{}", "x".repeat(LINE_WIDTH - 5));
+    test_eq!(type_keys(&mut editor, &keys), [expected.as_str()]);
+    Ok(())
+});
+
+create_test!(test_shell_execute, {
+    let mut shell = Shell::new();
+    register_command!(shell, "echo", "Prints its arguments", |out, args| {
+        writeln!(out, "{}", args.join(" "))?;
+        Ok(())
+    });
+    register_command!(shell, "fail", "Always fails", |_, _| Err(
+        CommandError::Usage("fail")
+    ));
+
+    let mut out = String::new();
+    shell.execute("  echo hello   world ", &mut out);
+    test_eq!(out, "hello world\n");
+
+    let mut out = String::new();
+    shell.execute("fail", &mut out);
+    test_eq!(out, "fail: Usage: fail\n");
+
+    let mut out = String::new();
+    shell.execute("missing", &mut out);
+    test_true!(out.starts_with("missing: Unknown command"));
+
+    let mut out = String::new();
+    shell.execute("help", &mut out);
+    test_true!(out.contains("echo"));
+
+    // `help` is built in, but completes like the registered commands
+    test_eq!(shell.completions("h"), ["help"]);
+    test_eq!(shell.completions(""), ["echo", "fail", "help"]);
+    Ok(())
+});
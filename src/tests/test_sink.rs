@@ -0,0 +1,82 @@
+use alloc::boxed::Box;
+use core::fmt;
+use kratos::io::sink::{ConsoleSink, MemorySink, RegistryFull, SinkRegistry, MAX_SINKS};
+use log::{Level, LevelFilter};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+// Fails every write
+struct BrokenSink;
+
+impl fmt::Write for BrokenSink {
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        Err(fmt::Error)
+    }
+}
+
+impl ConsoleSink for BrokenSink {
+    fn name(&self) -> &'static str {
+        "broken"
+    }
+}
+
+fn memory_text(registry: &mut SinkRegistry) -> alloc::string::String {
+    registry
+        .get_mut::<MemorySink>()
+        .map(|memory| core::mem::take(&mut memory.text))
+        .unwrap_or_default()
+}
+
+create_test!(test_sink_print, {
+    let mut registry = SinkRegistry::new();
+    let broken = registry
+        .register(Box::leak(Box::new(BrokenSink)))
+        .map_err(|e| alloc::format!("{}", e))?;
+    let memory = registry
+        .register(Box::leak(Box::<MemorySink>::default()))
+        .map_err(|e| alloc::format!("{}", e))?;
+
+    // The failing sink does not keep the others from their output
+    registry.print(format_args!("{} {}", "hello", 1));
+    test_eq!(memory_text(&mut registry), "hello 1");
+    test_eq!(registry.info(broken).map(|info| info.errors), Some(1));
+    test_eq!(registry.find("memory"), Some(memory));
+
+    registry.set_enabled(memory, false);
+    registry.print(format_args!("hidden"));
+    test_eq!(memory_text(&mut registry), "");
+
+    test_true!(registry.unregister(memory).is_some());
+    test_true!(registry.get_mut::<MemorySink>().is_none());
+    test_eq!(registry.sinks().count(), 1);
+    Ok(())
+});
+
+create_test!(test_sink_log_level, {
+    let mut registry = SinkRegistry::new();
+    let memory = registry
+        .register(Box::leak(Box::<MemorySink>::default()))
+        .map_err(|e| alloc::format!("{}", e))?;
+    registry.set_level(memory, LevelFilter::Warn);
+
+    registry.log(Level::Info, |out| out.write_str("info"));
+    registry.log(Level::Error, |out| out.write_str("error"));
+    test_eq!(memory_text(&mut registry), "error");
+    Ok(())
+});
+
+create_test!(test_sink_registry_full, {
+    let mut registry = SinkRegistry::new();
+    for _ in 0..MAX_SINKS {
+        registry
+            .register(Box::leak(Box::new(BrokenSink)))
+            .map_err(|e| alloc::format!("{}", e))?;
+    }
+
+    test_eq!(
+        registry.register(Box::leak(Box::new(BrokenSink))).err(),
+        Some(RegistryFull)
+    );
+    Ok(())
+});
@@ -0,0 +1,24 @@
+use alloc::format;
+use kratos::symbols::Demangle;
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq};
+
+create_test!(test_demangle, {
+    test_eq!(
+        format!(
+            "{}",
+            Demangle("_ZN6kratos9allocator24insert_segment_into_list17h0123456789abcdefE")
+        ),
+        "kratos::allocator::insert_segment_into_list"
+    );
+    test_eq!(
+        format!(
+            "{}",
+            Demangle("_ZN62_$LT$kratos..io..vga..Terminal$u20$as$u20$core..fmt..Write$GT$9write_str17h0123456789abcdefE")
+        ),
+        "<kratos::io::vga::Terminal as core::fmt::Write>::write_str"
+    );
+    test_eq!(format!("{}", Demangle("kernel_main")), "kernel_main");
+    Ok(())
+});
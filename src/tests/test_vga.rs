@@ -0,0 +1,152 @@
+use alloc::vec;
+use core::fmt::Write;
+use kratos::io::cp437;
+use kratos::io::vga::{Terminal, VgaColor, VGA_HEIGHT, VGA_WIDTH};
+
+use crate::tests::TestCase;
+use crate::{create_test, test_eq, test_true};
+
+fn terminal() -> Terminal {
+    let buffer = vec![0u16; VGA_WIDTH * VGA_HEIGHT].leak();
+    let mut terminal =
+        unsafe { Terminal::with_buffer(buffer.as_mut_ptr(), VgaColor::LightGrey, VgaColor::Black) };
+    terminal.clear();
+    terminal
+}
+
+create_test!(test_vga_control_characters, {
+    let mut terminal = terminal();
+
+    terminal.write_text(b"a\tb");
+    test_eq!(terminal.read_character(0, 8).0, b'b');
+    test_eq!(terminal.position(), (0, 9));
+
+    terminal.write_text(b"\rc");
+    test_eq!(terminal.read_character(0, 0).0, b'c');
+
+    terminal.write_text(b"\x08 \x08");
+    test_eq!(terminal.read_character(0, 0).0, b' ');
+    test_eq!(terminal.position(), (0, 0));
+
+    Ok(())
+});
+
+create_test!(test_vga_scrolling, {
+    let mut terminal = terminal();
+
+    terminal.write_text(b"first\n");
+    terminal.set_position(VGA_HEIGHT - 1, 0);
+    terminal.set_color(VgaColor::White, VgaColor::Blue);
+    terminal.write_text(b"last\n");
+
+    test_eq!(terminal.read_character(0, 0).0, b' ');
+    test_eq!(terminal.read_character(VGA_HEIGHT - 2, 0), (b'l', 0x1F));
+    test_eq!(terminal.read_character(VGA_HEIGHT - 1, 0), (b' ', 0x1F));
+    test_eq!(terminal.position(), (VGA_HEIGHT - 1, 0));
+
+    // Long lines wrap onto the next row
+    terminal.write_text(&[b'x'; VGA_WIDTH + 1]);
+    test_eq!(terminal.read_character(VGA_HEIGHT - 1, 0).0, b'x');
+    test_eq!(terminal.position(), (VGA_HEIGHT - 1, 1));
+    Ok(())
+});
+
+create_test!(test_vga_ansi_colors, {
+    let mut terminal = terminal();
+
+    write!(terminal, "\x1b[31mr\x1b[1;44mb\x1b[0md").map_err(|_| "Failed to write")?;
+    test_eq!(terminal.read_character(0, 0), (b'r', 0x04));
+    test_eq!(terminal.read_character(0, 1), (b'b', 0x1C));
+    test_eq!(terminal.read_character(0, 2), (b'd', 0x07));
+
+    // Unsupported and private sequences are swallowed
+    write!(terminal, "\x1b[?25l\x1b[5q\x1b(B").map_err(|_| "Failed to write")?;
+    test_eq!(terminal.position(), (0, 3));
+    Ok(())
+});
+
+create_test!(test_vga_ansi_cursor_and_erase, {
+    let mut terminal = terminal();
+
+    write!(terminal, "\x1b[3;5Hx\x1b[2Ay\x1b[10D").map_err(|_| "Failed to write")?;
+    test_eq!(terminal.read_character(2, 4).0, b'x');
+    test_eq!(terminal.read_character(0, 5).0, b'y');
+    test_eq!(terminal.position(), (0, 0));
+
+    write!(terminal, "abcdef\x1b[3G\x1b[K").map_err(|_| "Failed to write")?;
+    test_eq!(terminal.read_character(0, 1).0, b'b');
+    test_eq!(terminal.read_character(0, 2).0, b' ');
+    test_eq!(terminal.read_character(2, 4).0, b'x');
+
+    write!(terminal, "\x1b[2J").map_err(|_| "Failed to write")?;
+    test_eq!(terminal.read_character(0, 0).0, b' ');
+    test_eq!(terminal.read_character(2, 4).0, b' ');
+    Ok(())
+});
+
+create_test!(test_vga_scrollback, {
+    let mut terminal = terminal();
+    terminal.enable_scrollback(2);
+
+    // Lines 0 to 2 scroll off, only the last two are kept
+    for line in 0..VGA_HEIGHT + 2 {
+        write!(terminal, "{}\n", line % 10).map_err(|_| "Failed to write")?;
+    }
+    test_eq!(terminal.history_len(), 2);
+    test_eq!(terminal.read_character(0, 0).0, b'3');
+
+    terminal.scroll_view_up(10);
+    test_eq!(terminal.view_offset(), 2);
+    test_eq!(terminal.read_character(0, 0).0, b'1');
+    test_eq!(terminal.read_character(2, 0).0, b'3');
+
+    terminal.scroll_view_down(1);
+    test_eq!(terminal.read_character(0, 0).0, b'2');
+
+    // Output returns to the live screen
+    write!(terminal, "x").map_err(|_| "Failed to write")?;
+    test_eq!(terminal.view_offset(), 0);
+    test_eq!(terminal.read_character(0, 0).0, b'3');
+    test_eq!(terminal.read_character(VGA_HEIGHT - 1, 0).0, b'x');
+    Ok(())
+});
+
+create_test!(test_vga_cp437, {
+    let mut terminal = terminal();
+
+    write!(terminal, "25°C µs é╔═╗ ☃").map_err(|_| "Failed to write")?;
+    let expected = [
+        b'2', b'5', 0xF8, b'C', b' ', 0xE6, b's', b' ', 0x82, 0xC9, 0xCD, 0xBB, b' ', 0xFE,
+    ];
+    for (column, &character) in expected.iter().enumerate() {
+        test_eq!(terminal.read_character(0, column).0, character);
+    }
+
+    test_eq!(cp437::from_char('μ'), Some(0xE6));
+    test_true!(cp437::from_char('☃').is_none());
+    for byte in 0x20..=0xFF {
+        let character = cp437::to_char(byte).ok_or("Unmapped printable character")?;
+        test_eq!(cp437::from_char(character), Some(byte));
+    }
+    Ok(())
+});
+
+create_test!(test_vga_mouse_pointer, {
+    let mut terminal = terminal();
+    terminal.write_text(b"ab");
+
+    terminal.show_pointer(0, 1);
+    test_eq!(terminal.read_character(0, 1), (b'b', 0x70));
+
+    // Text written under the pointer keeps it drawn on top
+    terminal.write_text(b"\rxy");
+    test_eq!(terminal.read_character(0, 1), (b'y', 0x70));
+
+    terminal.show_pointer(0, 0);
+    test_eq!(terminal.read_character(0, 0), (b'x', 0x70));
+    test_eq!(terminal.read_character(0, 1), (b'y', 0x07));
+
+    terminal.hide_pointer();
+    test_eq!(terminal.read_character(0, 0), (b'x', 0x07));
+    Ok(())
+});
@@ -0,0 +1,235 @@
+use core::fmt;
+use core::time::Duration;
+use thiserror_no_std::Error;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+// Days from 0000-03-01 to 1970-01-01
+const UNIX_EPOCH_DAYS: i64 = 719_468;
+// Days in a 400 year cycle of the Gregorian calendar
+const DAYS_PER_ERA: i64 = 146_097;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum DateTimeError {
+    #[error("Year {0} is outside 1-9999")]
+    InvalidYear(u16),
+    #[error("Month {0} is outside 1-12")]
+    InvalidMonth(u8),
+    #[error("Day {day} does not exist in {year:04}-{month:02}")]
+    InvalidDay { year: u16, month: u8, day: u8 },
+    #[error("Time {0:02}:{1:02}:{2:02} does not exist")]
+    InvalidTime(u8, u8, u8),
+    #[error("Timestamp {0} is outside the years 1-9999")]
+    TimestampOutOfRange(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    Sunday,
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+}
+
+impl Weekday {
+    const ALL: [Weekday; 7] = [
+        Weekday::Sunday,
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+    ];
+
+    /// Days since Sunday.
+    pub fn days_from_sunday(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// A Gregorian calendar date and time of the years 1 to 9999, to the second.
+///
+/// Fields are only reachable through the constructors, so every value is a valid date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    // Field order makes the derived ordering chronological
+    year: u16,
+    month: u8,
+    day: u8,
+    hours: u8,
+    minutes: u8,
+    seconds: u8,
+}
+
+impl DateTime {
+    pub const MIN: DateTime = DateTime {
+        year: 1,
+        month: 1,
+        day: 1,
+        hours: 0,
+        minutes: 0,
+        seconds: 0,
+    };
+    pub const MAX: DateTime = DateTime {
+        year: 9999,
+        month: 12,
+        day: 31,
+        hours: 23,
+        minutes: 59,
+        seconds: 59,
+    };
+
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hours: u8,
+        minutes: u8,
+        seconds: u8,
+    ) -> Result<DateTime, DateTimeError> {
+        if !(1..=9999).contains(&year) {
+            return Err(DateTimeError::InvalidYear(year));
+        }
+        if !(1..=12).contains(&month) {
+            return Err(DateTimeError::InvalidMonth(month));
+        }
+        if day == 0 || day > days_in_month(year, month) {
+            return Err(DateTimeError::InvalidDay { year, month, day });
+        }
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return Err(DateTimeError::InvalidTime(hours, minutes, seconds));
+        }
+
+        Ok(DateTime {
+            year,
+            month,
+            day,
+            hours,
+            minutes,
+            seconds,
+        })
+    }
+
+    /// Seconds since 1970-01-01 00:00:00 become a date, earlier times are negative.
+    pub fn from_unix_timestamp(timestamp: i64) -> Result<DateTime, DateTimeError> {
+        if !(DateTime::MIN.unix_timestamp()..=DateTime::MAX.unix_timestamp()).contains(&timestamp) {
+            return Err(DateTimeError::TimestampOutOfRange(timestamp));
+        }
+
+        let days = timestamp.div_euclid(SECONDS_PER_DAY);
+        let seconds = timestamp.rem_euclid(SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+
+        Ok(DateTime {
+            year,
+            month,
+            day,
+            hours: (seconds / 3600) as u8,
+            minutes: (seconds / 60 % 60) as u8,
+            seconds: (seconds % 60) as u8,
+        })
+    }
+
+    pub fn unix_timestamp(&self) -> i64 {
+        let seconds = self.hours as i64 * 3600 + self.minutes as i64 * 60 + self.seconds as i64;
+
+        days_from_civil(self.year, self.month, self.day) * SECONDS_PER_DAY + seconds
+    }
+
+    /// Moves forward by the whole seconds of `duration`, `None` past `DateTime::MAX`.
+    pub fn checked_add(&self, duration: Duration) -> Option<DateTime> {
+        let seconds = i64::try_from(duration.as_secs()).ok()?;
+        DateTime::from_unix_timestamp(self.unix_timestamp().checked_add(seconds)?).ok()
+    }
+
+    /// Moves back by the whole seconds of `duration`, `None` before `DateTime::MIN`.
+    pub fn checked_sub(&self, duration: Duration) -> Option<DateTime> {
+        let seconds = i64::try_from(duration.as_secs()).ok()?;
+        DateTime::from_unix_timestamp(self.unix_timestamp().checked_sub(seconds)?).ok()
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        let days = days_from_civil(self.year, self.month, self.day);
+        Weekday::ALL[(days + Weekday::Thursday as i64).rem_euclid(7) as usize]
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hours(&self) -> u8 {
+        self.hours
+    }
+
+    pub fn minutes(&self) -> u8 {
+        self.minutes
+    }
+
+    pub fn seconds(&self) -> u8 {
+        self.seconds
+    }
+}
+
+/// ISO 8601, `2024-07-24T12:34:56`.
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hours, self.minutes, self.seconds
+        )
+    }
+}
+
+pub fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+pub fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Counts from a year starting in March, so the leap day is the last day of the year.
+// See https://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: u16, month: u8, day: u8) -> i64 {
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * DAYS_PER_ERA + day_of_era - UNIX_EPOCH_DAYS
+}
+
+fn civil_from_days(days: i64) -> (u16, u8, u8) {
+    let days = days + UNIX_EPOCH_DAYS;
+    let era = days.div_euclid(DAYS_PER_ERA);
+    let day_of_era = days.rem_euclid(DAYS_PER_ERA);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = (month_from_march + 2) % 12 + 1;
+    let year = era * 400 + year_of_era + (month <= 2) as i64;
+
+    (year as u16, month as u8, day as u8)
+}
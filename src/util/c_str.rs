@@ -0,0 +1,16 @@
+/// Builds a `&str` out of a NUL terminated string handed over by the boot loader.
+///
+/// Invalid UTF-8 yields an empty string rather than undefined behaviour.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn from_raw(ptr: *const u8) -> &'static str {
+    if ptr.is_null() {
+        return "";
+    }
+
+    let mut len = 0;
+    while *ptr.add(len) != 0 {
+        len += 1;
+    }
+
+    core::str::from_utf8(core::slice::from_raw_parts(ptr, len)).unwrap_or("")
+}
@@ -1 +1,3 @@
 pub mod bit_manipulation;
+pub mod c_str;
+pub mod ring_buffer;
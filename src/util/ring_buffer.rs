@@ -0,0 +1,83 @@
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Fixed size queue for a single producer, such as an interrupt handler, and a single consumer.
+///
+/// Pushing into a full buffer drops the new element and counts it.
+pub struct RingBuffer<T: Copy, const N: usize> {
+    buffer: UnsafeCell<MaybeUninit<[T; N]>>,
+    // Both only ever increase, the slot is the index modulo N
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+unsafe impl<T: Copy + Send, const N: usize> Sync for RingBuffer<T, N> {}
+
+impl<T: Copy, const N: usize> RingBuffer<T, N> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> RingBuffer<T, N> {
+        RingBuffer {
+            buffer: UnsafeCell::new(MaybeUninit::uninit()),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Appends `value`, returns false if the buffer is full.
+    pub fn push(&self, value: T) -> bool {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) == N {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        unsafe { self.slot(tail).write(value) };
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+        true
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let value = unsafe { self.slot(head).read() };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+
+        Some(value)
+    }
+
+    /// Discards every queued element, only to be called by the consumer.
+    pub fn clear(&self) {
+        self.head
+            .store(self.tail.load(Ordering::Acquire), Ordering::Release);
+    }
+
+    pub fn len(&self) -> usize {
+        self.tail
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of elements dropped because the buffer was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn slot(&self, index: usize) -> *mut T {
+        unsafe { (self.buffer.get() as *mut T).add(index % N) }
+    }
+}